@@ -6,7 +6,7 @@
 //!   curl -H "ID: test-device" http://localhost:3000/api/display
 
 use axum::{routing::get, Json, Router};
-use trmnl::{DeviceInfo, DisplayResponse, LogEntry, LogResponse, SetupResponse};
+use trmnl::{DeviceInfo, DisplayResponse, LogPayload, LogResponse, SetupResponse};
 
 /// GET /api/setup - Device registration
 async fn setup(device: DeviceInfo) -> Json<SetupResponse> {
@@ -48,16 +48,18 @@ async fn display(device: DeviceInfo) -> Json<DisplayResponse> {
 }
 
 /// POST /api/log - Device telemetry
-async fn log(device: DeviceInfo, Json(entry): Json<LogEntry>) -> Json<LogResponse> {
-    println!(
-        "Log from {}: {:?} (battery: {:?}V)",
-        device.mac_address,
-        entry.log_message,
-        entry
-            .device_status_stamp
-            .as_ref()
-            .and_then(|s| s.battery_voltage)
-    );
+async fn log(device: DeviceInfo, Json(payload): Json<LogPayload>) -> Json<LogResponse> {
+    for entry in &payload.logs {
+        println!(
+            "Log from {}: {:?} (battery: {:?}V)",
+            device.mac_address,
+            entry.log_message,
+            entry
+                .device_status_stamp
+                .as_ref()
+                .and_then(|s| s.battery_voltage)
+        );
+    }
 
     Json(LogResponse::ok())
 }
@@ -9,16 +9,10 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use axum::{
-    body::Body,
-    extract::State,
-    http::{header, StatusCode},
-    response::Response,
-    routing::get,
-    Json, Router,
-};
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
 use tokio::sync::RwLock;
 use trmnl::{
+    axum_ext::serve_image_dir,
     render::{render_html_to_png, timestamped_filename, RenderConfig},
     DeviceInfo, DisplayResponse,
 };
@@ -160,30 +154,6 @@ async fn display(
     ))
 }
 
-/// GET /images/:filename - Serve images
-async fn serve_image(
-    State(state): State<Arc<AppState>>,
-    axum::extract::Path(filename): axum::extract::Path<String>,
-) -> Result<Response, (StatusCode, String)> {
-    // Security: prevent path traversal
-    if filename.contains("..") || filename.contains('/') {
-        return Err((StatusCode::BAD_REQUEST, "Invalid filename".to_string()));
-    }
-
-    let image_path = state.image_dir.join(&filename);
-
-    let data = tokio::fs::read(&image_path)
-        .await
-        .map_err(|_| (StatusCode::NOT_FOUND, "Image not found".to_string()))?;
-
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "image/png")
-        .header(header::CACHE_CONTROL, "no-cache")
-        .body(Body::from(data))
-        .unwrap())
-}
-
 #[tokio::main]
 async fn main() {
     println!("Starting TRMNL BYOS server with rendering on http://localhost:3000");
@@ -204,7 +174,10 @@ async fn main() {
 
     let app = Router::new()
         .route("/api/display", get(display))
-        .route("/images/{filename}", get(serve_image))
+        .route(
+            "/images/{filename}",
+            serve_image_dir(state.image_dir.clone()),
+        )
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
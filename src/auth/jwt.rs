@@ -0,0 +1,236 @@
+//! JWT validation for deployments behind an identity-aware proxy.
+
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde_json::{Map, Value};
+
+use super::AuthError;
+use crate::error::Error;
+
+/// Claims of a validated JWT.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JwtClaims(pub Map<String, Value>);
+
+impl JwtClaims {
+    /// A claim by name.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    /// The `sub` claim, if it is a string.
+    pub fn subject(&self) -> Option<&str> {
+        self.get("sub").and_then(Value::as_str)
+    }
+}
+
+/// Validates JWTs signed with HS256 (shared secret) or RS256 (public key).
+///
+/// Tokens are read from `Authorization: Bearer <token>` by default, or from
+/// a proxy-specific header set with [`with_header`](Self::with_header).
+/// `exp` is always required and checked.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::auth::JwtValidator;
+///
+/// let validator = JwtValidator::rs256_pem(include_bytes!("proxy.pem"))?
+///     .with_header("Cf-Access-Jwt-Assertion")
+///     .with_issuer("https://team.cloudflareaccess.com")
+///     .with_audience("trmnl-byos");
+///
+/// let claims = validator.validate(token)?;
+/// println!("Request from {:?}", claims.subject());
+/// ```
+#[derive(Clone)]
+pub struct JwtValidator {
+    key: DecodingKey,
+    validation: Validation,
+    header: Option<String>,
+}
+
+impl JwtValidator {
+    /// Validate HS256 tokens signed with `secret`.
+    pub fn hs256(secret: &[u8]) -> Self {
+        Self::new(DecodingKey::from_secret(secret), Algorithm::HS256)
+    }
+
+    /// Validate RS256 tokens against a PEM-encoded RSA public key.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Config` if the key can't be parsed.
+    pub fn rs256_pem(pem: &[u8]) -> Result<Self, Error> {
+        let key = DecodingKey::from_rsa_pem(pem)
+            .map_err(|e| Error::Config(format!("invalid RSA public key: {}", e)))?;
+        Ok(Self::new(key, Algorithm::RS256))
+    }
+
+    fn new(key: DecodingKey, algorithm: Algorithm) -> Self {
+        let mut validation = Validation::new(algorithm);
+        validation.validate_aud = false;
+        Self {
+            key,
+            validation,
+            header: None,
+        }
+    }
+
+    /// Require the `iss` claim to equal `issuer`.
+    #[must_use]
+    pub fn with_issuer(mut self, issuer: &str) -> Self {
+        self.validation.set_issuer(&[issuer]);
+        self
+    }
+
+    /// Require the `aud` claim to contain `audience`.
+    #[must_use]
+    pub fn with_audience(mut self, audience: &str) -> Self {
+        self.validation.set_audience(&[audience]);
+        self.validation.validate_aud = true;
+        self
+    }
+
+    /// Require `claim` to be present (in addition to `exp`).
+    #[must_use]
+    pub fn with_required_claim(mut self, claim: &str) -> Self {
+        self.validation
+            .required_spec_claims
+            .insert(claim.to_string());
+        self
+    }
+
+    /// Allow this much clock skew when checking `exp` and `nbf` (default: 60s).
+    #[must_use]
+    pub fn with_leeway(mut self, seconds: u64) -> Self {
+        self.validation.leeway = seconds;
+        self
+    }
+
+    /// Read the token from header `name` instead of `Authorization: Bearer`.
+    #[must_use]
+    pub fn with_header(mut self, name: impl Into<String>) -> Self {
+        self.header = Some(name.into());
+        self
+    }
+
+    /// Validate a token and return its claims.
+    pub fn validate(&self, token: &str) -> Result<JwtClaims, AuthError> {
+        decode::<Map<String, Value>>(token, &self.key, &self.validation)
+            .map(|data| JwtClaims(data.claims))
+            .map_err(|e| {
+                AuthError::new(match e.kind() {
+                    ErrorKind::ExpiredSignature => "Expired token",
+                    ErrorKind::ImmatureSignature => "Token not yet valid",
+                    ErrorKind::InvalidIssuer | ErrorKind::InvalidAudience => "Invalid token claims",
+                    ErrorKind::MissingRequiredClaim(_) => "Missing token claim",
+                    _ => "Invalid token",
+                })
+            })
+    }
+
+    /// Find the token in request headers and validate it.
+    #[cfg(feature = "http")]
+    pub fn validate_headers(&self, headers: &http::HeaderMap) -> Result<JwtClaims, AuthError> {
+        let value = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        let token = match &self.header {
+            Some(name) => value(name),
+            None => value("Authorization").and_then(|v| {
+                v.strip_prefix("Bearer ")
+                    .or_else(|| v.strip_prefix("bearer "))
+            }),
+        };
+        self.validate(token.ok_or(AuthError::new("Missing token"))?.trim())
+    }
+}
+
+impl std::fmt::Debug for JwtValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwtValidator")
+            .field("validation", &self.validation)
+            .field("header", &self.header)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+
+    fn token(claims: Value) -> String {
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap()
+    }
+
+    fn in_one_hour() -> u64 {
+        jsonwebtoken::get_current_timestamp() + 3600
+    }
+
+    #[test]
+    fn test_hs256_claims() {
+        let validator = JwtValidator::hs256(b"secret")
+            .with_issuer("proxy")
+            .with_audience("trmnl");
+
+        let good =
+            token(json!({"sub": "kitchen", "iss": "proxy", "aud": "trmnl", "exp": in_one_hour()}));
+        assert_eq!(
+            validator.validate(&good).unwrap().subject(),
+            Some("kitchen")
+        );
+
+        let wrong_aud = token(json!({"iss": "proxy", "aud": "other", "exp": in_one_hour()}));
+        assert_eq!(
+            validator.validate(&wrong_aud).unwrap_err().message,
+            "Invalid token claims"
+        );
+
+        let expired = token(json!({"iss": "proxy", "aud": "trmnl", "exp": 1}));
+        assert_eq!(
+            validator.validate(&expired).unwrap_err().message,
+            "Expired token"
+        );
+
+        let wrong_key = JwtValidator::hs256(b"other");
+        assert_eq!(
+            wrong_key.validate(&good).unwrap_err().message,
+            "Invalid token"
+        );
+    }
+
+    #[test]
+    fn test_required_claim() {
+        let validator = JwtValidator::hs256(b"secret").with_required_claim("sub");
+        let no_sub = token(json!({"exp": in_one_hour()}));
+        assert_eq!(
+            validator.validate(&no_sub).unwrap_err().message,
+            "Missing token claim"
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_validate_headers() {
+        let jwt = token(json!({"exp": in_one_hour()}));
+        let mut headers = http::HeaderMap::new();
+        let validator = JwtValidator::hs256(b"secret");
+        assert_eq!(
+            validator.validate_headers(&headers).unwrap_err().message,
+            "Missing token"
+        );
+
+        headers.insert("Authorization", format!("Bearer {}", jwt).parse().unwrap());
+        assert!(validator.validate_headers(&headers).is_ok());
+
+        let proxied = validator.with_header("X-Proxy-Jwt");
+        assert!(proxied.validate_headers(&headers).is_err());
+        headers.insert("X-Proxy-Jwt", jwt.parse().unwrap());
+        assert!(proxied.validate_headers(&headers).is_ok());
+    }
+}
@@ -0,0 +1,604 @@
+//! Optional authentication for BYOS endpoints.
+//!
+//! By default, BYOS endpoints are public - anyone who knows the URL can access them.
+//! This module provides optional token-based authentication.
+//!
+//! # Usage
+//!
+//! Configure your device URL with a token query parameter:
+//! `https://yourserver.com/api/display?token=your-secret-token`
+//!
+//! Then validate it in your handler:
+//!
+//! ```rust,ignore
+//! use trmnl::{DeviceInfo, DisplayResponse, TokenAuth};
+//!
+//! async fn display(
+//!     device: DeviceInfo,
+//!     auth: TokenAuth,
+//! ) -> Result<Json<DisplayResponse>, (StatusCode, &'static str)> {
+//!     // Validate token against environment variable
+//!     auth.validate_env("TRMNL_TOKEN")?;
+//!
+//!     // Or validate against a specific value
+//!     // auth.validate("my-secret-token")?;
+//!
+//!     Ok(Json(DisplayResponse::new(...)))
+//! }
+//! ```
+//!
+//! With the `axum` feature, [`ValidatedTokenAuth`] does the check itself
+//! against a [`SecretProvider`] in the router state.
+
+#[cfg(feature = "jwt")]
+mod jwt;
+mod secret;
+
+#[cfg(feature = "jwt")]
+pub use jwt::{JwtClaims, JwtValidator};
+pub use secret::Secret;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::{DeviceInfo, MacAddress};
+
+/// Authentication error returned when token validation fails.
+#[derive(Debug, Clone)]
+pub struct AuthError {
+    /// Error message
+    pub message: &'static str,
+}
+
+impl AuthError {
+    /// Create a new auth error.
+    pub fn new(message: &'static str) -> Self {
+        Self { message }
+    }
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Token authentication extractor.
+///
+/// Extracts the `token` query parameter from the request URL.
+/// Use with `validate()` or `validate_env()` to check the token.
+#[derive(Debug, Clone, Default)]
+pub struct TokenAuth {
+    /// The token from the query string (if present)
+    pub token: Option<Secret<String>>,
+}
+
+impl TokenAuth {
+    /// Create a new TokenAuth with the given token.
+    pub fn new(token: Option<String>) -> Self {
+        Self {
+            token: token.map(Secret::new),
+        }
+    }
+
+    /// Validate the token against an expected value.
+    ///
+    /// Returns `Ok(())` if tokens match, or `Err(AuthError)` if not.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// auth.validate("my-secret-token")?;
+    /// ```
+    pub fn validate(&self, expected: &str) -> Result<(), AuthError> {
+        match &self.token {
+            Some(token) if constant_time_eq(token.expose().as_bytes(), expected.as_bytes()) => {
+                Ok(())
+            }
+            Some(_) => Err(AuthError::new("Invalid token")),
+            None => Err(AuthError::new("Missing token")),
+        }
+    }
+
+    /// Validate the token against an environment variable.
+    ///
+    /// If the environment variable is not set, authentication is skipped (allows open access).
+    /// This lets you easily enable/disable auth via environment.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // If TRMNL_TOKEN is set, validates against it
+    /// // If TRMNL_TOKEN is not set, allows all requests
+    /// auth.validate_env("TRMNL_TOKEN")?;
+    /// ```
+    pub fn validate_env(&self, env_var: &str) -> Result<(), AuthError> {
+        match std::env::var(env_var) {
+            Ok(expected) => self.validate(&expected),
+            Err(_) => Ok(()), // No token configured = open access
+        }
+    }
+
+    /// Validate the token against a [`SecretProvider`].
+    ///
+    /// If the provider has no secret, authentication is skipped (allows open
+    /// access), like [`validate_env`](Self::validate_env).
+    pub fn validate_secret(&self, secret: &SecretProvider) -> Result<(), AuthError> {
+        match secret.secret() {
+            Some(expected) => self.validate(expected.expose()),
+            None => Ok(()),
+        }
+    }
+
+    /// Validate the token against the one expected for `device`.
+    ///
+    /// Unlike the other methods, there is no open access: devices without
+    /// a parseable `ID` or without an entry in `tokens` are rejected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::auth::{TokenAuth, TokenMap};
+    /// use trmnl::DeviceInfo;
+    ///
+    /// let tokens: TokenMap = "AA:BB:CC:DD:EE:FF=kitchen-secret".parse().unwrap();
+    /// let device = DeviceInfo::new("aa:bb:cc:dd:ee:ff");
+    ///
+    /// let auth = TokenAuth::new(Some("kitchen-secret".to_string()));
+    /// assert!(auth.validate_for_device(&device, &tokens).is_ok());
+    /// ```
+    pub fn validate_for_device(
+        &self,
+        device: &DeviceInfo,
+        tokens: &TokenMap,
+    ) -> Result<(), AuthError> {
+        let mac = device.mac().ok_or(AuthError::new("Invalid device ID"))?;
+        let expected = tokens.get(&mac).ok_or(AuthError::new("Unknown device"))?;
+        self.validate(expected)
+    }
+
+    /// Check if a token was provided (without validating it).
+    pub fn has_token(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// Extract token from a query string.
+    ///
+    /// Useful for manual extraction outside of axum.
+    pub fn from_query_string(query: &str) -> Self {
+        let params: HashMap<_, _> = form_urlencoded::parse(query.as_bytes()).collect();
+        Self {
+            token: params.get("token").map(|s| Secret::new(s.to_string())),
+        }
+    }
+}
+
+/// Token that has already been checked against the router's secret.
+///
+/// Unlike [`TokenAuth`], extracting this validates the token, rejecting
+/// the request with `401 Unauthorized` before the handler runs. The
+/// expected secret comes from a [`SecretProvider`] in the router state
+/// (directly or via `FromRef`), so there is no separate validate call to
+/// forget.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::auth::{SecretProvider, ValidatedTokenAuth};
+///
+/// async fn display(_auth: ValidatedTokenAuth, device: DeviceInfo) -> Json<DisplayResponse> {
+///     // Only reached with a valid token
+/// }
+///
+/// let app = axum::Router::new()
+///     .route("/api/display", get(display))
+///     .with_state(SecretProvider::env("TRMNL_TOKEN"));
+/// ```
+#[cfg(feature = "axum")]
+#[derive(Debug, Clone, Default)]
+pub struct ValidatedTokenAuth(pub TokenAuth);
+
+#[cfg(feature = "axum")]
+impl std::ops::Deref for ValidatedTokenAuth {
+    type Target = TokenAuth;
+
+    fn deref(&self) -> &TokenAuth {
+        &self.0
+    }
+}
+
+/// Expected token per device, keyed by MAC address.
+///
+/// Giving each device its own token lets you revoke one device without
+/// reconfiguring the rest of the fleet. Parse from `MAC=token` pairs
+/// separated by commas or newlines (e.g. from an environment variable with
+/// [`TokenMap::from_env`]), or deserialize from a map in any serde format:
+///
+/// ```yaml
+/// "AA:BB:CC:DD:EE:FF": kitchen-secret
+/// "11:22:33:44:55:66": office-secret
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TokenMap(HashMap<MacAddress, Secret<String>>);
+
+impl TokenMap {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the map from an environment variable.
+    ///
+    /// A missing variable gives an empty map, which rejects every device.
+    pub fn from_env(var: &str) -> Result<Self, Error> {
+        match std::env::var(var) {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(Self::new()),
+        }
+    }
+
+    /// Set the token for a device, returning the previous one.
+    pub fn insert(&mut self, mac: MacAddress, token: impl Into<String>) -> Option<Secret<String>> {
+        self.0.insert(mac, Secret::new(token.into()))
+    }
+
+    /// Remove a device's token, returning it.
+    pub fn remove(&mut self, mac: &MacAddress) -> Option<Secret<String>> {
+        self.0.remove(mac)
+    }
+
+    /// Token expected for a device.
+    pub fn get(&self, mac: &MacAddress) -> Option<&str> {
+        self.0.get(mac).map(|token| token.expose().as_str())
+    }
+
+    /// Number of devices.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no devices are configured.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromStr for TokenMap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut tokens = Self::new();
+        for pair in s
+            .split([',', '\n'])
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+        {
+            let (mac, token) = pair
+                .split_once('=')
+                .ok_or_else(|| Error::Config(format!("expected MAC=token, got: {}", pair)))?;
+            let token = token.trim();
+            if token.is_empty() {
+                return Err(Error::Config(format!("empty token for {}", mac.trim())));
+            }
+            tokens.insert(mac.parse()?, token);
+        }
+        Ok(tokens)
+    }
+}
+
+impl FromIterator<(MacAddress, String)> for TokenMap {
+    fn from_iter<I: IntoIterator<Item = (MacAddress, String)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(mac, token)| (mac, Secret::new(token)))
+                .collect(),
+        )
+    }
+}
+
+/// Source of the expected token.
+///
+/// The secret is looked up on every request, so environment variables and
+/// functions can change it without restarting the server. A provider that
+/// returns no secret disables authentication.
+///
+/// # Example
+///
+/// ```
+/// use trmnl::auth::{SecretProvider, TokenAuth};
+///
+/// let auth = TokenAuth::new(Some("s3cret".to_string()));
+/// assert!(auth.validate_secret(&SecretProvider::fixed("s3cret")).is_ok());
+/// assert!(auth.validate_secret(&SecretProvider::from_fn(|| None)).is_ok());
+/// ```
+#[derive(Clone)]
+pub struct SecretProvider(SecretSource);
+
+#[derive(Clone)]
+enum SecretSource {
+    Fixed(Secret<String>),
+    Env(String),
+    Fn(Arc<dyn Fn() -> Option<String> + Send + Sync>),
+}
+
+impl SecretProvider {
+    /// Always expect `secret`.
+    pub fn fixed(secret: impl Into<String>) -> Self {
+        Self(SecretSource::Fixed(Secret::new(secret.into())))
+    }
+
+    /// Expect the value of an environment variable; open access if unset.
+    pub fn env(var: impl Into<String>) -> Self {
+        Self(SecretSource::Env(var.into()))
+    }
+
+    /// Ask `f` for the secret on each request; open access if it returns `None`.
+    pub fn from_fn(f: impl Fn() -> Option<String> + Send + Sync + 'static) -> Self {
+        Self(SecretSource::Fn(Arc::new(f)))
+    }
+
+    /// Current secret, if any.
+    pub fn secret(&self) -> Option<Secret<String>> {
+        match &self.0 {
+            SecretSource::Fixed(secret) => Some(secret.clone()),
+            SecretSource::Env(var) => std::env::var(var).ok().map(Secret::new),
+            SecretSource::Fn(f) => f().map(Secret::new),
+        }
+    }
+}
+
+impl From<&str> for SecretProvider {
+    fn from(secret: &str) -> Self {
+        Self::fixed(secret)
+    }
+}
+
+impl From<String> for SecretProvider {
+    fn from(secret: String) -> Self {
+        Self::fixed(secret)
+    }
+}
+
+impl std::fmt::Debug for SecretProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = match &self.0 {
+            SecretSource::Fixed(secret) => format!("fixed({})", secret),
+            SecretSource::Env(var) => format!("env({})", var),
+            SecretSource::Fn(_) => "fn".to_string(),
+        };
+        f.debug_tuple("SecretProvider").field(&source).finish()
+    }
+}
+
+/// Extract the `token` query parameter, stripping the firmware's `/api/...`
+/// suffix (see the axum extractor docs).
+#[cfg(feature = "http")]
+pub(crate) fn token_from_query(query: &str) -> Option<String> {
+    form_urlencoded::parse(query.as_bytes())
+        .find(|(k, _)| k == "token")
+        .map(|(_, v)| {
+            // Strip firmware's malformed /api/... suffix if present
+            let s = v.to_string();
+            if let Some(idx) = s.find("/api/") {
+                s[..idx].to_string()
+            } else {
+                s
+            }
+        })
+}
+
+#[cfg(feature = "axum")]
+mod axum_impl {
+    use super::*;
+    use axum::extract::{FromRef, FromRequestParts};
+    use axum::http::request::Parts;
+    use axum::http::StatusCode;
+
+    /// Axum extractor for TokenAuth.
+    ///
+    /// Extracts the `token` query parameter from the request.
+    ///
+    /// Note: TRMNL firmware has a quirk where if you configure the base URL with
+    /// a query parameter like `?token=xxx`, it appends `/api/display` to the token
+    /// value, resulting in `token=xxx/api/display`. We strip any `/api/...` suffix.
+    impl<S> FromRequestParts<S> for TokenAuth
+    where
+        S: Send + Sync,
+    {
+        type Rejection = (StatusCode, &'static str);
+
+        async fn from_request_parts(
+            parts: &mut Parts,
+            _state: &S,
+        ) -> Result<Self, Self::Rejection> {
+            Ok(TokenAuth::from_uri(&parts.uri))
+        }
+    }
+
+    /// Axum extractor for ValidatedTokenAuth, checking the token against
+    /// the [`SecretProvider`] in the router state.
+    impl<S> FromRequestParts<S> for ValidatedTokenAuth
+    where
+        S: Send + Sync,
+        SecretProvider: FromRef<S>,
+    {
+        type Rejection = (StatusCode, &'static str);
+
+        async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+            let auth = TokenAuth::from_uri(&parts.uri);
+            auth.validate_secret(&SecretProvider::from_ref(state))
+                .map_err(|e| (StatusCode::UNAUTHORIZED, e.message))?;
+            Ok(ValidatedTokenAuth(auth))
+        }
+    }
+}
+
+/// Compare without short-circuiting, so timing doesn't reveal the secret.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret1"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[test]
+    fn test_token_auth_validate() {
+        let auth = TokenAuth::new(Some("secret123".to_string()));
+        assert!(auth.validate("secret123").is_ok());
+        assert!(auth.validate("wrong").is_err());
+    }
+
+    #[test]
+    fn test_token_auth_missing() {
+        let auth = TokenAuth::new(None);
+        assert!(auth.validate("anything").is_err());
+    }
+
+    #[test]
+    fn test_from_query_string() {
+        let auth = TokenAuth::from_query_string("token=mysecret&other=value");
+        assert_eq!(
+            auth.token.as_ref().map(|t| t.expose().as_str()),
+            Some("mysecret")
+        );
+
+        let auth_empty = TokenAuth::from_query_string("other=value");
+        assert_eq!(auth_empty.token, None);
+    }
+
+    #[test]
+    fn test_token_map() {
+        let tokens: TokenMap = "aa-bb-cc-dd-ee-ff = one,\n11:22:33:44:55:66=two"
+            .parse()
+            .unwrap();
+        assert_eq!(tokens.len(), 2);
+
+        let kitchen = DeviceInfo::new("AA:BB:CC:DD:EE:FF");
+        let auth = TokenAuth::new(Some("one".to_string()));
+        assert!(auth.validate_for_device(&kitchen, &tokens).is_ok());
+
+        let office = DeviceInfo::new("11:22:33:44:55:66");
+        assert_eq!(
+            auth.validate_for_device(&office, &tokens)
+                .unwrap_err()
+                .message,
+            "Invalid token"
+        );
+        let unknown = DeviceInfo::new("00:00:00:00:00:01");
+        assert_eq!(
+            auth.validate_for_device(&unknown, &tokens)
+                .unwrap_err()
+                .message,
+            "Unknown device"
+        );
+        assert!(auth
+            .validate_for_device(&DeviceInfo::new("unknown"), &tokens)
+            .is_err());
+
+        assert!("AA:BB:CC:DD:EE:FF".parse::<TokenMap>().is_err());
+        assert!("AA:BB:CC:DD:EE:FF=".parse::<TokenMap>().is_err());
+        assert!("nope=token".parse::<TokenMap>().is_err());
+    }
+
+    #[test]
+    fn test_token_map_deserialize() {
+        let tokens: TokenMap = serde_json::from_str(r#"{"aa:bb:cc:dd:ee:ff": "one"}"#).unwrap();
+        let mac: MacAddress = "AA:BB:CC:DD:EE:FF".parse().unwrap();
+        assert_eq!(tokens.get(&mac), Some("one"));
+    }
+
+    #[test]
+    fn test_secret_provider() {
+        let auth = TokenAuth::new(Some("secret123".to_string()));
+        assert!(auth
+            .validate_secret(&SecretProvider::fixed("secret123"))
+            .is_ok());
+        assert!(auth
+            .validate_secret(&SecretProvider::fixed("other"))
+            .is_err());
+        assert!(auth
+            .validate_secret(&SecretProvider::env("NONEXISTENT_VAR_12345"))
+            .is_ok());
+        assert!(auth
+            .validate_secret(&SecretProvider::from_fn(|| Some("x".to_string())))
+            .is_err());
+
+        let debug = format!("{:?}", SecretProvider::fixed("secret123"));
+        assert!(!debug.contains("secret123"));
+        let debug = format!("{:?}", TokenAuth::new(Some("secret123".to_string())));
+        assert!(!debug.contains("secret123"));
+    }
+
+    #[test]
+    fn test_validate_env_not_set() {
+        // When env var is not set, should allow access
+        let auth = TokenAuth::new(None);
+        assert!(auth.validate_env("NONEXISTENT_VAR_12345").is_ok());
+    }
+
+    #[test]
+    fn test_from_query_string_with_firmware_quirk() {
+        // TRMNL firmware appends /api/... to tokens when URL is misconfigured
+        let auth = TokenAuth::from_query_string("token=mysecret/api/display");
+        // from_query_string doesn't strip (that's in axum extractor)
+        // but the token should validate if we manually strip
+        let token = auth.token.unwrap().expose().clone();
+        let clean = if let Some(idx) = token.find("/api/") {
+            token[..idx].to_string()
+        } else {
+            token
+        };
+        assert_eq!(clean, "mysecret");
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn test_validated_token_auth() {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use axum::routing::get;
+        use axum::Router;
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route(
+                "/api/display",
+                get(|_auth: ValidatedTokenAuth| async { "ok" }),
+            )
+            .with_state(SecretProvider::fixed("s3cret"));
+
+        let status = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let request = Request::get(uri).body(Body::empty()).unwrap();
+                app.oneshot(request).await.unwrap().status()
+            }
+        };
+        assert_eq!(status("/api/display?token=s3cret").await, StatusCode::OK);
+        assert_eq!(
+            status("/api/display?token=s3cret/api/display").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status("/api/display?token=wrong").await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(status("/api/display").await, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,93 @@
+//! Wrapper keeping tokens and API keys out of logs.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+/// A value that must not be logged, such as a token or API key.
+///
+/// `Debug` and `Display` print `[redacted]`, so structs holding secrets can
+/// derive `Debug` and be logged with `{:?}` safely. The value is
+/// overwritten with zeros when dropped. Call [`expose`](Self::expose) to
+/// read it.
+///
+/// Serializing writes the value itself, so that configs round-trip; don't
+/// serialize secrets into logs.
+///
+/// Comparing two secrets with `==` takes the same time wherever they
+/// differ, so it doesn't reveal how much of a guess was right.
+///
+/// # Example
+///
+/// ```
+/// use trmnl::auth::Secret;
+///
+/// let key = Secret::new("s3cret".to_string());
+/// assert_eq!(format!("{:?}", key), "[redacted]");
+/// assert_eq!(key.expose(), "s3cret");
+/// assert_eq!(key, Secret::from("s3cret"));
+/// assert_ne!(key, Secret::from("s3creT"));
+/// ```
+#[derive(Clone, Default)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    /// Wrap `value`.
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// The secret value.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        super::constant_time_eq(self.0.as_ref(), other.0.as_ref())
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> Eq for Secret<T> {}
+
+impl<T: Zeroize> std::fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+impl<T: Zeroize> std::fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+impl From<String> for Secret<String> {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for Secret<String> {
+    fn from(value: &str) -> Self {
+        Self::new(value.to_string())
+    }
+}
+
+impl<T: Zeroize + Serialize> Serialize for Secret<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Zeroize + Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self)
+    }
+}
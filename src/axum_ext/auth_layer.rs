@@ -0,0 +1,286 @@
+//! Token authentication as a tower layer.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use axum::http::{Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::auth::{SecretProvider, TokenAuth};
+
+/// Layer rejecting requests without a valid `?token=` before they reach
+/// handlers.
+///
+/// Like the [`TokenAuth`] extractor, it strips the firmware's malformed
+/// `token=SECRET/api/display` suffix. Rejected requests get
+/// `401 Unauthorized`. If the [`SecretProvider`] has no secret, every
+/// request passes.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::auth::SecretProvider;
+/// use trmnl::axum_ext::TokenAuthLayer;
+///
+/// let app = axum::Router::new()
+///     .route("/api/display", get(display))
+///     .layer(TokenAuthLayer::new(SecretProvider::env("TRMNL_TOKEN")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TokenAuthLayer {
+    secret: SecretProvider,
+}
+
+impl TokenAuthLayer {
+    /// Require the token provided by `expected`.
+    pub fn new(expected: impl Into<SecretProvider>) -> Self {
+        Self {
+            secret: expected.into(),
+        }
+    }
+}
+
+impl<S> Layer<S> for TokenAuthLayer {
+    type Service = TokenAuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TokenAuthService {
+            inner,
+            secret: self.secret.clone(),
+        }
+    }
+}
+
+/// Service produced by [`TokenAuthLayer`].
+#[derive(Debug, Clone)]
+pub struct TokenAuthService<S> {
+    inner: S,
+    secret: SecretProvider,
+}
+
+impl<S, B> Service<Request<B>> for TokenAuthService<S>
+where
+    S: Service<Request<B>, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let auth = TokenAuth::from_uri(request.uri());
+        if let Err(e) = auth.validate_secret(&self.secret) {
+            let response = (StatusCode::UNAUTHORIZED, e.message).into_response();
+            return Box::pin(async move { Ok(response) });
+        }
+        Box::pin(self.inner.call(request))
+    }
+}
+
+/// Layer rejecting requests without a valid JWT before they reach
+/// handlers.
+///
+/// Validated claims are added to the request extensions and can be
+/// extracted with [`JwtClaims`](crate::auth::JwtClaims). Rejected requests
+/// get `401 Unauthorized`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::auth::{JwtClaims, JwtValidator};
+/// use trmnl::axum_ext::JwtAuthLayer;
+///
+/// async fn display(claims: JwtClaims, device: DeviceInfo) -> Json<DisplayResponse> {
+///     // ...
+/// }
+///
+/// let validator = JwtValidator::hs256(secret.as_bytes()).with_audience("trmnl");
+/// let app = axum::Router::new()
+///     .route("/api/display", get(display))
+///     .layer(JwtAuthLayer::new(validator));
+/// ```
+#[cfg(feature = "jwt")]
+#[derive(Debug, Clone)]
+pub struct JwtAuthLayer {
+    validator: std::sync::Arc<crate::auth::JwtValidator>,
+}
+
+#[cfg(feature = "jwt")]
+impl JwtAuthLayer {
+    /// Require tokens accepted by `validator`.
+    pub fn new(validator: crate::auth::JwtValidator) -> Self {
+        Self {
+            validator: std::sync::Arc::new(validator),
+        }
+    }
+}
+
+#[cfg(feature = "jwt")]
+impl<S> Layer<S> for JwtAuthLayer {
+    type Service = JwtAuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        JwtAuthService {
+            inner,
+            validator: self.validator.clone(),
+        }
+    }
+}
+
+/// Service produced by [`JwtAuthLayer`].
+#[cfg(feature = "jwt")]
+#[derive(Debug, Clone)]
+pub struct JwtAuthService<S> {
+    inner: S,
+    validator: std::sync::Arc<crate::auth::JwtValidator>,
+}
+
+#[cfg(feature = "jwt")]
+impl<S, B> Service<Request<B>> for JwtAuthService<S>
+where
+    S: Service<Request<B>, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        match self.validator.validate_headers(request.headers()) {
+            Ok(claims) => {
+                request.extensions_mut().insert(claims);
+                Box::pin(self.inner.call(request))
+            }
+            Err(e) => {
+                let response = (StatusCode::UNAUTHORIZED, e.message).into_response();
+                Box::pin(async move { Ok(response) })
+            }
+        }
+    }
+}
+
+/// Claims added by [`JwtAuthLayer`]; rejects with `401 Unauthorized` on
+/// routes without the layer.
+#[cfg(feature = "jwt")]
+impl<S> axum::extract::FromRequestParts<S> for crate::auth::JwtClaims
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Self>()
+            .cloned()
+            .ok_or((StatusCode::UNAUTHORIZED, "Missing token"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    async fn status(secret: SecretProvider, uri: &str) -> StatusCode {
+        let app = Router::new()
+            .route("/api/display", get(|| async { "ok" }))
+            .layer(TokenAuthLayer::new(secret));
+        app.oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_rejects_missing_or_invalid_token() {
+        let secret = SecretProvider::fixed("s3cret");
+        assert_eq!(
+            status(secret.clone(), "/api/display").await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(secret, "/api/display?token=wrong").await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_accepts_token_with_firmware_suffix() {
+        let secret = SecretProvider::fixed("s3cret");
+        assert_eq!(
+            status(secret.clone(), "/api/display?token=s3cret").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(secret, "/api/display?token=s3cret/api/display").await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_secret_allows_all() {
+        let secret = SecretProvider::env("NONEXISTENT_VAR_12345");
+        assert_eq!(status(secret, "/api/display").await, StatusCode::OK);
+    }
+
+    #[cfg(feature = "jwt")]
+    #[tokio::test]
+    async fn test_jwt_layer() {
+        use crate::auth::{JwtClaims, JwtValidator};
+        use jsonwebtoken::{encode, EncodingKey, Header};
+
+        let app =
+            Router::new()
+                .route(
+                    "/api/display",
+                    get(|claims: JwtClaims| async move {
+                        claims.subject().unwrap_or_default().to_string()
+                    }),
+                )
+                .layer(JwtAuthLayer::new(JwtValidator::hs256(b"secret")));
+
+        let claims = serde_json::json!({
+            "sub": "kitchen",
+            "exp": jsonwebtoken::get_current_timestamp() + 3600,
+        });
+        let jwt = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+
+        let request = Request::get("/api/display")
+            .header("Authorization", format!("Bearer {}", jwt))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"kitchen");
+
+        let request = Request::get("/api/display").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,372 @@
+//! Per-device API keys issued on setup and checked on later requests.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+
+use axum::http::{Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::MethodRouter;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use super::{setup_handler, SetupDecision};
+use crate::auth::{constant_time_eq, Secret};
+use crate::error::Error;
+use crate::{DeviceInfo, MacAddress};
+
+/// Storage for issued API keys.
+///
+/// Implement this over your database to keep keys across restarts;
+/// [`MemoryCredentialStore`] keeps them in memory.
+pub trait CredentialStore: Send + Sync + 'static {
+    /// Store `api_key` for `mac` unless a key is already stored for it,
+    /// returning whether it was stored.
+    ///
+    /// The check and the write must be atomic (e.g. an `INSERT` that fails
+    /// on a duplicate key), so concurrent setups can't both succeed.
+    fn insert(
+        &self,
+        mac: &MacAddress,
+        api_key: &str,
+    ) -> impl Future<Output = Result<bool, Error>> + Send;
+
+    /// The key issued to `mac`, if any.
+    fn api_key(
+        &self,
+        mac: &MacAddress,
+    ) -> impl Future<Output = Result<Option<String>, Error>> + Send;
+
+    /// Forget the key issued to `mac`, if any.
+    fn remove(&self, mac: &MacAddress) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+/// In-memory [`CredentialStore`]. Keys are lost on restart, so devices
+/// must go through setup again.
+#[derive(Debug, Default)]
+pub struct MemoryCredentialStore {
+    keys: RwLock<HashMap<MacAddress, Secret<String>>>,
+}
+
+impl MemoryCredentialStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CredentialStore for MemoryCredentialStore {
+    async fn insert(&self, mac: &MacAddress, api_key: &str) -> Result<bool, Error> {
+        let mut keys = self.keys.write().unwrap_or_else(|e| e.into_inner());
+        match keys.entry(*mac) {
+            Entry::Occupied(_) => Ok(false),
+            Entry::Vacant(entry) => {
+                entry.insert(Secret::new(api_key.to_string()));
+                Ok(true)
+            }
+        }
+    }
+
+    async fn api_key(&self, mac: &MacAddress) -> Result<Option<String>, Error> {
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        Ok(keys.get(mac).map(|key| key.expose().clone()))
+    }
+
+    async fn remove(&self, mac: &MacAddress) -> Result<(), Error> {
+        self.keys
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(mac);
+        Ok(())
+    }
+}
+
+/// Issues an API key to each device on `/api/setup` and checks the
+/// `Access-Token` header against it afterwards.
+///
+/// This is the auth model the firmware was designed for: the device stores
+/// the key from the setup response and sends it on every request, so no
+/// secret needs to be put in the server URL.
+///
+/// `/api/setup` is unauthenticated, so only the first setup for a MAC
+/// address gets a key; later ones are rejected, or anyone sending a
+/// device's MAC could take it over. Call [`revoke`](DeviceCredentials::revoke)
+/// to let a device that lost its key (e.g. after a factory reset) set up
+/// again.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::axum_ext::{DeviceCredentials, MemoryCredentialStore};
+///
+/// let credentials = DeviceCredentials::new(MemoryCredentialStore::new());
+/// let app = axum::Router::new()
+///     .route("/api/display", get(display))
+///     .route("/api/log", log_handler(TracingSink::new()))
+///     .layer(credentials.layer())
+///     // Added after the layer, so setup stays reachable without a key
+///     .route("/api/setup", credentials.setup_handler());
+/// ```
+#[derive(Debug)]
+pub struct DeviceCredentials<St> {
+    store: Arc<St>,
+}
+
+impl<St> Clone for DeviceCredentials<St> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+        }
+    }
+}
+
+impl<St: CredentialStore> DeviceCredentials<St> {
+    /// Issue and check keys using `store`.
+    pub fn new(store: St) -> Self {
+        Self {
+            store: Arc::new(store),
+        }
+    }
+
+    /// The underlying store.
+    pub fn store(&self) -> &St {
+        &self.store
+    }
+
+    /// Register `device` with a new API key, saving it to the store.
+    ///
+    /// Devices that already have a key are rejected and keep their key;
+    /// [`revoke`](Self::revoke) it first to issue a new one. Devices without
+    /// a valid MAC address are rejected too.
+    pub async fn issue(&self, device: &DeviceInfo) -> Result<SetupDecision, Error> {
+        let Some(mac) = device.mac() else {
+            return Ok(SetupDecision::reject("Missing or invalid ID header"));
+        };
+        let decision = SetupDecision::register(device);
+        if let SetupDecision::Register { api_key, .. } = &decision {
+            if !self.store.insert(&mac, api_key).await? {
+                return Ok(SetupDecision::reject("Device is already registered"));
+            }
+        }
+        Ok(decision)
+    }
+
+    /// Revoke the key issued to `mac`, letting the device go through setup
+    /// again.
+    pub async fn revoke(&self, mac: &MacAddress) -> Result<(), Error> {
+        self.store.remove(mac).await
+    }
+
+    /// Whether `device` sent the `Access-Token` issued to it.
+    pub async fn verify(&self, device: &DeviceInfo) -> Result<bool, Error> {
+        let (Some(mac), Some(token)) = (device.mac(), device.access_token.as_ref()) else {
+            return Ok(false);
+        };
+        Ok(match self.store.api_key(&mac).await? {
+            Some(expected) => constant_time_eq(token.expose().as_bytes(), expected.as_bytes()),
+            None => false,
+        })
+    }
+
+    /// `/api/setup` handler issuing keys with [`issue`](Self::issue).
+    pub fn setup_handler<S>(&self) -> MethodRouter<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let credentials = self.clone();
+        setup_handler(move |device: DeviceInfo| {
+            let credentials = credentials.clone();
+            async move { credentials.issue(&device).await }
+        })
+    }
+
+    /// Layer rejecting requests that fail [`verify`](Self::verify) with
+    /// `401 Unauthorized`.
+    pub fn layer(&self) -> DeviceCredentialsLayer<St> {
+        DeviceCredentialsLayer {
+            credentials: self.clone(),
+        }
+    }
+}
+
+/// Layer produced by [`DeviceCredentials::layer`].
+#[derive(Debug)]
+pub struct DeviceCredentialsLayer<St> {
+    credentials: DeviceCredentials<St>,
+}
+
+impl<St> Clone for DeviceCredentialsLayer<St> {
+    fn clone(&self) -> Self {
+        Self {
+            credentials: self.credentials.clone(),
+        }
+    }
+}
+
+impl<S, St> Layer<S> for DeviceCredentialsLayer<St> {
+    type Service = DeviceCredentialsService<S, St>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DeviceCredentialsService {
+            inner,
+            credentials: self.credentials.clone(),
+        }
+    }
+}
+
+/// Service produced by [`DeviceCredentialsLayer`].
+#[derive(Debug)]
+pub struct DeviceCredentialsService<S, St> {
+    inner: S,
+    credentials: DeviceCredentials<St>,
+}
+
+impl<S: Clone, St> Clone for DeviceCredentialsService<S, St> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            credentials: self.credentials.clone(),
+        }
+    }
+}
+
+impl<S, St, B> Service<Request<B>> for DeviceCredentialsService<S, St>
+where
+    S: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    St: CredentialStore,
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        // Use the service that was polled ready; leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let credentials = self.credentials.clone();
+
+        Box::pin(async move {
+            let device = DeviceInfo::from_headers(request.headers());
+            match credentials.verify(&device).await {
+                Ok(true) => inner.call(request).await,
+                Ok(false) => Ok((StatusCode::UNAUTHORIZED, "Invalid access token").into_response()),
+                Err(e) => Ok(e.into_response()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    fn app(credentials: &DeviceCredentials<MemoryCredentialStore>) -> Router {
+        Router::new()
+            .route("/api/display", get(|| async { "screen" }))
+            .layer(credentials.layer())
+            .route("/api/setup", credentials.setup_handler())
+    }
+
+    async fn display(app: Router, token: Option<&str>) -> StatusCode {
+        let mut request = Request::get("/api/display").header("ID", "AA:BB:CC:DD:EE:FF");
+        if let Some(token) = token {
+            request = request.header("Access-Token", token);
+        }
+        app.oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_setup_issues_key_checked_on_display() {
+        let credentials = DeviceCredentials::new(MemoryCredentialStore::new());
+        let app = app(&credentials);
+
+        assert_eq!(display(app.clone(), None).await, StatusCode::UNAUTHORIZED);
+
+        let request = Request::get("/api/setup")
+            .header("ID", "aa:bb:cc:dd:ee:ff")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let api_key = json["api_key"].as_str().unwrap().to_string();
+
+        assert_eq!(display(app.clone(), Some(&api_key)).await, StatusCode::OK);
+        assert_eq!(display(app, Some("wrong")).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_second_setup_cannot_take_over_device() {
+        let credentials = DeviceCredentials::new(MemoryCredentialStore::new());
+        let app = app(&credentials);
+
+        let setup = |mac: &'static str| {
+            let app = app.clone();
+            async move {
+                let request = Request::get("/api/setup")
+                    .header("ID", mac)
+                    .body(Body::empty())
+                    .unwrap();
+                let response = app.oneshot(request).await.unwrap();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        let first = setup("AA:BB:CC:DD:EE:FF").await;
+        let api_key = first["api_key"].as_str().unwrap().to_string();
+
+        // Someone else claiming the same MAC gets no key
+        let second = setup("aa-bb-cc-dd-ee-ff").await;
+        assert_eq!(second["api_key"], "");
+        assert_ne!(second["status"], first["status"]);
+
+        // And the real device keeps working
+        assert_eq!(display(app, Some(&api_key)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_allows_new_setup() {
+        let credentials = DeviceCredentials::new(MemoryCredentialStore::new());
+        let device = DeviceInfo::new("AA:BB:CC:DD:EE:FF");
+
+        let key = |decision: SetupDecision| match decision {
+            SetupDecision::Register { api_key, .. } => api_key,
+            other => panic!("unexpected {:?}", other),
+        };
+        let old = key(credentials.issue(&device).await.unwrap());
+        assert!(matches!(
+            credentials.issue(&device).await.unwrap(),
+            SetupDecision::Reject(_)
+        ));
+
+        credentials.revoke(&device.mac().unwrap()).await.unwrap();
+        let new = key(credentials.issue(&device).await.unwrap());
+
+        let with_token = |token: &str| device.clone().with_access_token(token);
+        assert!(!credentials.verify(&with_token(&old)).await.unwrap());
+        assert!(credentials.verify(&with_token(&new)).await.unwrap());
+
+        let invalid = DeviceInfo::new("unknown");
+        assert!(matches!(
+            credentials.issue(&invalid).await.unwrap(),
+            SetupDecision::Reject(_)
+        ));
+    }
+}
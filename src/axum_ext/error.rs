@@ -0,0 +1,89 @@
+//! HTTP responses for [`Error`].
+
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+use crate::error::Error;
+use crate::DisplayResponse;
+
+/// Responds with [`Error::status_code`] and a plain text body, so handlers
+/// can return `Result<_, trmnl::Error>` and use `?`.
+///
+/// Client errors carry the error message. Server errors get a generic body
+/// (e.g. `Internal Server Error`) and the details are logged with `tracing`.
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        (self.status_code(), self.response_body()).into_response()
+    }
+}
+
+/// Error for `/api/display` handlers that answers with
+/// [`DisplayResponse::error`] instead of an HTTP error.
+///
+/// Firmware shows HTTP errors as a broken screen; an error
+/// `DisplayResponse` keeps the current image and retries later. The error
+/// is logged with `tracing`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use axum::Json;
+/// use trmnl::axum_ext::DisplayError;
+/// use trmnl::{DeviceInfo, DisplayResponse};
+///
+/// async fn display(device: DeviceInfo) -> Result<Json<DisplayResponse>, DisplayError> {
+///     let png = trmnl::render_html_to_png(&build_html(&device), &Default::default()).await?;
+///     let response = DisplayResponse::builder()
+///         .with_inline_image(&png)
+///         .with_filename("screen.png")
+///         .build()?;
+///     Ok(Json(response))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DisplayError(pub Error);
+
+impl From<Error> for DisplayError {
+    fn from(err: Error) -> Self {
+        Self(err)
+    }
+}
+
+impl IntoResponse for DisplayError {
+    fn into_response(self) -> Response {
+        tracing::warn!("Display request failed: {}", self.0);
+        Json(DisplayResponse::error()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use axum::http::StatusCode;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_error_response() {
+        let response = Error::Protocol("invalid MAC address: x".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"Protocol error: invalid MAC address: x");
+
+        let timeout = Error::RenderTimeout(Duration::from_secs(30)).into_response();
+        assert_eq!(timeout.status(), StatusCode::GATEWAY_TIMEOUT);
+        let render = Error::Render("boom".to_string()).into_response();
+        assert_eq!(render.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = to_bytes(render.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"Internal Server Error");
+    }
+
+    #[tokio::test]
+    async fn test_display_error_response() {
+        let response = DisplayError::from(Error::Render("boom".to_string())).into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], 1);
+    }
+}
@@ -0,0 +1,199 @@
+//! Liveness and readiness routes.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+
+/// Checks run by the `/readyz` route of [`health_routes`].
+///
+/// With no checks configured, `/readyz` always succeeds.
+#[derive(Debug, Clone, Default)]
+pub struct HealthChecks {
+    #[cfg(feature = "render")]
+    render_config: Option<crate::render::RenderConfig>,
+    image_dir: Option<PathBuf>,
+    #[cfg(feature = "schedule")]
+    schedule_path: Option<PathBuf>,
+}
+
+impl HealthChecks {
+    /// Create an empty set of checks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check that the Chrome executable used by `config` exists.
+    #[cfg(feature = "render")]
+    #[must_use]
+    pub fn with_render_config(mut self, config: crate::render::RenderConfig) -> Self {
+        self.render_config = Some(config);
+        self
+    }
+
+    /// Check that images can be written to `path` (created if missing).
+    #[must_use]
+    pub fn with_image_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.image_dir = Some(path.into());
+        self
+    }
+
+    /// Check that the schedule file at `path` loads.
+    #[cfg(feature = "schedule")]
+    #[must_use]
+    pub fn with_schedule_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.schedule_path = Some(path.into());
+        self
+    }
+
+    /// Run every configured check, returning `(name, result)` pairs.
+    async fn run(&self) -> Vec<(&'static str, Result<(), Error>)> {
+        let mut results = Vec::new();
+
+        #[cfg(feature = "render")]
+        if let Some(config) = &self.render_config {
+            results.push(("render", config.check_chrome()));
+        }
+
+        if let Some(dir) = &self.image_dir {
+            results.push(("image_dir", check_writable(dir).await));
+        }
+
+        #[cfg(feature = "schedule")]
+        if let Some(path) = &self.schedule_path {
+            let loaded = crate::schedule::RefreshSchedule::load(path).map(|_| ());
+            results.push(("schedule", loaded));
+        }
+
+        results
+    }
+}
+
+/// Write and remove a probe file in `dir`.
+async fn check_writable(dir: &std::path::Path) -> Result<(), Error> {
+    tokio::fs::create_dir_all(dir).await?;
+    let probe = dir.join(format!(".trmnl-health-{}", std::process::id()));
+    tokio::fs::write(&probe, b"ok").await?;
+    tokio::fs::remove_file(&probe).await?;
+    Ok(())
+}
+
+/// Routes for container orchestrators such as Kubernetes.
+///
+/// - `GET /healthz` - Liveness: `200 ok` while the server is responding
+/// - `GET /readyz` - Readiness: runs the [`HealthChecks`] and answers `200`
+///   if all pass or `503` otherwise, with a JSON body like
+///   `{"status":"ok","checks":{"render":"ok","image_dir":"failed"}}`.
+///   Why a check failed is logged rather than sent, since errors can name
+///   paths on the server
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::axum_ext::{health_routes, HealthChecks};
+///
+/// let checks = HealthChecks::new()
+///     .with_render_config(RenderConfig::default())
+///     .with_image_dir("/var/lib/trmnl/images");
+/// let app = axum::Router::new()
+///     .merge(health_routes(checks));
+/// ```
+pub fn health_routes<S>(checks: HealthChecks) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let checks = Arc::new(checks);
+    Router::new()
+        .route("/healthz", get(|| async { "ok" }))
+        .route(
+            "/readyz",
+            get(move || async move { readiness(&checks).await }),
+        )
+}
+
+async fn readiness(checks: &HealthChecks) -> (StatusCode, Json<Value>) {
+    let mut ready = true;
+    let mut results = Map::new();
+    for (name, result) in checks.run().await {
+        let value = match result {
+            Ok(()) => "ok",
+            Err(e) => {
+                tracing::warn!("Readiness check {} failed: {}", name, e);
+                ready = false;
+                "failed"
+            }
+        };
+        results.insert(name.to_string(), Value::from(value));
+    }
+
+    let (status, label) = if ready {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+    (
+        status,
+        Json(serde_json::json!({ "status": label, "checks": results })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn get_json(app: Router, uri: &str) -> (StatusCode, Value) {
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_healthz_and_ready() {
+        let dir = std::env::temp_dir().join("trmnl-health-ok");
+        let app = health_routes(HealthChecks::new().with_image_dir(&dir));
+
+        let (status, _) = get_json(app.clone(), "/healthz").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, json) = get_json(app, "/readyz").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["checks"]["image_dir"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_not_ready() {
+        // A regular file can't be used as a directory
+        let file = std::env::temp_dir().join("trmnl-health-file");
+        std::fs::write(&file, b"x").unwrap();
+        let app: Router = health_routes(HealthChecks::new().with_image_dir(&file));
+
+        let (status, json) = get_json(app, "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["status"], "unavailable");
+        assert_eq!(json["checks"]["image_dir"], "failed");
+    }
+
+    #[cfg(feature = "render")]
+    #[tokio::test]
+    async fn test_missing_chrome() {
+        let config = crate::render::RenderConfig::default().with_chrome_path("/nonexistent/chrome");
+        let app: Router = health_routes(HealthChecks::new().with_render_config(config));
+
+        let (status, json) = get_json(app, "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["checks"]["render"], "failed");
+        assert!(!json.to_string().contains("/nonexistent/chrome"));
+    }
+}
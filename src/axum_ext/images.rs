@@ -0,0 +1,306 @@
+//! Serving rendered images to devices.
+
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Arc;
+
+use axum::extract::Path;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, MethodRouter};
+
+use crate::auth::Secret;
+use crate::TokenAuth;
+
+/// Directory of images served by [`serve_image_dir`].
+///
+/// Paths convert into an `ImageDir` with no token and `no-cache`
+/// caching, so `serve_image_dir("/var/trmnl")` works directly.
+#[derive(Debug, Clone)]
+pub struct ImageDir {
+    path: PathBuf,
+    cache_control: String,
+    token: Option<Secret<String>>,
+}
+
+impl ImageDir {
+    /// Serve images from `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            cache_control: "no-cache".to_string(),
+            token: None,
+        }
+    }
+
+    /// Set the `Cache-Control` header (default: `no-cache`).
+    ///
+    /// Filenames change whenever the image does, so long-lived caching
+    /// (e.g. `public, max-age=86400`) is safe behind a CDN.
+    #[must_use]
+    pub fn with_cache_control(mut self, value: impl Into<String>) -> Self {
+        self.cache_control = value.into();
+        self
+    }
+
+    /// Require `?token=<token>` on image URLs.
+    ///
+    /// Include the token in the `image_url` you return to devices.
+    #[must_use]
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(Secret::new(token.into()));
+        self
+    }
+
+    async fn serve(&self, filename: &str, auth: &TokenAuth, headers: &HeaderMap) -> Response {
+        if let Some(expected) = &self.token {
+            if let Err(e) = auth.validate(expected.expose()) {
+                return (StatusCode::UNAUTHORIZED, e.message).into_response();
+            }
+        }
+
+        // Security: prevent path traversal
+        if filename.is_empty() || filename.contains("..") || filename.contains(['/', '\\']) {
+            return (StatusCode::BAD_REQUEST, "Invalid filename").into_response();
+        }
+
+        let path = self.path.join(filename);
+        let is_file = tokio::fs::metadata(&path)
+            .await
+            .map(|m| m.is_file())
+            .unwrap_or(false);
+        if !is_file {
+            return (StatusCode::NOT_FOUND, "Image not found").into_response();
+        }
+
+        match tokio::fs::read(&path).await {
+            Ok(data) => {
+                let etag = etag(&data);
+                if if_none_match(headers, &etag) {
+                    return (
+                        StatusCode::NOT_MODIFIED,
+                        [
+                            (header::ETAG, etag),
+                            (header::CACHE_CONTROL, self.cache_control.clone()),
+                        ],
+                    )
+                        .into_response();
+                }
+                (
+                    [
+                        (header::CONTENT_TYPE, content_type(&path).to_string()),
+                        (header::CONTENT_LENGTH, data.len().to_string()),
+                        (header::CACHE_CONTROL, self.cache_control.clone()),
+                        (header::ETAG, etag),
+                    ],
+                    data,
+                )
+                    .into_response()
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read image {}: {}", path.display(), e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read image").into_response()
+            }
+        }
+    }
+}
+
+impl From<PathBuf> for ImageDir {
+    fn from(path: PathBuf) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<&FsPath> for ImageDir {
+    fn from(path: &FsPath) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<&str> for ImageDir {
+    fn from(path: &str) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<String> for ImageDir {
+    fn from(path: String) -> Self {
+        Self::new(path)
+    }
+}
+
+/// Handler serving files from a directory, for a route with one path
+/// parameter holding the filename.
+///
+/// Rejects filenames containing `..` or path separators, sets
+/// `Content-Type` from the extension (PNG, BMP, JPEG, GIF), and
+/// optionally requires a token (see [`ImageDir::with_token`]).
+///
+/// Responses carry an `ETag` derived from the file contents; requests whose
+/// `If-None-Match` matches get `304 Not Modified` without the image.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::axum_ext::{serve_image_dir, ImageDir};
+///
+/// let app = axum::Router::new()
+///     .route("/images/{filename}", serve_image_dir("/var/lib/trmnl/images"))
+///     .route(
+///         "/private/{filename}",
+///         serve_image_dir(ImageDir::new("/var/lib/trmnl/private").with_token("secret")),
+///     );
+/// ```
+pub fn serve_image_dir<S>(dir: impl Into<ImageDir>) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let dir = Arc::new(dir.into());
+    get(
+        move |Path(filename): Path<String>, auth: TokenAuth, headers: HeaderMap| async move {
+            dir.serve(&filename, &auth, &headers).await
+        },
+    )
+}
+
+/// 64-bit FNV-1a hash, stable across Rust versions and restarts so stored
+/// filenames and ETags stay valid. Shared by [`etag`] and `byos_router`'s
+/// image filenames.
+pub(crate) fn content_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Strong ETag from the [`content_hash`] of the contents.
+fn etag(data: &[u8]) -> String {
+    format!("\"{:016x}\"", content_hash(data))
+}
+
+/// Whether `If-None-Match` lists `etag` (or `*`), ignoring weak prefixes.
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+/// MIME type for an image path, by extension.
+fn content_type(path: &FsPath) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("bmp") => "image/bmp",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    fn image_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join("trmnl-serve-image-dir");
+        std::fs::create_dir_all(dir.join("subdir")).unwrap();
+        std::fs::write(dir.join("1.png"), b"png-bytes").unwrap();
+        std::fs::write(dir.join("1.bmp"), b"bmp").unwrap();
+        dir
+    }
+
+    async fn status(app: Router, uri: &str) -> Response {
+        app.oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_serves_with_headers() {
+        let app = Router::new().route(
+            "/images/{filename}",
+            serve_image_dir(ImageDir::new(image_dir()).with_cache_control("max-age=60")),
+        );
+
+        let response = status(app.clone(), "/images/1.png").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(headers[header::CONTENT_TYPE], "image/png");
+        assert_eq!(headers[header::CONTENT_LENGTH], "9");
+        assert_eq!(headers[header::CACHE_CONTROL], "max-age=60");
+
+        let response = status(app, "/images/1.bmp").await;
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/bmp");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_bad_paths() {
+        let app = Router::new().route("/images/{filename}", serve_image_dir(image_dir()));
+
+        for (uri, expected) in [
+            ("/images/missing.png", StatusCode::NOT_FOUND),
+            ("/images/subdir", StatusCode::NOT_FOUND),
+            ("/images/..%2F..%2Fetc%2Fpasswd", StatusCode::BAD_REQUEST),
+            ("/images/..", StatusCode::BAD_REQUEST),
+        ] {
+            assert_eq!(status(app.clone(), uri).await.status(), expected, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_etag_not_modified() {
+        let app = Router::new().route("/images/{filename}", serve_image_dir(image_dir()));
+
+        let response = status(app.clone(), "/images/1.png").await;
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
+
+        for if_none_match in [etag.clone(), format!("\"other\", W/{}", etag)] {
+            let request = Request::get("/images/1.png")
+                .header(header::IF_NONE_MATCH, if_none_match)
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(response.headers()[header::ETAG], etag.as_str());
+        }
+
+        let request = Request::get("/images/1.bmp")
+            .header(header::IF_NONE_MATCH, etag)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_token() {
+        let app = Router::new().route(
+            "/images/{filename}",
+            serve_image_dir(ImageDir::new(image_dir()).with_token("s3cret")),
+        );
+
+        let missing = status(app.clone(), "/images/1.png").await;
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+        let ok = status(app, "/images/1.png?token=s3cret").await;
+        assert_eq!(ok.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_debug_redacts_token() {
+        let dir = ImageDir::new("/tmp").with_token("s3cret");
+        assert!(!format!("{dir:?}").contains("s3cret"));
+    }
+}
@@ -0,0 +1,305 @@
+//! Stock `/api/log` handling with pluggable destinations.
+
+use std::borrow::Cow;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::body::{Body, Bytes};
+use axum::http::request::Parts;
+use axum::http::Request;
+use axum::routing::{post, MethodRouter};
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+use crate::error::Error;
+use crate::http_ext::handle_log;
+#[cfg(feature = "privacy")]
+use crate::privacy::IdObfuscator;
+use crate::{DeviceInfo, LogEntry};
+
+/// Destination for device logs received by [`log_handler`].
+///
+/// Implemented by [`TracingSink`], [`FileSink`], tokio channel senders of
+/// `(DeviceInfo, LogEntry)`, and closures taking a [`DeviceInfo`] and the
+/// entries and returning a future of `Result<(), Error>` (e.g. to insert
+/// into a database).
+pub trait LogSink: Send + Sync + 'static {
+    /// Store the entries one device sent in a single request.
+    fn record(
+        &self,
+        device: &DeviceInfo,
+        entries: Vec<LogEntry>,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+impl<F, Fut> LogSink for F
+where
+    F: Fn(DeviceInfo, Vec<LogEntry>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), Error>> + Send,
+{
+    fn record(
+        &self,
+        device: &DeviceInfo,
+        entries: Vec<LogEntry>,
+    ) -> impl Future<Output = Result<(), Error>> + Send {
+        self(device.clone(), entries)
+    }
+}
+
+/// How device MAC addresses appear in logs and stored records: as sent,
+/// or passed through an [`IdObfuscator`] with the `privacy` feature.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DeviceIdMask {
+    #[cfg(feature = "privacy")]
+    obfuscator: Option<Arc<dyn IdObfuscator>>,
+}
+
+impl DeviceIdMask {
+    #[cfg(feature = "privacy")]
+    pub(crate) fn new(obfuscator: impl IdObfuscator + 'static) -> Self {
+        Self {
+            obfuscator: Some(Arc::new(obfuscator)),
+        }
+    }
+
+    /// The ID to record for `mac_address`.
+    pub(crate) fn apply<'a>(&self, mac_address: &'a str) -> Cow<'a, str> {
+        #[cfg(feature = "privacy")]
+        if let Some(obfuscator) = &self.obfuscator {
+            return Cow::Owned(obfuscator.obfuscate(mac_address));
+        }
+        Cow::Borrowed(mac_address)
+    }
+}
+
+/// Emits each entry as a `tracing` event at `info` level.
+#[derive(Debug, Clone, Default)]
+pub struct TracingSink {
+    ids: DeviceIdMask,
+}
+
+impl TracingSink {
+    /// Log entries with the device's MAC address.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(feature = "render")]
+    pub(crate) fn masked(ids: DeviceIdMask) -> Self {
+        Self { ids }
+    }
+
+    /// Log `obfuscator`'s ID for each device instead of its MAC address.
+    #[cfg(feature = "privacy")]
+    pub fn with_obfuscator(mut self, obfuscator: impl IdObfuscator + 'static) -> Self {
+        self.ids = DeviceIdMask::new(obfuscator);
+        self
+    }
+}
+
+impl LogSink for TracingSink {
+    async fn record(&self, device: &DeviceInfo, entries: Vec<LogEntry>) -> Result<(), Error> {
+        let device_id = self.ids.apply(&device.mac_address);
+        for entry in &entries {
+            tracing::info!(
+                device = %device_id,
+                message = entry.log_message.as_deref().unwrap_or_default(),
+                "Device log"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Appends entries to a file as JSON lines, each with a `device` field
+/// holding the MAC address (or its obfuscated ID).
+#[derive(Debug, Clone)]
+pub struct FileSink {
+    path: PathBuf,
+    ids: DeviceIdMask,
+}
+
+impl FileSink {
+    /// Append to `path`, creating it if needed.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            ids: DeviceIdMask::default(),
+        }
+    }
+
+    /// Store `obfuscator`'s ID for each device instead of its MAC address.
+    #[cfg(feature = "privacy")]
+    pub fn with_obfuscator(mut self, obfuscator: impl IdObfuscator + 'static) -> Self {
+        self.ids = DeviceIdMask::new(obfuscator);
+        self
+    }
+}
+
+#[derive(Serialize)]
+struct FileLine<'a> {
+    device: &'a str,
+    #[serde(flatten)]
+    entry: &'a LogEntry,
+}
+
+impl LogSink for FileSink {
+    async fn record(&self, device: &DeviceInfo, entries: Vec<LogEntry>) -> Result<(), Error> {
+        let device_id = self.ids.apply(&device.mac_address);
+        let mut lines = String::new();
+        for entry in &entries {
+            let line = FileLine {
+                device: &device_id,
+                entry,
+            };
+            lines.push_str(&serde_json::to_string(&line)?);
+            lines.push('\n');
+        }
+
+        // One write per request keeps concurrent requests from interleaving
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(lines.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+impl LogSink for mpsc::Sender<(DeviceInfo, LogEntry)> {
+    async fn record(&self, device: &DeviceInfo, entries: Vec<LogEntry>) -> Result<(), Error> {
+        for entry in entries {
+            self.send((device.clone(), entry))
+                .await
+                .map_err(|_| Error::Io("log channel closed".to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Stock `POST /api/log` handler passing parsed entries to `sink`.
+///
+/// Built on [`handle_log`](crate::http_ext::handle_log): malformed bodies
+/// get `400 Bad Request`, and otherwise the device always gets a success
+/// response. Sink failures are logged with `tracing` (without the device's
+/// MAC address) rather than making the firmware resend its logs.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::axum_ext::{log_handler, FileSink};
+///
+/// let app = axum::Router::new()
+///     .route("/api/log", log_handler(FileSink::new("/var/log/trmnl.jsonl")));
+/// ```
+pub fn log_handler<S>(sink: impl LogSink) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let sink = Arc::new(sink);
+    post(move |parts: Parts, body: Bytes| async move {
+        let request = Request::from_parts(parts, body);
+        handle_log(&request, |device, payload| async move {
+            sink.record(&device, payload.logs).await
+        })
+        .await
+        .map(Body::from)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use axum::Router;
+    use tower::ServiceExt;
+
+    async fn post_logs(app: Router) -> StatusCode {
+        let request = Request::post("/api/log")
+            .header("ID", "aa:bb:cc:dd:ee:ff")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                r#"{"logs":[{"log_message":"one"},{"log_message":"two"}]}"#,
+            ))
+            .unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_channel_sink() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let app = Router::new().route("/api/log", log_handler(tx));
+        assert_eq!(post_logs(app).await, StatusCode::OK);
+
+        let (device, entry) = rx.recv().await.unwrap();
+        assert_eq!(device.mac_address, "aa:bb:cc:dd:ee:ff");
+        assert_eq!(entry.log_message.as_deref(), Some("one"));
+        assert_eq!(
+            rx.recv().await.unwrap().1.log_message.as_deref(),
+            Some("two")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_sink() {
+        let path = std::env::temp_dir().join("trmnl-log-sink.jsonl");
+        let _ = std::fs::remove_file(&path);
+        let app = Router::new().route("/api/log", log_handler(FileSink::new(&path)));
+        assert_eq!(post_logs(app).await, StatusCode::OK);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["device"], "aa:bb:cc:dd:ee:ff");
+        assert_eq!(lines[1]["log_message"], "two");
+    }
+
+    #[cfg(feature = "privacy")]
+    #[tokio::test]
+    async fn test_file_sink_obfuscates_device() {
+        use crate::privacy::{IdObfuscator, SaltedHash};
+
+        let path = std::env::temp_dir().join("trmnl-log-sink-obfuscated.jsonl");
+        let _ = std::fs::remove_file(&path);
+        let sink = FileSink::new(&path).with_obfuscator(SaltedHash::new("salt"));
+        let app = Router::new().route("/api/log", log_handler(sink));
+        assert_eq!(post_logs(app).await, StatusCode::OK);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("aa:bb:cc:dd:ee:ff"));
+        let line: serde_json::Value =
+            serde_json::from_str(contents.lines().next().unwrap()).unwrap();
+        assert_eq!(
+            line["device"],
+            SaltedHash::new("salt").obfuscate("aa:bb:cc:dd:ee:ff")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_malformed_body_rejected() {
+        let app = Router::new().route("/api/log", log_handler(TracingSink::new()));
+        let request = Request::post("/api/log")
+            .header("ID", "aa:bb:cc:dd:ee:ff")
+            .body(Body::from("nope"))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_sink_failure_still_acknowledged() {
+        let sink = |_device: DeviceInfo, _entries: Vec<LogEntry>| async {
+            Err(Error::Io("database down".to_string()))
+        };
+        let app = Router::new().route("/api/log", log_handler(sink));
+        assert_eq!(post_logs(app).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,306 @@
+//! Axum integration for TRMNL BYOS servers.
+//!
+//! Provides extractors to easily get device info from requests.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use axum::{Router, routing::get};
+//! use trmnl::{DisplayResponse, DeviceInfo};
+//!
+//! async fn display(device: DeviceInfo) -> axum::Json<DisplayResponse> {
+//!     tracing::info!("Device {} requesting display", device.mac_address);
+//!
+//!     axum::Json(DisplayResponse::new(
+//!         "https://example.com/screen.png",
+//!         "screen.png",
+//!     ))
+//! }
+//!
+//! let app = Router::new()
+//!     .route("/api/display", get(display));
+//! ```
+//!
+//! With the `render` feature, [`byos_router`] provides a complete server
+//! from a single function producing each device's HTML.
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+
+use crate::http_ext::BaseUrl;
+use crate::DeviceInfo;
+
+mod auth_layer;
+mod credentials;
+mod error;
+mod health;
+mod images;
+mod logs;
+#[cfg(feature = "render")]
+mod router;
+mod setup;
+mod trace;
+
+#[cfg(feature = "jwt")]
+pub use auth_layer::{JwtAuthLayer, JwtAuthService};
+pub use auth_layer::{TokenAuthLayer, TokenAuthService};
+pub use credentials::{
+    CredentialStore, DeviceCredentials, DeviceCredentialsLayer, DeviceCredentialsService,
+    MemoryCredentialStore,
+};
+pub use error::DisplayError;
+pub use health::{health_routes, HealthChecks};
+pub use images::{serve_image_dir, ImageDir};
+pub use logs::{log_handler, FileSink, LogSink, TracingSink};
+pub use setup::{setup_handler, SetupDecision};
+pub use trace::{DeviceTraceLayer, DeviceTraceService};
+
+#[cfg(feature = "render")]
+pub use router::{byos_router, display_handler, ByosState, DisplayProvider, Screen};
+
+/// Extract device info from request headers.
+///
+/// This extractor reads TRMNL firmware headers:
+/// - `ID`: Device MAC address (required)
+/// - `Battery-Voltage`: Battery voltage
+/// - `FW-Version`: Firmware version
+/// - `RSSI`: WiFi signal strength
+/// - `Refresh-Rate`: Current refresh rate
+/// - `Access-Token`: API key issued during setup
+/// - `User-Agent`: Firmware HTTP client identifier
+/// - `Width` / `Height`: Reported panel resolution
+/// - `Model`: Device model hint
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::DeviceInfo;
+///
+/// async fn handler(device: DeviceInfo) {
+///     println!("MAC: {}", device.mac_address);
+///     if let Some(pct) = device.battery_percentage() {
+///         println!("Battery: {}%", pct);
+///     }
+/// }
+/// ```
+impl<S> FromRequestParts<S> for DeviceInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(DeviceInfo::from_headers(&parts.headers))
+    }
+}
+
+/// Device info extractor that requires the `ID` header.
+///
+/// [`DeviceInfo`] falls back to `"unknown"` when `ID` is missing, which
+/// hides reverse proxies that strip custom headers. This extractor rejects
+/// such requests with `400 Bad Request` instead.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::axum_ext::RequiredDeviceInfo;
+///
+/// async fn handler(RequiredDeviceInfo(device): RequiredDeviceInfo) {
+///     println!("MAC: {}", device.mac_address);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequiredDeviceInfo(pub DeviceInfo);
+
+impl std::ops::Deref for RequiredDeviceInfo {
+    type Target = DeviceInfo;
+
+    fn deref(&self) -> &DeviceInfo {
+        &self.0
+    }
+}
+
+impl<S> FromRequestParts<S> for RequiredDeviceInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let has_id = parts
+            .headers
+            .get("ID")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|s| !s.trim().is_empty());
+        if !has_id {
+            return Err((StatusCode::BAD_REQUEST, "Missing ID header"));
+        }
+        DeviceInfo::from_request_parts(parts, state)
+            .await
+            .map(RequiredDeviceInfo)
+    }
+}
+
+/// Marker that lets the [`BaseUrl`] extractor trust `X-Forwarded-Proto`
+/// and `X-Forwarded-Host`.
+///
+/// Add it with `axum::Extension` only when a reverse proxy sets or strips
+/// those headers; otherwise any client can choose the host its image URLs
+/// point at.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrustForwardedHeaders;
+
+/// Extract the server's public base URL.
+///
+/// Uses a [`BaseUrl`] added with `axum::Extension` if present (to override
+/// detection). Otherwise uses [`BaseUrl::from_headers`] (the `Host` header),
+/// or [`BaseUrl::from_forwarded_headers`] if the [`TrustForwardedHeaders`]
+/// extension is present. Rejects with `400 Bad Request` if no valid host is
+/// found.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::axum_ext::TrustForwardedHeaders;
+/// use trmnl::http_ext::BaseUrl;
+///
+/// async fn display(base: BaseUrl) -> Json<DisplayResponse> {
+///     Json(DisplayResponse::new(base.join("/images/1.png"), "1.png"))
+/// }
+///
+/// // Either pin the URL...
+/// let app = app.layer(axum::Extension(BaseUrl::new("https://trmnl.example.com")));
+/// // ...or, behind a proxy that sets them, trust X-Forwarded-* headers
+/// let app = app.layer(axum::Extension(TrustForwardedHeaders));
+/// ```
+impl<S> FromRequestParts<S> for BaseUrl
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(base) = parts.extensions.get::<BaseUrl>() {
+            return Ok(base.clone());
+        }
+        let base = if parts.extensions.get::<TrustForwardedHeaders>().is_some() {
+            BaseUrl::from_forwarded_headers(&parts.headers)
+        } else {
+            BaseUrl::from_headers(&parts.headers)
+        };
+        base.ok_or((StatusCode::BAD_REQUEST, "Missing or invalid Host header"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    #[tokio::test]
+    async fn test_device_info_extractor() {
+        let request = Request::builder()
+            .header("ID", "AA:BB:CC:DD:EE:FF")
+            .header("Battery-Voltage", "4.2")
+            .header("FW-Version", "1.0.0")
+            .header("RSSI", "-50")
+            .header("Refresh-Rate", "60")
+            .header("Access-Token", "abc123")
+            .header("User-Agent", "ESP32HTTPClient")
+            .header("Width", "800")
+            .header("Height", "480")
+            .header("Model", "og")
+            .body(())
+            .unwrap();
+
+        let (mut parts, _body) = request.into_parts();
+        let device = DeviceInfo::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+
+        assert_eq!(device.mac_address, "AA:BB:CC:DD:EE:FF");
+        assert_eq!(device.battery_voltage, Some(4.2));
+        assert_eq!(device.firmware_version, Some("1.0.0".to_string()));
+        assert_eq!(device.rssi, Some(-50));
+        assert_eq!(device.refresh_rate, Some(60));
+        assert_eq!(
+            device.access_token.as_ref().map(|t| t.expose().as_str()),
+            Some("abc123")
+        );
+        assert_eq!(device.user_agent, Some("ESP32HTTPClient".to_string()));
+        assert_eq!(device.width, Some(800));
+        assert_eq!(device.height, Some(480));
+        assert_eq!(device.model, Some("og".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_device_info_missing_headers() {
+        let request = Request::builder().body(()).unwrap();
+
+        let (mut parts, _body) = request.into_parts();
+        let device = DeviceInfo::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+
+        assert_eq!(device.mac_address, "unknown");
+        assert_eq!(device.battery_voltage, None);
+    }
+
+    #[tokio::test]
+    async fn test_required_device_info() {
+        let request = Request::builder()
+            .header("ID", "AA:BB:CC:DD:EE:FF")
+            .body(())
+            .unwrap();
+        let (mut parts, _body) = request.into_parts();
+        let device = RequiredDeviceInfo::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        assert_eq!(device.mac_address, "AA:BB:CC:DD:EE:FF");
+
+        for request in [
+            Request::builder().body(()).unwrap(),
+            Request::builder().header("ID", " ").body(()).unwrap(),
+        ] {
+            let (mut parts, _body) = request.into_parts();
+            let rejection = RequiredDeviceInfo::from_request_parts(&mut parts, &())
+                .await
+                .unwrap_err();
+            assert_eq!(rejection.0, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_base_url_extractor() {
+        let request = Request::builder()
+            .header("Host", "localhost:3000")
+            .body(())
+            .unwrap();
+        let (mut parts, _body) = request.into_parts();
+        let base = BaseUrl::from_request_parts(&mut parts, &()).await.unwrap();
+        assert_eq!(base.as_str(), "http://localhost:3000");
+
+        parts
+            .headers
+            .insert("X-Forwarded-Proto", "https".parse().unwrap());
+        parts
+            .headers
+            .insert("X-Forwarded-Host", "evil.example".parse().unwrap());
+        let base = BaseUrl::from_request_parts(&mut parts, &()).await.unwrap();
+        assert_eq!(base.as_str(), "http://localhost:3000");
+
+        parts.extensions.insert(TrustForwardedHeaders);
+        let base = BaseUrl::from_request_parts(&mut parts, &()).await.unwrap();
+        assert_eq!(base.as_str(), "https://evil.example");
+
+        parts
+            .extensions
+            .insert(BaseUrl::new("https://trmnl.example.com/"));
+        let base = BaseUrl::from_request_parts(&mut parts, &()).await.unwrap();
+        assert_eq!(base.as_str(), "https://trmnl.example.com");
+
+        let (mut parts, _body) = Request::builder().body(()).unwrap().into_parts();
+        assert!(BaseUrl::from_request_parts(&mut parts, &()).await.is_err());
+    }
+}
@@ -0,0 +1,743 @@
+//! Drop-in BYOS server routes.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::http::HeaderMap;
+use axum::routing::{get, MethodRouter};
+use axum::{Json, Router};
+
+use super::images::content_hash;
+use super::logs::DeviceIdMask;
+use super::{log_handler, serve_image_dir, setup_handler, SetupDecision, TracingSink};
+use crate::error::Error;
+use crate::http_ext::BaseUrl;
+use crate::negotiate::ImageFormat;
+#[cfg(feature = "privacy")]
+use crate::privacy::IdObfuscator;
+use crate::render::{render_html_to_png, RenderConfig};
+use crate::{DeviceInfo, DisplayResponse, MacAddress, RefreshRate};
+
+/// What to show on a device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Screen {
+    /// HTML to render with the state's [`RenderConfig`]
+    Html(String),
+    /// An already rendered image, stored as-is
+    Image {
+        /// Encoded image bytes
+        data: Vec<u8>,
+        /// Format of `data`, used for the stored file's extension
+        format: ImageFormat,
+    },
+}
+
+impl Screen {
+    /// A pre-rendered PNG.
+    pub fn png(data: impl Into<Vec<u8>>) -> Self {
+        Screen::Image {
+            data: data.into(),
+            format: ImageFormat::Png,
+        }
+    }
+}
+
+impl From<String> for Screen {
+    fn from(html: String) -> Self {
+        Screen::Html(html)
+    }
+}
+
+impl From<&str> for Screen {
+    fn from(html: &str) -> Self {
+        Screen::Html(html.to_string())
+    }
+}
+
+/// Produces the screen for a device.
+///
+/// This is the only part of a BYOS server that differs between
+/// deployments. Closures taking a [`DeviceInfo`] and returning a future of
+/// `Result<T, Error>`, where `T` converts into a [`Screen`] (such as an
+/// HTML `String`), implement this trait.
+pub trait DisplayProvider: Send + Sync + 'static {
+    /// Build the screen to show on `device`.
+    fn screen(&self, device: &DeviceInfo) -> impl Future<Output = Result<Screen, Error>> + Send;
+}
+
+impl<F, Fut, T> DisplayProvider for F
+where
+    F: Fn(DeviceInfo) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, Error>> + Send,
+    T: Into<Screen>,
+{
+    fn screen(&self, device: &DeviceInfo) -> impl Future<Output = Result<Screen, Error>> + Send {
+        let screen = self(device.clone());
+        async move { screen.await.map(Into::into) }
+    }
+}
+
+/// Configuration and provider for [`byos_router`].
+#[derive(Debug)]
+pub struct ByosState<P> {
+    provider: P,
+    base_url: Option<BaseUrl>,
+    trust_forwarded_headers: bool,
+    image_dir: PathBuf,
+    render_config: RenderConfig,
+    refresh_rate: RefreshRate,
+    /// Limits concurrent renders when set
+    render_permits: Option<Arc<tokio::sync::Semaphore>>,
+    ids: DeviceIdMask,
+    #[cfg(feature = "preview")]
+    live_preview: Option<crate::preview::LivePreview>,
+    /// Images stored for each device. Held while files are written or
+    /// deleted, so deciding a file is unused and deleting it can't race
+    /// with another request storing it again.
+    device_files: tokio::sync::Mutex<HashMap<MacAddress, DeviceFiles>>,
+}
+
+/// Images stored for one device.
+#[derive(Debug, Default)]
+struct DeviceFiles {
+    /// Image in the latest response
+    current: Option<String>,
+    /// Image in the response before it, kept because an overlapping request
+    /// from the device may still have handed out its URL
+    previous: Option<String>,
+}
+
+impl DeviceFiles {
+    fn references(&self, filename: &str) -> bool {
+        self.current.as_deref() == Some(filename) || self.previous.as_deref() == Some(filename)
+    }
+}
+
+impl<P: DisplayProvider> ByosState<P> {
+    /// Create state for a server reachable at `base_url` (e.g.
+    /// `"http://192.168.1.10:3000"`), used to build image URLs.
+    pub fn new(provider: P, base_url: impl Into<String>) -> Self {
+        Self {
+            provider,
+            base_url: Some(BaseUrl::new(base_url)),
+            trust_forwarded_headers: false,
+            image_dir: PathBuf::from("/tmp/trmnl-images"),
+            render_config: RenderConfig::default(),
+            refresh_rate: RefreshRate::default(),
+            render_permits: None,
+            ids: DeviceIdMask::default(),
+            #[cfg(feature = "preview")]
+            live_preview: None,
+            device_files: tokio::sync::Mutex::default(),
+        }
+    }
+
+    /// Create state that derives the base URL from each request's `Host`
+    /// header (see [`BaseUrl::from_headers`]).
+    pub fn detect_base_url(provider: P) -> Self {
+        Self {
+            base_url: None,
+            ..Self::new(provider, "")
+        }
+    }
+
+    /// Prefer `X-Forwarded-Proto` and `X-Forwarded-Host` when detecting the
+    /// base URL (see [`BaseUrl::from_forwarded_headers`]).
+    ///
+    /// Only use this behind a proxy that sets or strips those headers, as
+    /// clients control them. Has no effect with a fixed base URL.
+    #[must_use]
+    pub fn trust_forwarded_headers(mut self) -> Self {
+        self.trust_forwarded_headers = true;
+        self
+    }
+
+    /// Set the directory rendered images are stored in and served from
+    /// (default: "/tmp/trmnl-images").
+    #[must_use]
+    pub fn with_image_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.image_dir = path.into();
+        self
+    }
+
+    /// Set the render configuration.
+    #[must_use]
+    pub fn with_render_config(mut self, config: RenderConfig) -> Self {
+        self.render_config = config;
+        self
+    }
+
+    /// Set the refresh rate returned to devices (default: 60 seconds).
+    #[must_use]
+    pub fn with_refresh_rate(mut self, rate: impl Into<RefreshRate>) -> Self {
+        self.refresh_rate = rate.into();
+        self
+    }
+
+    /// Render at most `limit` screens at once (default: no limit).
+    ///
+    /// Devices that ask for a screen while `limit` renders are running get
+    /// [`DisplayResponse::busy`]: they keep their current image and retry
+    /// shortly instead of waiting on a saturated server. Devices without an
+    /// image yet get [`DisplayResponse::error`].
+    #[must_use]
+    pub fn with_max_concurrent_renders(mut self, limit: usize) -> Self {
+        self.render_permits = Some(Arc::new(tokio::sync::Semaphore::new(limit)));
+        self
+    }
+
+    /// Log `obfuscator`'s ID for each device instead of its MAC address,
+    /// including in the device logs [`byos_router`] records.
+    #[cfg(feature = "privacy")]
+    #[must_use]
+    pub fn with_obfuscator(mut self, obfuscator: impl IdObfuscator + 'static) -> Self {
+        self.ids = DeviceIdMask::new(obfuscator);
+        self
+    }
+
+    /// Push every image sent to a device to `live` (see
+    /// [`LivePreview`](crate::preview::LivePreview)).
+    #[cfg(feature = "preview")]
+    #[must_use]
+    pub fn with_live_preview(mut self, live: crate::preview::LivePreview) -> Self {
+        self.live_preview = Some(live);
+        self
+    }
+
+    /// Render the device's screen and store it, returning the filename.
+    ///
+    /// Filenames are the device's full MAC address and a hash of the image,
+    /// so a changed screen gets a new URL and caches never serve a stale
+    /// one, while an unchanged screen keeps its filename and the firmware
+    /// skips the e-ink refresh. Each device keeps its last two images; older
+    /// ones are deleted unless another device's entry still names them.
+    async fn render_screen(&self, device: &DeviceInfo, mac: &MacAddress) -> Result<String, Error> {
+        let (image, format) = match self.provider.screen(device).await? {
+            Screen::Html(html) => (
+                render_html_to_png(&html, &self.render_config).await?,
+                self.render_config.format,
+            ),
+            Screen::Image { data, format } => (data, format),
+        };
+
+        let filename = format!(
+            "{}-{:016x}.{}",
+            mac.to_string().replace(':', "").to_lowercase(),
+            content_hash(&image),
+            format.extension()
+        );
+
+        let mut device_files = self.device_files.lock().await;
+        tokio::fs::create_dir_all(&self.image_dir).await?;
+        tokio::fs::write(self.image_dir.join(&filename), &image).await?;
+
+        let files = device_files.entry(*mac).or_default();
+        let stale = if files.current.as_deref() == Some(filename.as_str()) {
+            None
+        } else {
+            let current = files.current.replace(filename.clone());
+            std::mem::replace(&mut files.previous, current)
+        };
+        if let Some(stale) = stale {
+            if !device_files.values().any(|files| files.references(&stale)) {
+                if let Err(e) = tokio::fs::remove_file(self.image_dir.join(&stale)).await {
+                    tracing::debug!("Failed to remove old image {}: {}", stale, e);
+                }
+            }
+        }
+        drop(device_files);
+
+        #[cfg(feature = "preview")]
+        if let Some(live) = &self.live_preview {
+            live.publish(&device.mac_address, &filename, &image, format);
+        }
+        Ok(filename)
+    }
+
+    /// Response keeping the device on its current image while renders are
+    /// saturated.
+    async fn busy_response(&self, mac: &MacAddress, base_url: &BaseUrl) -> DisplayResponse {
+        let device_files = self.device_files.lock().await;
+        match device_files
+            .get(mac)
+            .and_then(|files| files.current.as_ref())
+        {
+            Some(filename) => {
+                DisplayResponse::busy(base_url.join(&format!("images/{}", filename)), filename)
+            }
+            None => DisplayResponse::error(),
+        }
+    }
+}
+
+/// Build a complete BYOS server.
+///
+/// - `GET /api/setup` - Registers the device with a friendly ID derived
+///   from its MAC address and a random API key. The key is neither stored
+///   nor checked on later requests; use [`DeviceCredentials`](super::DeviceCredentials)
+///   to enforce it, or [`setup_handler`] to persist or restrict
+///   registrations
+/// - `GET /api/display` - Calls the provider and returns a
+///   [`DisplayResponse`] pointing at the stored image (see
+///   [`display_handler`])
+/// - `POST /api/log` - Logs device telemetry via `tracing` (see
+///   [`log_handler`])
+/// - `GET /images/{filename}` - Serves stored images
+///
+/// Render failures and provider panics are logged and answered with
+/// [`DisplayResponse::error`], so devices retry later instead of showing a
+/// broken image; so are requests without a valid MAC address in the `ID`
+/// header. Each device keeps its last two images; a screen that hasn't
+/// changed keeps its filename, so the device doesn't redraw it. See
+/// [`ByosState::with_max_concurrent_renders`] for shedding load.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::axum_ext::{byos_router, ByosState};
+/// use trmnl::DeviceInfo;
+///
+/// async fn screen(device: DeviceInfo) -> Result<String, trmnl::Error> {
+///     Ok(format!("<h1>Hello, {}!</h1>", device.short_id()))
+/// }
+///
+/// let app = byos_router(ByosState::new(screen, "http://192.168.1.10:3000"));
+/// let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
+/// axum::serve(listener, app).await?;
+/// ```
+pub fn byos_router<S, P>(state: ByosState<P>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    P: DisplayProvider,
+{
+    let image_dir = state.image_dir.clone();
+    let logs = TracingSink::masked(state.ids.clone());
+    Router::new()
+        .route(
+            "/api/setup",
+            setup_handler(|device: DeviceInfo| async move { Ok(SetupDecision::register(&device)) }),
+        )
+        .route("/api/display", display_handler(state))
+        .route("/api/log", log_handler(logs))
+        .route("/images/{filename}", serve_image_dir(image_dir))
+}
+
+/// Stock `GET /api/display` handler.
+///
+/// Calls the provider, renders HTML screens, stores the image under a
+/// filename derived from its contents and returns a [`DisplayResponse`] pointing at
+/// `{base_url}/images/{filename}`. Serve the image directory at that path
+/// (see [`serve_image_dir`]). Failures are logged and answered with
+/// [`DisplayResponse::error`]; the provider runs in its own task, so a
+/// panic in it is answered the same way.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::axum_ext::{display_handler, serve_image_dir, ByosState};
+///
+/// let state = ByosState::new(screen, "http://192.168.1.10:3000")
+///     .with_image_dir("/var/lib/trmnl");
+/// let app = axum::Router::new()
+///     .route("/api/display", display_handler(state))
+///     .route("/images/{filename}", serve_image_dir("/var/lib/trmnl"));
+/// ```
+pub fn display_handler<S, P>(state: impl Into<Arc<ByosState<P>>>) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+    P: DisplayProvider,
+{
+    let state = state.into();
+    get(move |device: DeviceInfo, headers: HeaderMap| async move {
+        display(state, device, &headers).await
+    })
+}
+
+async fn display<P: DisplayProvider>(
+    state: Arc<ByosState<P>>,
+    device: DeviceInfo,
+    headers: &HeaderMap,
+) -> Json<DisplayResponse> {
+    let detected = || {
+        if state.trust_forwarded_headers {
+            BaseUrl::from_forwarded_headers(headers)
+        } else {
+            BaseUrl::from_headers(headers)
+        }
+    };
+    let device_id = state.ids.apply(&device.mac_address);
+    let Some(base_url) = state.base_url.clone().or_else(detected) else {
+        tracing::warn!(
+            "Display for {} failed: missing or invalid Host header",
+            device_id
+        );
+        return Json(DisplayResponse::error());
+    };
+    let Some(mac) = device.mac() else {
+        tracing::warn!("Display request without a valid ID header");
+        return Json(DisplayResponse::error());
+    };
+
+    let permit = match &state.render_permits {
+        Some(permits) => match permits.clone().try_acquire_owned() {
+            Ok(permit) => Some(permit),
+            Err(_) => {
+                tracing::info!("Renders saturated, asking {} to retry", device_id);
+                return Json(state.busy_response(&mac, &base_url).await);
+            }
+        },
+        None => None,
+    };
+
+    // The provider is user code; run it in its own task so a panic is
+    // answered like any other failure instead of dropping the connection.
+    // The task holds the permit, so a render counts until it finishes even
+    // if the device disconnects.
+    let render = tokio::spawn({
+        let state = state.clone();
+        let device = device.clone();
+        async move {
+            let _permit = permit;
+            state.render_screen(&device, &mac).await
+        }
+    });
+    match render.await {
+        Ok(Ok(filename)) => {
+            let image_url = base_url.join(&format!("images/{}", filename));
+            Json(DisplayResponse::new(image_url, filename).with_refresh_rate(state.refresh_rate))
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("Display for {} failed: {}", device_id, e);
+            Json(DisplayResponse::error())
+        }
+        Err(e) => {
+            tracing::error!("Display for {} panicked: {}", device_id, e);
+            Json(DisplayResponse::error())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, StatusCode};
+    use tower::ServiceExt;
+
+    fn router(image_dir: &str) -> Router {
+        let render_config = RenderConfig::default()
+            .with_chrome_path("/nonexistent/chrome")
+            .with_temp_dir(std::env::temp_dir().join("trmnl-router-test"));
+        let state = ByosState::new(
+            |_device: DeviceInfo| async { Ok("<p>hi</p>".to_string()) },
+            "http://localhost:3000/",
+        )
+        .with_image_dir(std::env::temp_dir().join(image_dir))
+        .with_render_config(render_config);
+        byos_router(state)
+    }
+
+    async fn get_json(app: Router, request: Request<Body>) -> serde_json::Value {
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_setup() {
+        let request = Request::get("/api/setup")
+            .header("ID", "aa:bb:cc:dd:ee:ff")
+            .body(Body::empty())
+            .unwrap();
+        let json = get_json(router("trmnl-router-setup"), request).await;
+        assert_eq!(json["status"], 200);
+        assert_eq!(json["friendly_id"], "DDEEFF");
+
+        let request = Request::get("/api/setup").body(Body::empty()).unwrap();
+        let json = get_json(router("trmnl-router-setup"), request).await;
+        assert_eq!(json["status"], 404);
+    }
+
+    #[tokio::test]
+    async fn test_display_render_failure_returns_error_response() {
+        let request = Request::get("/api/display")
+            .header("ID", "aa:bb:cc:dd:ee:ff")
+            .body(Body::empty())
+            .unwrap();
+        let json = get_json(router("trmnl-router-display"), request).await;
+        assert_eq!(json["status"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_display_provider_panic_returns_error_response() {
+        let state = ByosState::new(
+            |_device: DeviceInfo| async { panic!("provider bug") as Result<Screen, Error> },
+            "http://localhost:3000",
+        )
+        .with_image_dir(std::env::temp_dir().join("trmnl-router-panic"));
+        let app: Router = Router::new().route("/api/display", display_handler(state));
+
+        let request = Request::get("/api/display")
+            .header("ID", "aa:bb:cc:dd:ee:ff")
+            .body(Body::empty())
+            .unwrap();
+        let json = get_json(app, request).await;
+        assert_eq!(json["status"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_display_prerendered_image() {
+        let dir = std::env::temp_dir().join("trmnl-router-prerendered");
+        let state = ByosState::new(
+            |_device: DeviceInfo| async { Ok(Screen::png(b"png".to_vec())) },
+            "http://localhost:3000",
+        )
+        .with_image_dir(&dir);
+        let app: Router = Router::new().route("/api/display", display_handler(state));
+
+        let request = Request::get("/api/display")
+            .header("ID", "aa:bb:cc:dd:ee:ff")
+            .body(Body::empty())
+            .unwrap();
+        let json = get_json(app, request).await;
+        assert_eq!(json["status"], 0);
+        let filename = json["filename"].as_str().unwrap();
+        assert!(filename.starts_with("aabbccddeeff-") && filename.ends_with(".png"));
+        assert_eq!(
+            json["image_url"],
+            format!("http://localhost:3000/images/{}", filename)
+        );
+        assert_eq!(std::fs::read(dir.join(filename)).unwrap(), b"png");
+    }
+
+    #[tokio::test]
+    async fn test_display_filename_follows_content() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let dir = std::env::temp_dir().join("trmnl-router-content");
+        let polls = Arc::new(AtomicU32::new(0));
+        let provider = {
+            let polls = polls.clone();
+            move |_device: DeviceInfo| {
+                // Changes every second poll
+                let n = polls.fetch_add(1, Ordering::SeqCst) / 2;
+                async move { Ok(Screen::png(format!("png {}", n).into_bytes())) }
+            }
+        };
+        let state =
+            Arc::new(ByosState::new(provider, "http://localhost:3000").with_image_dir(&dir));
+        let filename = || async {
+            let app: Router = Router::new().route("/api/display", display_handler(state.clone()));
+            let request = Request::get("/api/display")
+                .header("ID", "aa:bb:cc:dd:ee:ff")
+                .body(Body::empty())
+                .unwrap();
+            get_json(app, request).await["filename"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+
+        let first = filename().await;
+        assert_eq!(filename().await, first);
+        let second = filename().await;
+        assert_ne!(second, first);
+        // The image before the current one is kept for overlapping requests
+        assert!(dir.join(&first).exists());
+        assert_eq!(filename().await, second);
+        let third = filename().await;
+        assert!(!dir.join(&first).exists());
+        assert!(dir.join(&second).exists());
+        assert!(dir.join(&third).exists());
+    }
+
+    #[tokio::test]
+    async fn test_display_devices_never_share_files() {
+        let dir = std::env::temp_dir().join("trmnl-router-shared");
+        let state = Arc::new(
+            ByosState::new(
+                |_device: DeviceInfo| async { Ok(Screen::png(b"same".to_vec())) },
+                "http://localhost:3000",
+            )
+            .with_image_dir(&dir),
+        );
+        let display = |id: &'static str| {
+            let app: Router = Router::new().route("/api/display", display_handler(state.clone()));
+            async move {
+                let request = Request::get("/api/display")
+                    .header("ID", id)
+                    .body(Body::empty())
+                    .unwrap();
+                get_json(app, request).await
+            }
+        };
+
+        // Same last three bytes and same image
+        let a = display("11:22:33:dd:ee:ff").await;
+        let b = display("44:55:66:dd:ee:ff").await;
+        assert_ne!(a["filename"], b["filename"]);
+        assert!(dir.join(a["filename"].as_str().unwrap()).exists());
+        assert!(dir.join(b["filename"].as_str().unwrap()).exists());
+
+        // Requests without a usable ID get no image
+        assert_eq!(display("unknown").await["status"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_display_detects_base_url() {
+        let provider = |_device: DeviceInfo| async { Ok(Screen::png(b"png".to_vec())) };
+        let dir = std::env::temp_dir().join("trmnl-router-detect");
+        let request = || {
+            Request::get("/api/display")
+                .header("ID", "aa:bb:cc:dd:ee:ff")
+                .header("Host", "10.0.0.5:3000")
+                .header("X-Forwarded-Proto", "https")
+                .header("X-Forwarded-Host", "trmnl.example.com")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let state = ByosState::detect_base_url(provider).with_image_dir(&dir);
+        let app: Router = Router::new().route("/api/display", display_handler(state));
+        let json = get_json(app, request()).await;
+        assert!(json["image_url"]
+            .as_str()
+            .unwrap()
+            .starts_with("http://10.0.0.5:3000/images/aabbccddeeff-"));
+
+        let state = ByosState::detect_base_url(provider)
+            .with_image_dir(&dir)
+            .trust_forwarded_headers();
+        let app: Router = Router::new().route("/api/display", display_handler(state));
+        let json = get_json(app, request()).await;
+        assert!(json["image_url"]
+            .as_str()
+            .unwrap()
+            .starts_with("https://trmnl.example.com/images/aabbccddeeff-"));
+    }
+
+    #[tokio::test]
+    async fn test_display_busy_when_renders_saturated() {
+        let gate = Arc::new(tokio::sync::Mutex::new(()));
+        let provider = {
+            let gate = gate.clone();
+            move |_device: DeviceInfo| {
+                let gate = gate.clone();
+                async move {
+                    let _open = gate.lock().await;
+                    Ok(Screen::png(b"png".to_vec()))
+                }
+            }
+        };
+        let state = Arc::new(
+            ByosState::new(provider, "http://localhost:3000")
+                .with_image_dir(std::env::temp_dir().join("trmnl-router-busy"))
+                .with_max_concurrent_renders(1),
+        );
+        let display = |id: &'static str| {
+            let app: Router = Router::new().route("/api/display", display_handler(state.clone()));
+            async move {
+                let request = Request::get("/api/display")
+                    .header("ID", id)
+                    .body(Body::empty())
+                    .unwrap();
+                get_json(app, request).await
+            }
+        };
+
+        let shown = display("aa:bb:cc:dd:ee:ff").await;
+        assert_eq!(shown["status"], 0);
+
+        // Hold the only render slot with another device's render
+        let closed = gate.lock().await;
+        let blocked = tokio::spawn(display("11:22:33:44:55:66"));
+        let permits = state.render_permits.clone().unwrap();
+        while permits.available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let busy = display("aa:bb:cc:dd:ee:ff").await;
+        assert_eq!(busy["status"], 0);
+        assert_eq!(busy["filename"], shown["filename"]);
+        assert_eq!(busy["image_url"], shown["image_url"]);
+        assert_eq!(busy["refresh_rate"], "60");
+
+        // Nothing to keep showing yet
+        assert_eq!(display("77:88:99:aa:bb:cc").await["status"], 1);
+
+        drop(closed);
+        assert_eq!(blocked.await.unwrap()["status"], 0);
+    }
+
+    #[cfg(feature = "privacy")]
+    #[tokio::test]
+    async fn test_display_logs_obfuscated_id() {
+        use crate::privacy::{IdObfuscator, Reversible, SaltedHash};
+
+        let ids = Reversible::new(SaltedHash::new("salt"));
+        let failing =
+            |_device: DeviceInfo| async { Err::<String, _>(Error::Render("boom".into())) };
+        let state = ByosState::new(failing, "http://localhost:3000").with_obfuscator(ids.clone());
+        let app: Router = Router::new().route("/api/display", display_handler(state));
+
+        let request = Request::get("/api/display")
+            .header("ID", "aa:bb:cc:dd:ee:ff")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(get_json(app, request).await["status"], 1);
+
+        let id = SaltedHash::new("salt").obfuscate("aa:bb:cc:dd:ee:ff");
+        assert_eq!(ids.reveal(&id).as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+    }
+
+    #[cfg(feature = "preview")]
+    #[tokio::test]
+    async fn test_display_publishes_live_preview() {
+        let live = crate::preview::LivePreview::new();
+        let state = ByosState::new(
+            |_device: DeviceInfo| async { Ok(Screen::png(b"png".to_vec())) },
+            "http://localhost:3000",
+        )
+        .with_image_dir(std::env::temp_dir().join("trmnl-router-live"))
+        .with_live_preview(live.clone());
+        let app: Router = Router::new().route("/api/display", display_handler(state));
+
+        let request = Request::get("/api/display")
+            .header("ID", "aa:bb:cc:dd:ee:ff")
+            .body(Body::empty())
+            .unwrap();
+        let json = get_json(app, request).await;
+        let screen = live.latest().unwrap();
+        assert_eq!(screen.filename, json["filename"].as_str().unwrap());
+        assert_eq!(screen.device, "aa:bb:cc:dd:ee:ff");
+    }
+
+    #[tokio::test]
+    async fn test_log() {
+        let request = Request::post("/api/log")
+            .header("ID", "aa:bb:cc:dd:ee:ff")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"logs":[{"log_message":"hi"}]}"#))
+            .unwrap();
+        let json = get_json(router("trmnl-router-log"), request).await;
+        assert_eq!(json["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_serve_image() {
+        let dir = std::env::temp_dir().join("trmnl-router-images");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("1.png"), b"png").unwrap();
+
+        let response = router("trmnl-router-images")
+            .oneshot(Request::get("/images/1.png").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+    }
+}
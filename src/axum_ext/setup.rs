@@ -0,0 +1,206 @@
+//! Stock `/api/setup` handler with a registration callback.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::request::Parts;
+use axum::http::Request;
+use axum::routing::{get, MethodRouter};
+
+use crate::error::Error;
+use crate::http_ext::handle_setup;
+use crate::{generate_api_key, generate_friendly_id, DeviceInfo, SetupResponse};
+
+/// Outcome of a setup request, returned by the callback passed to
+/// [`setup_handler`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SetupDecision {
+    /// Register the device with these credentials
+    Register {
+        /// API key the device sends as `Access-Token` from now on
+        api_key: String,
+        /// Short ID shown on the device
+        friendly_id: String,
+    },
+    /// Refuse the device; the firmware shows the message and retries later
+    Reject(String),
+}
+
+impl SetupDecision {
+    /// Register `device` with a fresh API key and a friendly ID derived
+    /// from its MAC address.
+    pub fn register(device: &DeviceInfo) -> Self {
+        let mac = device
+            .mac()
+            .map(|mac| mac.to_string())
+            .unwrap_or_else(|| device.mac_address.clone());
+        SetupDecision::Register {
+            api_key: generate_api_key(),
+            friendly_id: generate_friendly_id(&mac),
+        }
+    }
+
+    /// Refuse the device with `message`.
+    pub fn reject(message: impl Into<String>) -> Self {
+        SetupDecision::Reject(message.into())
+    }
+
+    fn into_setup_response(self) -> SetupResponse {
+        match self {
+            SetupDecision::Register {
+                api_key,
+                friendly_id,
+            } => SetupResponse::new(friendly_id, "", "Welcome to TRMNL BYOS").with_api_key(api_key),
+            SetupDecision::Reject(message) => SetupResponse::not_registered(message),
+        }
+    }
+}
+
+/// Stock `GET /api/setup` handler calling `on_setup` for each device.
+///
+/// Built on [`handle_setup`](crate::http_ext::handle_setup). Requests
+/// without a valid MAC address in the `ID` header are answered with
+/// [`SetupResponse::not_registered`] without calling `on_setup`. The callback
+/// decides whether to register the device and is the place to persist the
+/// issued credentials; if it fails, the device gets the error's HTTP
+/// response and retries setup later.
+///
+/// The outcome is logged without the device's MAC address; add
+/// [`DeviceTraceLayer`](super::DeviceTraceLayer) to attribute it to a
+/// device, optionally through an obfuscator.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::axum_ext::{setup_handler, SetupDecision};
+/// use trmnl::DeviceInfo;
+///
+/// let app = axum::Router::new().route(
+///     "/api/setup",
+///     setup_handler(move |device: DeviceInfo| async move {
+///         let decision = SetupDecision::register(&device);
+///         if let SetupDecision::Register { api_key, friendly_id } = &decision {
+///             db.save_device(&device.mac_address, api_key, friendly_id).await?;
+///         }
+///         Ok(decision)
+///     }),
+/// );
+/// ```
+pub fn setup_handler<S, F, Fut>(on_setup: F) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+    F: Fn(DeviceInfo) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<SetupDecision, Error>> + Send,
+{
+    let on_setup = Arc::new(on_setup);
+    get(move |parts: Parts| async move {
+        let request = Request::from_parts(parts, ());
+        handle_setup(&request, |device| setup(&*on_setup, device))
+            .await
+            .map(Body::from)
+    })
+}
+
+async fn setup<F, Fut>(on_setup: &F, device: DeviceInfo) -> Result<SetupResponse, Error>
+where
+    F: Fn(DeviceInfo) -> Fut,
+    Fut: Future<Output = Result<SetupDecision, Error>>,
+{
+    match on_setup(device).await {
+        Ok(decision) => {
+            tracing::info!("Device setup: {:?}", decision_kind(&decision));
+            Ok(decision.into_setup_response())
+        }
+        Err(e) => {
+            tracing::warn!("Device setup failed: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Decision without the credentials, for logging.
+fn decision_kind(decision: &SetupDecision) -> &str {
+    match decision {
+        SetupDecision::Register { .. } => "registered",
+        SetupDecision::Reject(message) => message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+
+    async fn call(app: Router, id: Option<&str>) -> (StatusCode, serde_json::Value) {
+        let mut request = Request::get("/api/setup");
+        if let Some(id) = id {
+            request = request.header("ID", id);
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_register_persists_credentials() {
+        let saved = Arc::new(Mutex::new(Vec::new()));
+        let store = saved.clone();
+        let app = Router::new().route(
+            "/api/setup",
+            setup_handler(move |device: DeviceInfo| {
+                let store = store.clone();
+                async move {
+                    let decision = SetupDecision::register(&device);
+                    store.lock().unwrap().push(decision.clone());
+                    Ok(decision)
+                }
+            }),
+        );
+
+        let (status, json) = call(app, Some("aa-bb-cc-dd-ee-ff")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["status"], 200);
+        assert_eq!(json["friendly_id"], "DDEEFF");
+
+        let saved = saved.lock().unwrap();
+        let SetupDecision::Register { api_key, .. } = &saved[0] else {
+            panic!("expected registration");
+        };
+        assert_eq!(json["api_key"], api_key.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_reject_and_failure() {
+        let app = Router::new().route(
+            "/api/setup",
+            setup_handler(|device: DeviceInfo| async move {
+                if device.mac_address.starts_with("AA") {
+                    Ok(SetupDecision::reject("Unknown device"))
+                } else {
+                    Err(Error::Io("database down".to_string()))
+                }
+            }),
+        );
+
+        let (status, json) = call(app.clone(), Some("AA:BB:CC:DD:EE:FF")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["status"], 404);
+        assert_eq!(json["message"], "Unknown device");
+
+        let (status, _) = call(app.clone(), Some("11:BB:CC:DD:EE:FF")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+        let (_, json) = call(app, None).await;
+        assert_eq!(json["status"], 404);
+    }
+}
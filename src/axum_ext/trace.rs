@@ -0,0 +1,282 @@
+//! Per-request tracing spans annotated with device headers.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request};
+use axum::response::{IntoResponse, Response};
+use tower_layer::Layer;
+use tower_service::Service;
+use tracing::Instrument;
+
+use super::logs::DeviceIdMask;
+use crate::error::Error;
+#[cfg(feature = "privacy")]
+use crate::privacy::IdObfuscator;
+use crate::DeviceInfo;
+
+/// Layer opening a `trmnl_request` span for each request.
+///
+/// The span records the endpoint and the device's MAC address, battery
+/// percentage, RSSI and firmware version, so every event logged by
+/// handlers carries them. When the request completes, an event logs the
+/// response status and, for `/api/display`, the refresh rate sent to the
+/// device. With the `privacy` feature, [`DeviceTraceLayer::with_obfuscator`]
+/// records an obfuscated ID in place of the MAC address.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::axum_ext::DeviceTraceLayer;
+///
+/// let app = axum::Router::new()
+///     .route("/api/display", get(display))
+///     .layer(DeviceTraceLayer::new());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeviceTraceLayer {
+    ids: DeviceIdMask,
+}
+
+impl DeviceTraceLayer {
+    /// Create the layer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `obfuscator`'s ID for each device instead of its MAC address.
+    #[cfg(feature = "privacy")]
+    pub fn with_obfuscator(mut self, obfuscator: impl IdObfuscator + 'static) -> Self {
+        self.ids = DeviceIdMask::new(obfuscator);
+        self
+    }
+}
+
+impl<S> Layer<S> for DeviceTraceLayer {
+    type Service = DeviceTraceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DeviceTraceService {
+            inner,
+            ids: self.ids.clone(),
+        }
+    }
+}
+
+/// Service produced by [`DeviceTraceLayer`].
+#[derive(Debug, Clone)]
+pub struct DeviceTraceService<S> {
+    inner: S,
+    ids: DeviceIdMask,
+}
+
+impl<S, B> Service<Request<B>> for DeviceTraceService<S>
+where
+    S: Service<Request<B>, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let device = DeviceInfo::from_headers(request.headers());
+        let endpoint = request.uri().path().to_string();
+        let span = tracing::info_span!(
+            "trmnl_request",
+            endpoint = %endpoint,
+            device = %self.ids.apply(&device.mac_address),
+            battery_percent = device.battery_percentage(),
+            rssi = device.rssi,
+            firmware = device.firmware_version.as_deref(),
+        );
+
+        let response = span.in_scope(|| self.inner.call(request));
+        Box::pin(
+            async move {
+                let response = response.await?;
+                if endpoint.ends_with("/api/display") && is_json(&response) {
+                    Ok(log_display_response(response).await)
+                } else {
+                    tracing::info!(status = response.status().as_u16(), "TRMNL response");
+                    Ok(response)
+                }
+            }
+            .instrument(span),
+        )
+    }
+}
+
+fn is_json(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"))
+}
+
+/// Log the refresh rate from a display response, buffering the body.
+///
+/// A body that fails to stream is replaced with a `500` error response.
+async fn log_display_response(response: Response) -> Response {
+    let (parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return Error::Io(format!("Failed to read display response: {}", e)).into_response();
+        }
+    };
+
+    let refresh_rate = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .and_then(|json| match &json["refresh_rate"] {
+            serde_json::Value::String(s) => s.parse::<u32>().ok(),
+            value => value.as_u64().and_then(|n| u32::try_from(n).ok()),
+        });
+    tracing::info!(
+        status = parts.status.as_u16(),
+        refresh_rate,
+        "TRMNL response"
+    );
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use tower::ServiceExt;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata};
+
+    /// Subscriber recording every span and event field as `name=value`.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Recorder {
+        fn fields(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl Visit for Recorder {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{}={}", field.name(), value));
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl tracing::Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            span.record(&mut self.clone());
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut self.clone());
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut self.clone());
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[tokio::test]
+    async fn test_records_device_and_refresh_rate() {
+        let recorder = Recorder::default();
+        let _guard = tracing::subscriber::set_default(recorder.clone());
+
+        let app = Router::new()
+            .route(
+                "/api/display",
+                get(|| async {
+                    Json(
+                        crate::DisplayResponse::new("http://x/1.png", "1.png")
+                            .with_refresh_rate(300),
+                    )
+                }),
+            )
+            .layer(DeviceTraceLayer::new());
+
+        let request = Request::get("/api/display")
+            .header("ID", "AA:BB:CC:DD:EE:FF")
+            .header("Battery-Voltage", "3.6")
+            .header("RSSI", "-60")
+            .header("FW-Version", "1.5.2")
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap();
+
+        let fields = recorder.fields();
+        for expected in [
+            "endpoint=/api/display",
+            "device=AA:BB:CC:DD:EE:FF",
+            "battery_percent=50",
+            "rssi=-60",
+            "firmware=1.5.2",
+            "status=200",
+            "refresh_rate=300",
+        ] {
+            assert!(
+                fields.iter().any(|f| f == expected),
+                "{expected} not in {fields:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_passes_through() {
+        let app = Router::new()
+            .route(
+                "/api/display",
+                get(|| async {
+                    Json(
+                        crate::DisplayResponse::new("http://x/1.png", "1.png")
+                            .with_refresh_rate(300),
+                    )
+                }),
+            )
+            .layer(DeviceTraceLayer::new());
+
+        let request = Request::get("/api/display")
+            .header("ID", "AA:BB:CC:DD:EE:FF")
+            .header("Battery-Voltage", "3.9")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert!(response.status().is_success());
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["refresh_rate"], "300");
+    }
+}
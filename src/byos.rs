@@ -3,9 +3,36 @@
 //! These types match what the TRMNL firmware expects.
 //! See: <https://github.com/usetrmnl/trmnl-firmware>
 
+use std::time::Duration;
+
+use base64::prelude::{Engine as _, BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD};
 use serde::{Deserialize, Serialize};
 
-use crate::battery_percentage;
+use crate::auth::Secret;
+use crate::error::Error;
+use crate::mac::MacAddress;
+use crate::{
+    battery_percentage, MAX_FILENAME_LENGTH, MAX_INLINE_RESPONSE_SIZE, MAX_REFRESH_RATE,
+    MAX_RESPONSE_SIZE, MAX_URL_LENGTH, MIN_REFRESH_RATE,
+};
+
+/// Data URI prefix used by [`DisplayResponse::with_inline_image`].
+const INLINE_IMAGE_PREFIX: &str = "data:image/png;base64,";
+
+/// Refresh rate used by [`DisplayResponse::busy`] (seconds).
+const BUSY_RETRY_SECONDS: u32 = 60;
+
+/// `SetupResponse::status` for a recognized device.
+const SETUP_STATUS_OK: u32 = 200;
+
+/// `SetupResponse::status` for an unknown device.
+const SETUP_STATUS_NOT_REGISTERED: u32 = 404;
+
+/// Random bytes in keys from [`generate_api_key`] (32 base64 characters).
+const API_KEY_BYTES: usize = 24;
+
+/// Length of IDs from [`generate_friendly_id`].
+const FRIENDLY_ID_LENGTH: usize = 6;
 
 /// Device information extracted from HTTP headers.
 ///
@@ -15,6 +42,10 @@ use crate::battery_percentage;
 /// - `FW-Version`: Firmware version string
 /// - `RSSI`: WiFi signal strength in dBm
 /// - `Refresh-Rate`: Current refresh rate in seconds
+/// - `Access-Token`: API key issued during setup
+/// - `User-Agent`: Firmware HTTP client identifier
+/// - `Width` / `Height`: Panel resolution reported by the firmware
+/// - `Model`: Device model hint
 ///
 /// # Example (manual extraction)
 ///
@@ -45,6 +76,21 @@ pub struct DeviceInfo {
 
     /// Current refresh rate in seconds (from `Refresh-Rate` header)
     pub refresh_rate: Option<u32>,
+
+    /// API key issued during setup (from `Access-Token` header)
+    pub access_token: Option<Secret<String>>,
+
+    /// HTTP user agent (from `User-Agent` header)
+    pub user_agent: Option<String>,
+
+    /// Reported panel width in pixels (from `Width` header)
+    pub width: Option<u32>,
+
+    /// Reported panel height in pixels (from `Height` header)
+    pub height: Option<u32>,
+
+    /// Device model hint (from `Model` header)
+    pub model: Option<String>,
 }
 
 impl DeviceInfo {
@@ -84,6 +130,35 @@ impl DeviceInfo {
         self
     }
 
+    /// Set access token.
+    #[must_use]
+    pub fn with_access_token(mut self, token: impl Into<String>) -> Self {
+        self.access_token = Some(Secret::new(token.into()));
+        self
+    }
+
+    /// Set user agent.
+    #[must_use]
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Set reported panel dimensions.
+    #[must_use]
+    pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.width = Some(width);
+        self.height = Some(height);
+        self
+    }
+
+    /// Set device model.
+    #[must_use]
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
     /// Get battery voltage in millivolts.
     pub fn battery_voltage_mv(&self) -> Option<u32> {
         self.battery_voltage.map(|v| (v * 1000.0) as u32)
@@ -96,6 +171,34 @@ impl DeviceInfo {
         self.battery_voltage_mv().map(battery_percentage)
     }
 
+    /// Interpret battery voltage using [`BatteryThresholds::default`].
+    ///
+    /// Returns `None` if the device didn't report a voltage.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::{BatteryState, DeviceInfo};
+    ///
+    /// let device = DeviceInfo::new("AA:BB:CC:DD:EE:FF").with_battery_voltage(4.4);
+    /// assert_eq!(device.battery_state(), Some(BatteryState::Charging));
+    /// ```
+    pub fn battery_state(&self) -> Option<BatteryState> {
+        self.battery_state_with(&BatteryThresholds::default())
+    }
+
+    /// Interpret battery voltage using custom thresholds.
+    pub fn battery_state_with(&self, thresholds: &BatteryThresholds) -> Option<BatteryState> {
+        self.battery_voltage_mv().map(|mv| thresholds.classify(mv))
+    }
+
+    /// Parse `mac_address` into a normalized [`MacAddress`].
+    ///
+    /// Returns `None` if the `ID` header was missing or isn't a MAC address.
+    pub fn mac(&self) -> Option<MacAddress> {
+        self.mac_address.parse().ok()
+    }
+
     /// Get short device ID (last 4 chars of MAC).
     pub fn short_id(&self) -> &str {
         let len = self.mac_address.len();
@@ -107,6 +210,56 @@ impl DeviceInfo {
     }
 }
 
+/// Power state inferred from battery voltage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BatteryState {
+    /// Voltage above what a LiPo cell reaches on its own; USB powered
+    Charging,
+    /// Fully charged
+    Full,
+    /// Running on battery
+    Discharging,
+    /// Low enough that the device may soon stop waking
+    Critical,
+}
+
+/// Voltage thresholds for [`DeviceInfo::battery_state_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryThresholds {
+    /// At or above this (mV), the device is assumed to be on USB power (default: 4250)
+    pub charging_mv: u32,
+    /// At or above this (mV), the battery is full (default: 4150)
+    pub full_mv: u32,
+    /// At or below this (mV), the battery is critical (default: 3300)
+    pub critical_mv: u32,
+}
+
+impl Default for BatteryThresholds {
+    fn default() -> Self {
+        Self {
+            charging_mv: 4250,
+            full_mv: 4150,
+            critical_mv: 3300,
+        }
+    }
+}
+
+impl BatteryThresholds {
+    /// Classify a voltage in millivolts.
+    pub fn classify(&self, voltage_mv: u32) -> BatteryState {
+        if voltage_mv >= self.charging_mv {
+            BatteryState::Charging
+        } else if voltage_mv >= self.full_mv {
+            BatteryState::Full
+        } else if voltage_mv <= self.critical_mv {
+            BatteryState::Critical
+        } else {
+            BatteryState::Discharging
+        }
+    }
+}
+
 /// Response for GET /api/display endpoint.
 ///
 /// This is the critical response type - the firmware uses these fields to:
@@ -114,6 +267,11 @@ impl DeviceInfo {
 /// - Detect new images by comparing `filename`
 /// - Control refresh rate and firmware updates
 ///
+/// If `filename` matches the one the device already shows, the firmware
+/// skips the download and leaves the e-ink panel untouched, saving battery
+/// and avoiding a visible flash. [`DisplayResponse::unchanged`] makes that
+/// intent explicit.
+///
 /// # Example
 ///
 /// ```
@@ -128,10 +286,11 @@ impl DeviceInfo {
 /// let json = serde_json::to_string(&response).unwrap();
 /// assert!(json.contains("\"status\":0"));
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct DisplayResponse {
-    /// Status code (0 = success)
-    pub status: u32,
+    /// Status code (serialized as a number)
+    pub status: DisplayStatus,
 
     /// Full URL to the display image
     pub image_url: String,
@@ -144,6 +303,12 @@ pub struct DisplayResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub filename: Option<String>,
 
+    /// Seconds the firmware waits for the image download before giving up.
+    ///
+    /// Omitted by default so the firmware uses its built-in timeout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_url_timeout: Option<u32>,
+
     /// Whether to trigger firmware update
     pub update_firmware: bool,
 
@@ -151,16 +316,49 @@ pub struct DisplayResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub firmware_url: Option<String>,
 
-    /// Refresh rate in seconds.
-    ///
-    /// **Note**: Must be a string (firmware expects string).
-    pub refresh_rate: String,
+    /// Refresh rate in seconds (serialized as a string, as the firmware expects).
+    pub refresh_rate: RefreshRate,
 
     /// Whether to reset the device
     pub reset_firmware: bool,
+
+    /// Special function for the firmware to perform (e.g. "identify", "sleep",
+    /// "restart_playlist")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub special_function: Option<String>,
 }
 
 impl DisplayResponse {
+    /// Start building a response that is validated before use.
+    ///
+    /// Unlike [`DisplayResponse::new`], [`DisplayResponseBuilder::build`]
+    /// rejects responses the firmware would mishandle, so mistakes surface
+    /// on the server instead of as a blank or stuck device.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::DisplayResponse;
+    ///
+    /// let response = DisplayResponse::builder()
+    ///     .with_image_url("https://example.com/1700000000.png")
+    ///     .with_filename("1700000000.png")
+    ///     .with_refresh_rate(300)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(response.refresh_rate, "300");
+    ///
+    /// // Relative URLs can't be fetched by the firmware
+    /// let result = DisplayResponse::builder()
+    ///     .with_image_url("/screen.png")
+    ///     .with_filename("screen.png")
+    ///     .build();
+    /// assert!(result.is_err());
+    /// ```
+    pub fn builder() -> DisplayResponseBuilder {
+        DisplayResponseBuilder::default()
+    }
+
     /// Create a new display response.
     ///
     /// # Arguments
@@ -169,23 +367,68 @@ impl DisplayResponse {
     /// * `filename` - Filename for change detection (use timestamps!)
     pub fn new(image_url: impl Into<String>, filename: impl Into<String>) -> Self {
         Self {
-            status: 0,
+            status: DisplayStatus::Ok,
             image_url: image_url.into(),
             filename: Some(filename.into()),
+            image_url_timeout: None,
             update_firmware: false,
             firmware_url: None,
-            refresh_rate: "60".to_string(),
+            refresh_rate: RefreshRate::default(),
             reset_firmware: false,
+            special_function: None,
         }
     }
 
-    /// Set custom refresh rate (in seconds).
+    /// Set custom refresh rate (seconds or a `Duration`).
+    ///
+    /// Values outside the firmware's supported range are clamped; see
+    /// [`RefreshRate`].
     #[must_use]
-    pub fn with_refresh_rate(mut self, seconds: u32) -> Self {
-        self.refresh_rate = seconds.to_string();
+    pub fn with_refresh_rate(mut self, rate: impl Into<RefreshRate>) -> Self {
+        self.refresh_rate = rate.into();
+        self
+    }
+
+    /// Set how long the firmware waits for the image download (in seconds).
+    ///
+    /// Raise this for servers behind slow links or cold-starting image
+    /// backends, where the firmware would otherwise abort the download.
+    #[must_use]
+    pub fn with_image_url_timeout(mut self, seconds: u32) -> Self {
+        self.image_url_timeout = Some(seconds);
+        self
+    }
+
+    /// Embed the image in the response instead of linking to it.
+    ///
+    /// The PNG is base64-encoded into a `data:image/png;base64,...` URI in
+    /// `image_url`, so there is no image file to host or serve. Only newer
+    /// firmware accepts inline images; older firmware will fail to download
+    /// the image and keep showing the previous one.
+    ///
+    /// `filename` is still used for change detection, so keep it unique per
+    /// image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::DisplayResponse;
+    ///
+    /// let png = [0x89, b'P', b'N', b'G'];
+    /// let response = DisplayResponse::new("", "1700000000.png").with_inline_image(&png);
+    /// assert!(response.image_url.starts_with("data:image/png;base64,"));
+    /// ```
+    #[must_use]
+    pub fn with_inline_image(mut self, png: &[u8]) -> Self {
+        self.image_url = format!("{}{}", INLINE_IMAGE_PREFIX, BASE64_STANDARD.encode(png));
         self
     }
 
+    /// Whether the image is embedded in `image_url` as a data URI.
+    pub fn has_inline_image(&self) -> bool {
+        self.image_url.starts_with(INLINE_IMAGE_PREFIX)
+    }
+
     /// Set firmware update URL.
     #[must_use]
     pub fn with_firmware_update(mut self, firmware_url: impl Into<String>) -> Self {
@@ -201,41 +444,570 @@ impl DisplayResponse {
         self
     }
 
+    /// Ask the firmware to perform a special function.
+    ///
+    /// Supported values depend on the firmware version; current firmware
+    /// understands e.g. `"identify"`, `"sleep"` and `"restart_playlist"`.
+    #[must_use]
+    pub fn with_special_function(mut self, function: impl Into<String>) -> Self {
+        self.special_function = Some(function.into());
+        self
+    }
+
+    /// Let the device sleep until `until` before polling again.
+    ///
+    /// Sets the refresh rate to the time remaining, clamped to
+    /// [`MIN_REFRESH_RATE`](crate::MIN_REFRESH_RATE)..=[`MAX_REFRESH_RATE`](crate::MAX_REFRESH_RATE),
+    /// so a wake time more than a day away takes several polls to reach.
+    /// The firmware has no timed-sleep special function (its `"sleep"`
+    /// function is a button action), so `special_function` is left alone.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::{Duration, Utc};
+    /// use trmnl::DisplayResponse;
+    ///
+    /// let wake = Utc::now() + Duration::hours(8);
+    /// let response = DisplayResponse::new("https://example.com/night.png", "night.png")
+    ///     .sleep_until(wake);
+    /// assert!(response.refresh_rate.seconds() > 7 * 3600);
+    /// ```
+    #[cfg(feature = "schedule")]
+    #[must_use]
+    pub fn sleep_until<Tz: chrono::TimeZone>(self, until: chrono::DateTime<Tz>) -> Self {
+        self.sleep_until_from(until, chrono::Utc::now())
+    }
+
+    /// Create a display response with the refresh rate `schedule` gives
+    /// for the current time.
+    ///
+    /// During a `mode: sleep` rule this is the time until the rule ends, so
+    /// the device sleeps through the window in one go.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::schedule::RefreshSchedule;
+    /// use trmnl::DisplayResponse;
+    ///
+    /// let schedule = RefreshSchedule::from_yaml(
+    ///     "timezone: UTC\ndefault_refresh_rate: 600\nschedule: []",
+    /// )
+    /// .unwrap();
+    /// let response = DisplayResponse::from_schedule(
+    ///     "https://example.com/1700000000.png",
+    ///     "1700000000.png",
+    ///     &schedule,
+    /// );
+    /// assert_eq!(response.refresh_rate, "600");
+    /// ```
+    #[cfg(feature = "schedule")]
+    pub fn from_schedule(
+        image_url: impl Into<String>,
+        filename: impl Into<String>,
+        schedule: &crate::schedule::RefreshSchedule,
+    ) -> Self {
+        Self::new(image_url, filename).with_refresh_rate(schedule.get_refresh_rate())
+    }
+
+    /// Set the refresh rate `schedule` gives `device` at the current time.
+    ///
+    /// Applies the schedule's `battery_scaling` for the device's battery
+    /// level, and shortens the rate so the device doesn't sleep past the
+    /// next rule change (see
+    /// [`RefreshSchedule::next_transition`](crate::schedule::RefreshSchedule::next_transition)).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::schedule::RefreshSchedule;
+    /// use trmnl::{DeviceInfo, DisplayResponse};
+    ///
+    /// let schedule = RefreshSchedule::from_yaml(
+    ///     "timezone: UTC\ndefault_refresh_rate: 600\nschedule: []",
+    /// )
+    /// .unwrap();
+    /// let device = DeviceInfo::new("AA:BB:CC:DD:EE:FF");
+    /// let response = DisplayResponse::new("https://example.com/1700000000.png", "1700000000.png")
+    ///     .with_schedule(&schedule, &device);
+    /// assert_eq!(response.refresh_rate, "600");
+    /// ```
+    #[cfg(feature = "schedule")]
+    #[must_use]
+    pub fn with_schedule(
+        self,
+        schedule: &crate::schedule::RefreshSchedule,
+        device: &DeviceInfo,
+    ) -> Self {
+        self.with_schedule_at(schedule, device, schedule.now())
+    }
+
+    #[cfg(feature = "schedule")]
+    fn with_schedule_at<Tz: chrono::TimeZone>(
+        self,
+        schedule: &crate::schedule::RefreshSchedule,
+        device: &DeviceInfo,
+        now: chrono::DateTime<Tz>,
+    ) -> Self {
+        let refresh_rate = schedule.get_refresh_rate_for_device_at(device, now.clone());
+        let refresh_rate = match schedule.next_transition(now.clone()) {
+            Some((at, _)) => {
+                let until = at.signed_duration_since(now).num_seconds().max(1);
+                refresh_rate.min(u32::try_from(until).unwrap_or(u32::MAX))
+            }
+            None => refresh_rate,
+        };
+        self.with_refresh_rate(refresh_rate)
+    }
+
+    #[cfg(feature = "schedule")]
+    fn sleep_until_from<Tz: chrono::TimeZone>(
+        self,
+        until: chrono::DateTime<Tz>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        let seconds = until.signed_duration_since(now).num_seconds().max(0);
+        let seconds = u32::try_from(seconds).unwrap_or(u32::MAX);
+        self.with_refresh_rate(seconds)
+    }
+
+    /// Create a response that tells the device nothing has changed.
+    ///
+    /// Returns `previous_filename` so the firmware skips the download and
+    /// the e-ink refresh; `image_url` is left empty because it is never
+    /// fetched. Use this when content is identical to what the device is
+    /// already showing (see [`diff_images`](crate::diff_images) with the
+    /// `image` feature).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::DisplayResponse;
+    ///
+    /// let response = DisplayResponse::unchanged("1700000000.png").with_refresh_rate(300);
+    /// assert_eq!(response.filename.as_deref(), Some("1700000000.png"));
+    /// ```
+    pub fn unchanged(previous_filename: impl Into<String>) -> Self {
+        Self::new(String::new(), previous_filename)
+    }
+
+    /// Create a response that keeps the device's current image.
+    ///
+    /// Use this when the server is saturated (render pool busy, data source
+    /// slow) instead of timing out or returning HTTP 429. The firmware sees a
+    /// valid response with the filename it already has, skips the refresh and
+    /// checks back after a short interval (60 seconds).
+    ///
+    /// # Arguments
+    ///
+    /// * `image_url` - URL of the image the device is currently showing
+    /// * `current_filename` - Filename the device is currently showing
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::{DisplayResponse, DisplayStatus};
+    ///
+    /// let response = DisplayResponse::busy("https://example.com/123.png", "123.png");
+    /// assert_eq!(response.status, DisplayStatus::Ok);
+    /// assert_eq!(response.refresh_rate, "60");
+    /// ```
+    pub fn busy(image_url: impl Into<String>, current_filename: impl Into<String>) -> Self {
+        Self::new(image_url, current_filename).with_refresh_rate(BUSY_RETRY_SECONDS)
+    }
+
+    /// Check the response against firmware parser limits.
+    ///
+    /// The firmware parses responses into fixed-size buffers; overly long
+    /// URLs or bodies are truncated or dropped without any error reaching
+    /// the server. Call this before returning a response (e.g. in tests or
+    /// debug builds) to catch presigned URLs that have grown too long.
+    ///
+    /// Checks [`MAX_RESPONSE_SIZE`](crate::MAX_RESPONSE_SIZE),
+    /// [`MAX_URL_LENGTH`](crate::MAX_URL_LENGTH) and
+    /// [`MAX_FILENAME_LENGTH`](crate::MAX_FILENAME_LENGTH). A response with an
+    /// [inline image](Self::with_inline_image) is exempt from the URL limit
+    /// and its whole body is checked against
+    /// [`MAX_INLINE_RESPONSE_SIZE`](crate::MAX_INLINE_RESPONSE_SIZE) instead.
+    ///
+    /// Response headers are not counted: they are added by the web framework
+    /// rather than carried by `DisplayResponse`, and the firmware's HTTP
+    /// client only keeps the headers it explicitly collects, discarding the
+    /// rest, so extra headers cannot overflow its parser.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Protocol` describing the first limit exceeded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::DisplayResponse;
+    ///
+    /// let response = DisplayResponse::new("https://example.com/1.png", "1.png");
+    /// assert!(response.check_limits().is_ok());
+    ///
+    /// let long_url = format!("https://example.com/{}", "x".repeat(2000));
+    /// assert!(DisplayResponse::new(long_url, "1.png").check_limits().is_err());
+    /// ```
+    pub fn check_limits(&self) -> Result<(), Error> {
+        // Inline images are sized by the image itself, not the URL parser
+        let inline = self.has_inline_image();
+        let urls = [
+            ("image_url", Some(&self.image_url).filter(|_| !inline)),
+            ("firmware_url", self.firmware_url.as_ref()),
+        ];
+        for (field, url) in urls {
+            if let Some(url) = url.filter(|u| u.len() > MAX_URL_LENGTH) {
+                return Err(Error::Protocol(format!(
+                    "{} is {} bytes (max {})",
+                    field,
+                    url.len(),
+                    MAX_URL_LENGTH
+                )));
+            }
+        }
+
+        if let Some(filename) = self
+            .filename
+            .as_ref()
+            .filter(|f| f.len() > MAX_FILENAME_LENGTH)
+        {
+            return Err(Error::Protocol(format!(
+                "filename is {} bytes (max {})",
+                filename.len(),
+                MAX_FILENAME_LENGTH
+            )));
+        }
+
+        let size = serde_json::to_vec(self)?.len();
+        let max = if inline {
+            MAX_INLINE_RESPONSE_SIZE
+        } else {
+            MAX_RESPONSE_SIZE
+        };
+        if size > max {
+            return Err(Error::Protocol(format!(
+                "response is {} bytes (max {})",
+                size, max
+            )));
+        }
+
+        Ok(())
+    }
+
     /// Create an error response.
     ///
-    /// Uses status code 1 and empty image URL.
+    /// Uses [`DisplayStatus::Error`] and empty image URL.
     pub fn error() -> Self {
         Self {
-            status: 1,
+            status: DisplayStatus::Error,
             image_url: String::new(),
             filename: None,
+            image_url_timeout: None,
             update_firmware: false,
             firmware_url: None,
-            refresh_rate: "300".to_string(), // Retry in 5 minutes
+            refresh_rate: RefreshRate::from(300), // Retry in 5 minutes
             reset_firmware: false,
+            special_function: None,
         }
     }
 }
 
-impl Default for DisplayResponse {
+/// Builder for a validated [`DisplayResponse`].
+///
+/// Created by [`DisplayResponse::builder`].
+#[derive(Debug, Clone)]
+pub struct DisplayResponseBuilder {
+    response: DisplayResponse,
+    refresh_rate: u32,
+}
+
+impl Default for DisplayResponseBuilder {
     fn default() -> Self {
         Self {
-            status: 0,
-            image_url: String::new(),
-            filename: None,
-            update_firmware: false,
-            firmware_url: None,
-            refresh_rate: "60".to_string(),
-            reset_firmware: false,
+            response: DisplayResponse::default(),
+            refresh_rate: 60,
+        }
+    }
+}
+
+impl DisplayResponseBuilder {
+    /// Set the image URL (absolute http(s) URL).
+    #[must_use]
+    pub fn with_image_url(mut self, image_url: impl Into<String>) -> Self {
+        self.response.image_url = image_url.into();
+        self
+    }
+
+    /// Embed the image as a data URI (see [`DisplayResponse::with_inline_image`]).
+    #[must_use]
+    pub fn with_inline_image(mut self, png: &[u8]) -> Self {
+        self.response = self.response.with_inline_image(png);
+        self
+    }
+
+    /// Set filename for change detection.
+    #[must_use]
+    pub fn with_filename(mut self, filename: impl Into<String>) -> Self {
+        self.response.filename = Some(filename.into());
+        self
+    }
+
+    /// Set refresh rate (in seconds).
+    #[must_use]
+    pub fn with_refresh_rate(mut self, seconds: u32) -> Self {
+        self.refresh_rate = seconds;
+        self
+    }
+
+    /// Set image download timeout (in seconds).
+    #[must_use]
+    pub fn with_image_url_timeout(mut self, seconds: u32) -> Self {
+        self.response.image_url_timeout = Some(seconds);
+        self
+    }
+
+    /// Set firmware update URL.
+    #[must_use]
+    pub fn with_firmware_update(mut self, firmware_url: impl Into<String>) -> Self {
+        self.response = self.response.with_firmware_update(firmware_url);
+        self
+    }
+
+    /// Trigger device reset.
+    #[must_use]
+    pub fn with_reset(mut self) -> Self {
+        self.response.reset_firmware = true;
+        self
+    }
+
+    /// Ask the firmware to perform a special function.
+    #[must_use]
+    pub fn with_special_function(mut self, function: impl Into<String>) -> Self {
+        self.response.special_function = Some(function.into());
+        self
+    }
+
+    /// Validate and build the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Protocol` if:
+    /// - the refresh rate is outside [`MIN_REFRESH_RATE`](crate::MIN_REFRESH_RATE)
+    ///   to [`MAX_REFRESH_RATE`](crate::MAX_REFRESH_RATE)
+    /// - `image_url` is set but is neither an absolute http(s) URL nor an
+    ///   inline image
+    /// - `image_url` is set without a non-empty filename
+    /// - a firmware update is requested without an absolute http(s) URL
+    /// - any limit checked by [`DisplayResponse::check_limits`] is exceeded
+    pub fn build(self) -> Result<DisplayResponse, Error> {
+        let Self {
+            response,
+            refresh_rate,
+        } = self;
+
+        if !(MIN_REFRESH_RATE..=MAX_REFRESH_RATE).contains(&refresh_rate) {
+            return Err(Error::Protocol(format!(
+                "refresh_rate {}s is outside {}..={}s",
+                refresh_rate, MIN_REFRESH_RATE, MAX_REFRESH_RATE
+            )));
+        }
+
+        if !response.image_url.is_empty() {
+            if !response.has_inline_image() && !is_http_url(&response.image_url) {
+                return Err(Error::Protocol(format!(
+                    "image_url must be an absolute http(s) URL: {}",
+                    response.image_url
+                )));
+            }
+            if response.filename.as_deref().map_or(true, str::is_empty) {
+                return Err(Error::Protocol(
+                    "filename is required when image_url is set".to_string(),
+                ));
+            }
+        }
+
+        if response.update_firmware && !response.firmware_url.as_deref().is_some_and(is_http_url) {
+            return Err(Error::Protocol(
+                "update_firmware requires an absolute http(s) firmware_url".to_string(),
+            ));
+        }
+
+        let response = response.with_refresh_rate(RefreshRate(refresh_rate));
+        response.check_limits()?;
+        Ok(response)
+    }
+}
+
+/// Whether `url` is an absolute http(s) URL with a host.
+fn is_http_url(url: &str) -> bool {
+    ["http://", "https://"]
+        .iter()
+        .filter_map(|scheme| url.strip_prefix(scheme))
+        .any(|rest| !rest.is_empty() && !rest.starts_with('/'))
+}
+
+/// Status code of a [`DisplayResponse`].
+///
+/// Serialized as the number the firmware expects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DisplayStatus {
+    /// Image is ready (0)
+    #[default]
+    Ok,
+    /// Server could not produce an image; the device retries later (1)
+    Error,
+}
+
+impl DisplayStatus {
+    /// Numeric code sent to the firmware.
+    pub fn code(&self) -> u32 {
+        match self {
+            DisplayStatus::Ok => 0,
+            DisplayStatus::Error => 1,
+        }
+    }
+}
+
+impl From<DisplayStatus> for u32 {
+    fn from(status: DisplayStatus) -> Self {
+        status.code()
+    }
+}
+
+impl TryFrom<u32> for DisplayStatus {
+    type Error = Error;
+
+    fn try_from(code: u32) -> Result<Self, Error> {
+        match code {
+            0 => Ok(DisplayStatus::Ok),
+            1 => Ok(DisplayStatus::Error),
+            other => Err(Error::Protocol(format!("unknown display status {}", other))),
+        }
+    }
+}
+
+impl PartialEq<u32> for DisplayStatus {
+    fn eq(&self, other: &u32) -> bool {
+        self.code() == *other
+    }
+}
+
+impl Serialize for DisplayStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for DisplayStatus {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = u32::deserialize(deserializer)?;
+        DisplayStatus::try_from(code).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Refresh rate sent to the firmware, in seconds.
+///
+/// The firmware expects the refresh rate as a JSON string; this type
+/// serializes accordingly so servers can work with plain numbers. Conversions
+/// from `u32` and `Duration` clamp to
+/// [`MIN_REFRESH_RATE`](crate::MIN_REFRESH_RATE)..=[`MAX_REFRESH_RATE`](crate::MAX_REFRESH_RATE).
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use trmnl::{RefreshRate, MIN_REFRESH_RATE};
+///
+/// assert_eq!(RefreshRate::from(Duration::from_secs(900)).seconds(), 900);
+/// assert_eq!(RefreshRate::from(0).seconds(), MIN_REFRESH_RATE);
+/// assert_eq!(serde_json::to_string(&RefreshRate::from(300)).unwrap(), "\"300\"");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RefreshRate(u32);
+
+impl RefreshRate {
+    /// Refresh rate in seconds.
+    pub fn seconds(&self) -> u32 {
+        self.0
+    }
+
+    /// Refresh rate as a `Duration`.
+    pub fn as_duration(&self) -> Duration {
+        Duration::from_secs(u64::from(self.0))
+    }
+}
+
+impl Default for RefreshRate {
+    fn default() -> Self {
+        Self(60)
+    }
+}
+
+impl From<u32> for RefreshRate {
+    fn from(seconds: u32) -> Self {
+        Self(seconds.clamp(MIN_REFRESH_RATE, MAX_REFRESH_RATE))
+    }
+}
+
+impl From<Duration> for RefreshRate {
+    fn from(duration: Duration) -> Self {
+        let seconds = u32::try_from(duration.as_secs()).unwrap_or(u32::MAX);
+        Self::from(seconds)
+    }
+}
+
+impl std::fmt::Display for RefreshRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl PartialEq<&str> for RefreshRate {
+    fn eq(&self, other: &&str) -> bool {
+        other.parse::<u32>() == Ok(self.0)
+    }
+}
+
+impl Serialize for RefreshRate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for RefreshRate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Accept both the firmware's string form and plain numbers
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(u32),
+            Text(String),
         }
+
+        let seconds = match Raw::deserialize(deserializer)? {
+            Raw::Number(n) => n,
+            Raw::Text(s) => s.trim().parse().map_err(serde::de::Error::custom)?,
+        };
+        Ok(Self::from(seconds))
     }
 }
 
 /// Response for GET /api/setup endpoint.
 ///
 /// Sent when device first connects. The device stores this configuration.
+/// A `status` other than 200 tells the firmware the device is not
+/// recognized; see [`SetupResponse::not_registered`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct SetupResponse {
+    /// Status code (200 = registered, 404 = not registered)
+    #[serde(default = "default_setup_status")]
+    pub status: u32,
+
     /// API key (can be any string for BYOS)
     pub api_key: String,
 
@@ -257,26 +1029,202 @@ impl SetupResponse {
         message: impl Into<String>,
     ) -> Self {
         Self {
+            status: SETUP_STATUS_OK,
             api_key: "byos".to_string(),
             friendly_id: friendly_id.into(),
             image_url: image_url.into(),
             message: message.into(),
         }
     }
+
+    /// Create a response for a device the server doesn't recognize.
+    ///
+    /// The firmware shows `message` and retries setup later.
+    pub fn not_registered(message: impl Into<String>) -> Self {
+        Self {
+            status: SETUP_STATUS_NOT_REGISTERED,
+            api_key: String::new(),
+            friendly_id: String::new(),
+            image_url: String::new(),
+            message: message.into(),
+        }
+    }
+
+    /// Set status code.
+    #[must_use]
+    pub fn with_status(mut self, status: u32) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Set friendly device name.
+    #[must_use]
+    pub fn with_friendly_id(mut self, friendly_id: impl Into<String>) -> Self {
+        self.friendly_id = friendly_id.into();
+        self
+    }
+
+    /// Set initial image URL.
+    #[must_use]
+    pub fn with_image_url(mut self, image_url: impl Into<String>) -> Self {
+        self.image_url = image_url.into();
+        self
+    }
+
+    /// Set welcome message.
+    #[must_use]
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    /// Whether the device was recognized.
+    pub fn is_registered(&self) -> bool {
+        self.status == SETUP_STATUS_OK
+    }
+
+    /// Set the API key the device sends back in the `Access-Token` header.
+    ///
+    /// Use [`generate_api_key`] to issue a unique key per device.
+    #[must_use]
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+}
+
+fn default_setup_status() -> u32 {
+    SETUP_STATUS_OK
+}
+
+/// Generate a random API key for a newly set up device.
+///
+/// Keys are 32 URL-safe base64 characters (192 bits) from the operating
+/// system's secure random number generator.
+///
+/// # Panics
+///
+/// Panics if the operating system's random number generator is unavailable.
+pub fn generate_api_key() -> String {
+    let mut bytes = [0u8; API_KEY_BYTES];
+    getrandom::getrandom(&mut bytes).expect("OS random number generator unavailable");
+    BASE64_URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Derive a short, human-friendly device ID from a MAC address.
+///
+/// Returns the last 6 hex digits of the MAC, uppercased, so the same device
+/// always gets the same ID (e.g. `AA:BB:CC:DD:EE:FF` becomes `DDEEFF`).
+/// Separators, case and any other non-hex characters in the input are
+/// ignored, so inputs without hex digits give an empty ID.
+pub fn generate_friendly_id(mac: &str) -> String {
+    let digits: Vec<char> = mac
+        .chars()
+        .filter(char::is_ascii_hexdigit)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let start = digits.len().saturating_sub(FRIENDLY_ID_LENGTH);
+    digits[start..].iter().collect()
+}
+
+/// Body of POST /api/log.
+///
+/// Current firmware batches log entries in a `logs` array. Older firmware
+/// posts a single bare [`LogEntry`]; that shape parses as a one-entry batch.
+///
+/// # Example
+///
+/// ```
+/// use trmnl::LogPayload;
+///
+/// let json = r#"{"logs": [{
+///     "log_id": 7,
+///     "creation_timestamp": 1700000000,
+///     "log_message": "Failed to fetch image",
+///     "log_codeline": 512,
+///     "log_sourcefile": "src/bl.cpp",
+///     "device_status_stamp": {"battery_voltage": 3.9, "wifi_rssi_level": -71}
+/// }]}"#;
+///
+/// let payload: LogPayload = serde_json::from_str(json).unwrap();
+/// assert_eq!(payload.logs[0].log_codeline, Some(512));
+///
+/// let single: LogPayload = serde_json::from_str(r#"{"log_message": "boot"}"#).unwrap();
+/// assert_eq!(single.logs.len(), 1);
+/// ```
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LogPayload {
+    /// Log entries, oldest first
+    pub logs: Vec<LogEntry>,
+}
+
+impl<'de> Deserialize<'de> for LogPayload {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Batch {
+            logs: Vec<LogEntry>,
+        }
+
+        // `LogEntry` keeps unknown fields, so it would swallow a malformed
+        // batch; only fall back to a single entry when `logs` is absent
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let logs = if value.get("logs").is_some() {
+            serde_json::from_value::<Batch>(value)
+                .map_err(serde::de::Error::custom)?
+                .logs
+        } else {
+            vec![serde_json::from_value::<LogEntry>(value).map_err(serde::de::Error::custom)?]
+        };
+        Ok(Self { logs })
+    }
 }
 
 /// Log entry from device (POST /api/log).
 ///
-/// The firmware may send device status and debug logs.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// The firmware may send device status and debug logs. Fields use the
+/// firmware's `snake_case` names; `camelCase` is also accepted when parsing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct LogEntry {
+    /// Firmware-assigned log sequence number
+    #[serde(default, alias = "logId", skip_serializing_if = "Option::is_none")]
+    pub log_id: Option<u64>,
+
+    /// When the entry was recorded (Unix seconds, device clock)
+    #[serde(
+        default,
+        alias = "creationTimestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub creation_timestamp: Option<i64>,
+
     /// Log message text
-    #[serde(default)]
+    #[serde(default, alias = "logMessage", skip_serializing_if = "Option::is_none")]
     pub log_message: Option<String>,
 
+    /// Firmware source line that emitted the entry
+    #[serde(
+        default,
+        alias = "logCodeline",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub log_codeline: Option<u32>,
+
+    /// Firmware source file that emitted the entry
+    #[serde(
+        default,
+        alias = "logSourcefile",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub log_sourcefile: Option<String>,
+
     /// Device status snapshot
-    #[serde(default)]
+    #[serde(
+        default,
+        alias = "deviceStatusStamp",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub device_status_stamp: Option<DeviceStatusStamp>,
 
     /// Any additional fields
@@ -285,28 +1233,50 @@ pub struct LogEntry {
 }
 
 /// Device status snapshot in log entries.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "snake_case")]
 pub struct DeviceStatusStamp {
     /// Battery voltage
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub battery_voltage: Option<f32>,
 
     /// WiFi signal strength
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub wifi_rssi_level: Option<i32>,
 
     /// Current refresh rate
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub refresh_rate: Option<u32>,
 
     /// Firmware version
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub current_fw_version: Option<String>,
+
+    /// Why the device woke (e.g. "timer", "button")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wakeup_reason: Option<String>,
+
+    /// Free heap in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub free_heap_size: Option<u32>,
+
+    /// WiFi connection status (e.g. "connected")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wifi_status: Option<String>,
+
+    /// Seconds since the device last went to sleep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_since_last_sleep_start: Option<u32>,
+
+    /// Special function the device is currently performing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub special_function: Option<String>,
 }
 
 /// Response for POST /api/log endpoint.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct LogResponse {
     /// Status string ("ok")
     pub status: String,
@@ -345,6 +1315,16 @@ mod tests {
         assert_eq!(device.short_id(), "E:FF");
     }
 
+    #[test]
+    fn test_device_info_mac() {
+        let device = DeviceInfo::new("AA:BB:CC:DD:EE:FF");
+        assert_eq!(
+            device.mac().unwrap(),
+            "aa-bb-cc-dd-ee-ff".parse::<MacAddress>().unwrap()
+        );
+        assert!(DeviceInfo::new("unknown").mac().is_none());
+    }
+
     #[test]
     fn test_display_response_serialization() {
         let response = DisplayResponse::new("https://example.com/screen.png", "screen.png")
@@ -357,15 +1337,376 @@ mod tests {
         assert!(!json.contains("firmware_url"));
     }
 
+    #[test]
+    fn test_image_url_timeout() {
+        let response = DisplayResponse::new("https://example.com/screen.png", "screen.png");
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("image_url_timeout"));
+
+        let json = serde_json::to_string(&response.with_image_url_timeout(45)).unwrap();
+        assert!(json.contains("\"image_url_timeout\":45"));
+    }
+
+    #[test]
+    fn test_inline_image() {
+        let png = vec![0u8; 4096];
+        let response = DisplayResponse::new("", "1.png").with_inline_image(&png);
+
+        assert!(response.has_inline_image());
+        assert_eq!(
+            response.image_url,
+            format!("data:image/png;base64,{}", BASE64_STANDARD.encode(&png))
+        );
+        // Data URIs are exempt from the URL limit
+        assert!(response.check_limits().is_ok());
+    }
+
+    #[test]
+    fn test_special_function_omitted_by_default() {
+        let response = DisplayResponse::new("https://example.com/screen.png", "screen.png");
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("special_function"));
+    }
+
+    #[test]
+    fn test_special_function() {
+        let response = DisplayResponse::new("https://example.com/screen.png", "screen.png")
+            .with_special_function("identify");
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"special_function\":\"identify\""));
+    }
+
+    #[test]
+    fn test_busy_response_keeps_image() {
+        let response = DisplayResponse::busy("https://example.com/123.png", "123.png");
+        assert_eq!(response.status, 0);
+        assert_eq!(response.filename, Some("123.png".to_string()));
+        assert_eq!(response.refresh_rate.seconds(), BUSY_RETRY_SECONDS);
+    }
+
+    #[test]
+    fn test_check_limits() {
+        let ok = DisplayResponse::new("https://example.com/1.png", "1.png");
+        assert!(ok.check_limits().is_ok());
+
+        let long_filename = DisplayResponse::new("https://example.com/1.png", "f".repeat(300));
+        let err = long_filename.check_limits().unwrap_err();
+        assert!(err.to_string().contains("filename"));
+
+        let long_firmware_url = DisplayResponse::default()
+            .with_firmware_update(format!("https://example.com/{}", "x".repeat(1100)));
+        let err = long_firmware_url.check_limits().unwrap_err();
+        assert!(err.to_string().contains("firmware_url"));
+    }
+
+    #[test]
+    fn test_check_limits_body_boundary() {
+        // Pad an otherwise unchecked field so the body lands exactly on `max`
+        let at_limit = |response: DisplayResponse, max: usize| {
+            let base = serde_json::to_vec(&response.clone().with_special_function(""))
+                .unwrap()
+                .len();
+            let padded = |extra: usize| response.clone().with_special_function("x".repeat(extra));
+            (padded(max - base), padded(max - base + 1))
+        };
+
+        let (fits, over) = at_limit(
+            DisplayResponse::new("https://example.com/1.png", "1.png"),
+            MAX_RESPONSE_SIZE,
+        );
+        assert_eq!(serde_json::to_vec(&fits).unwrap().len(), MAX_RESPONSE_SIZE);
+        assert!(fits.check_limits().is_ok());
+        assert!(over.check_limits().is_err());
+
+        let png = vec![0u8; crate::MAX_IMAGE_SIZE];
+        let (fits, over) = at_limit(
+            DisplayResponse::new("", "1.png").with_inline_image(&png),
+            MAX_INLINE_RESPONSE_SIZE,
+        );
+        assert!(fits.check_limits().is_ok());
+        assert!(over.check_limits().is_err());
+
+        // An inline image over MAX_IMAGE_SIZE can't fit
+        let png = vec![0u8; crate::MAX_IMAGE_SIZE + 2048];
+        let too_big = DisplayResponse::new("", "1.png").with_inline_image(&png);
+        assert!(too_big.check_limits().is_err());
+    }
+
     #[test]
     fn test_setup_response() {
         let response = SetupResponse::new("my-device", "https://example.com/setup.png", "Welcome!");
 
         let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"status\":200"));
         assert!(json.contains("\"api_key\":\"byos\""));
         assert!(json.contains("\"friendly_id\":\"my-device\""));
     }
 
+    #[cfg(feature = "schedule")]
+    #[test]
+    fn test_sleep_until() {
+        use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+
+        let now = Utc.with_ymd_and_hms(2024, 12, 14, 22, 0, 0).unwrap();
+        let response = || DisplayResponse::new("https://example.com/1.png", "1.png");
+
+        let wake = now + ChronoDuration::hours(8);
+        assert_eq!(
+            response()
+                .sleep_until_from(wake, now)
+                .refresh_rate
+                .seconds(),
+            8 * 3600
+        );
+
+        let past = now - ChronoDuration::minutes(5);
+        assert_eq!(
+            response()
+                .sleep_until_from(past, now)
+                .refresh_rate
+                .seconds(),
+            MIN_REFRESH_RATE
+        );
+
+        let far = now + ChronoDuration::days(3);
+        assert_eq!(
+            response().sleep_until_from(far, now).refresh_rate.seconds(),
+            MAX_REFRESH_RATE
+        );
+    }
+
+    #[cfg(feature = "schedule")]
+    #[test]
+    fn test_with_schedule() {
+        use chrono::{TimeZone, Utc};
+
+        let schedule = crate::schedule::RefreshSchedule::from_yaml(
+            r#"
+timezone: UTC
+default_refresh_rate: 900
+battery_scaling:
+  - below: 20
+    multiplier: 4
+schedule:
+  - start: "09:00"
+    end: "17:00"
+    refresh_rate: 300
+"#,
+        )
+        .unwrap();
+        let device = DeviceInfo::new("AA:BB:CC:DD:EE:FF");
+        let low = device.clone().with_battery_voltage(3.1);
+        let rate = |device: &DeviceInfo, hour: u32, min: u32| {
+            let now = Utc.with_ymd_and_hms(2025, 1, 6, hour, min, 0).unwrap();
+            DisplayResponse::new("https://example.com/1.png", "1.png")
+                .with_schedule_at(&schedule, device, now)
+                .refresh_rate
+                .seconds()
+        };
+
+        assert_eq!(rate(&device, 12, 0), 300);
+        assert_eq!(rate(&low, 12, 0), 1200);
+        // Clamped so the device wakes when the 09:00 rule starts
+        assert_eq!(rate(&device, 8, 50), 600);
+        assert_eq!(rate(&low, 16, 50), 600);
+    }
+
+    #[test]
+    fn test_battery_state() {
+        let state = |v: f32| DeviceInfo::new("x").with_battery_voltage(v).battery_state();
+        assert_eq!(state(4.3), Some(BatteryState::Charging));
+        assert_eq!(state(4.2), Some(BatteryState::Full));
+        assert_eq!(state(3.8), Some(BatteryState::Discharging));
+        assert_eq!(state(3.2), Some(BatteryState::Critical));
+        assert_eq!(DeviceInfo::new("x").battery_state(), None);
+
+        let strict = BatteryThresholds {
+            critical_mv: 3500,
+            ..BatteryThresholds::default()
+        };
+        let device = DeviceInfo::new("x").with_battery_voltage(3.45);
+        assert_eq!(
+            device.battery_state_with(&strict),
+            Some(BatteryState::Critical)
+        );
+    }
+
+    #[test]
+    fn test_unchanged() {
+        let response = DisplayResponse::unchanged("1.png");
+        assert_eq!(response.status, DisplayStatus::Ok);
+        assert_eq!(response.filename.as_deref(), Some("1.png"));
+        assert!(response.image_url.is_empty());
+        assert!(response.check_limits().is_ok());
+    }
+
+    #[test]
+    fn test_display_status_codes() {
+        assert_eq!(DisplayResponse::error().status, DisplayStatus::Error);
+        assert_eq!(u32::from(DisplayStatus::Error), 1);
+        assert_eq!(DisplayStatus::try_from(0).unwrap(), DisplayStatus::Ok);
+        assert!(DisplayStatus::try_from(7).is_err());
+
+        let json = serde_json::to_string(&DisplayResponse::error()).unwrap();
+        assert!(json.contains("\"status\":1"));
+        let parsed: DisplayResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.status, DisplayStatus::Error);
+    }
+
+    #[test]
+    fn test_refresh_rate_conversions() {
+        assert_eq!(RefreshRate::from(0).seconds(), MIN_REFRESH_RATE);
+        assert_eq!(RefreshRate::from(u32::MAX).seconds(), MAX_REFRESH_RATE);
+        assert_eq!(
+            RefreshRate::from(Duration::from_secs(u64::MAX)).seconds(),
+            MAX_REFRESH_RATE
+        );
+        assert_eq!(
+            RefreshRate::from(Duration::from_secs(120)).as_duration(),
+            Duration::from_secs(120)
+        );
+
+        let from_string: RefreshRate = serde_json::from_str("\"1800\"").unwrap();
+        let from_number: RefreshRate = serde_json::from_str("1800").unwrap();
+        assert_eq!(from_string, from_number);
+        assert!(serde_json::from_str::<RefreshRate>("\"soon\"").is_err());
+
+        let too_short: RefreshRate = serde_json::from_str("\"0\"").unwrap();
+        let too_long: RefreshRate = serde_json::from_str("999999").unwrap();
+        assert_eq!(too_short.seconds(), MIN_REFRESH_RATE);
+        assert_eq!(too_long.seconds(), MAX_REFRESH_RATE);
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        let ok = DisplayResponse::builder()
+            .with_image_url("https://example.com/1.png")
+            .with_filename("1.png")
+            .with_firmware_update("http://example.com/fw.bin")
+            .build()
+            .unwrap();
+        assert!(ok.update_firmware);
+        assert_eq!(ok.refresh_rate, "60");
+
+        let inline = DisplayResponse::builder()
+            .with_inline_image(&[1, 2, 3])
+            .with_filename("1.png")
+            .build();
+        assert!(inline.is_ok());
+
+        let base = || DisplayResponse::builder().with_image_url("https://example.com/1.png");
+        assert!(base().build().is_err()); // missing filename
+        assert!(base().with_filename("").build().is_err());
+        let base = || base().with_filename("1.png");
+        assert!(base().with_refresh_rate(0).build().is_err());
+        assert!(base()
+            .with_refresh_rate(MAX_REFRESH_RATE + 1)
+            .build()
+            .is_err());
+        assert!(base().with_firmware_update("fw.bin").build().is_err());
+        assert!(DisplayResponse::builder()
+            .with_image_url("https:///1.png")
+            .with_filename("1.png")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_setup_not_registered() {
+        let response = SetupResponse::not_registered("Unknown device");
+        assert!(!response.is_registered());
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"status\":404"));
+        assert!(json.contains("\"message\":\"Unknown device\""));
+
+        let legacy: SetupResponse = serde_json::from_str(
+            r#"{"api_key":"k","friendly_id":"f","image_url":"u","message":"m"}"#,
+        )
+        .unwrap();
+        assert!(legacy.is_registered());
+    }
+
+    #[test]
+    fn test_setup_with_generated_credentials() {
+        let key = generate_api_key();
+        assert_eq!(key.len(), 32);
+        assert_ne!(key, generate_api_key());
+
+        let response = SetupResponse::new(
+            generate_friendly_id("aa:bb:cc:dd:ee:ff"),
+            "https://example.com/setup.png",
+            "Welcome!",
+        )
+        .with_api_key(key.clone());
+        assert_eq!(response.api_key, key);
+        assert_eq!(response.friendly_id, "DDEEFF");
+        assert_eq!(generate_friendly_id("AB"), "AB");
+        assert_eq!(generate_friendly_id("unknown"), "");
+        assert_eq!(generate_friendly_id("xx:00:1b:zz:2c:3d:4e"), "2C3D4E");
+    }
+
+    #[test]
+    fn test_log_payload_parsing() {
+        let json = r#"{"logs": [
+            {"log_id": 1, "creation_timestamp": 1700000000, "log_message": "boot",
+             "log_codeline": 42, "log_sourcefile": "src/bl.cpp",
+             "device_status_stamp": {"wifi_rssi_level": -60, "wakeup_reason": "timer",
+                                     "free_heap_size": 160000, "wifi_status": "connected",
+                                     "time_since_last_sleep_start": 900,
+                                     "special_function": "none"}},
+            {"log_message": "sleep", "unknown_field": true}
+        ]}"#;
+        let payload: LogPayload = serde_json::from_str(json).unwrap();
+
+        assert_eq!(payload.logs.len(), 2);
+        let first = &payload.logs[0];
+        assert_eq!(first.log_id, Some(1));
+        assert_eq!(first.creation_timestamp, Some(1_700_000_000));
+        assert_eq!(first.log_sourcefile.as_deref(), Some("src/bl.cpp"));
+        assert!(first.extra.is_empty());
+        let stamp = first.device_status_stamp.as_ref().unwrap();
+        assert_eq!(stamp.wifi_rssi_level, Some(-60));
+        assert_eq!(stamp.wakeup_reason.as_deref(), Some("timer"));
+        assert_eq!(stamp.free_heap_size, Some(160_000));
+        assert_eq!(stamp.wifi_status.as_deref(), Some("connected"));
+        assert_eq!(stamp.time_since_last_sleep_start, Some(900));
+        assert_eq!(stamp.special_function.as_deref(), Some("none"));
+        assert!(payload.logs[1].extra.contains_key("unknown_field"));
+    }
+
+    #[test]
+    fn test_log_payload_accepts_single_entry() {
+        let json = r#"{"log_message": "boot", "device_status_stamp": {"battery_voltage": 3.9}}"#;
+        let payload: LogPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.logs.len(), 1);
+        assert_eq!(payload.logs[0].log_message.as_deref(), Some("boot"));
+
+        assert!(serde_json::from_str::<LogPayload>(r#"{"logs": "boot"}"#).is_err());
+        assert!(serde_json::from_str::<LogPayload>(r#"[1, 2]"#).is_err());
+    }
+
+    #[test]
+    fn test_log_payload_round_trip() {
+        let json = r#"{"logs":[{"log_id":3,"log_message":"wake","device_status_stamp":{"battery_voltage":4.0},"custom":1}]}"#;
+        let payload: LogPayload = serde_json::from_str(json).unwrap();
+
+        let value = serde_json::to_value(&payload).unwrap();
+        assert_eq!(
+            value,
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+
+        // camelCase input is re-emitted with firmware field names
+        let entry: LogEntry = serde_json::from_str(r#"{"logMessage":"hi"}"#).unwrap();
+        assert_eq!(
+            serde_json::to_string(&entry).unwrap(),
+            r#"{"log_message":"hi"}"#
+        );
+    }
+
     #[test]
     fn test_log_entry_parsing() {
         let json = r#"{"logMessage": "test", "deviceStatusStamp": {"battery_voltage": 4.1}}"#;
@@ -17,6 +17,10 @@ pub enum Error {
     #[error("I/O error: {0}")]
     Io(String),
 
+    /// Rendering did not finish within the configured timeout
+    #[error("Render timed out after {0:?}")]
+    RenderTimeout(std::time::Duration),
+
     /// Chrome/browser not found or failed
     #[error("Chrome error: {0}")]
     Chrome(String),
@@ -30,6 +34,14 @@ pub enum Error {
         max: usize,
     },
 
+    /// Image could not be decoded or encoded
+    #[error("Image error: {0}")]
+    Image(String),
+
+    /// Response violates a firmware protocol limit or requirement
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+
     /// JSON serialization error
     #[error("JSON error: {0}")]
     Json(String),
@@ -39,6 +51,41 @@ pub enum Error {
     Config(String),
 }
 
+#[cfg(feature = "http")]
+impl Error {
+    /// HTTP status code used when this error is returned from a handler.
+    ///
+    /// Protocol errors (e.g. an unparseable MAC address) are the client's
+    /// fault and map to `400 Bad Request`; render timeouts map to
+    /// `504 Gateway Timeout`; everything else is a `500`.
+    pub fn status_code(&self) -> http::StatusCode {
+        match self {
+            Error::Protocol(_) => http::StatusCode::BAD_REQUEST,
+            Error::RenderTimeout(_) => http::StatusCode::GATEWAY_TIMEOUT,
+            _ => http::StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Plain text body sent to the client for this error.
+    ///
+    /// Client errors carry the error message. Server errors only carry the
+    /// status's reason phrase, keeping file paths, Chrome/ImageMagick output
+    /// and configuration details away from devices; the full error is
+    /// logged with `tracing` instead.
+    pub(crate) fn response_body(&self) -> String {
+        let status = self.status_code();
+        if status.is_server_error() {
+            tracing::error!("Request failed: {}", self);
+            status
+                .canonical_reason()
+                .unwrap_or("Internal Server Error")
+                .to_string()
+        } else {
+            self.to_string()
+        }
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Error::Io(err.to_string())
@@ -0,0 +1,374 @@
+//! Framework-neutral integration built on the `http` crate.
+//!
+//! For servers not using axum (hyper, warp, poem, `lambda_http`, ...).
+//! Parse device headers with [`DeviceInfo::from_headers`], then hand the
+//! request to [`handle_setup`], [`handle_display`] or [`handle_log`], which
+//! build complete JSON responses.
+//!
+//! # Example
+//!
+//! ```
+//! use trmnl::http_ext::handle_display;
+//! use trmnl::DisplayResponse;
+//!
+//! # tokio::runtime::Runtime::new().unwrap().block_on(async {
+//! let request = http::Request::get("/api/display")
+//!     .header("ID", "AA:BB:CC:DD:EE:FF")
+//!     .body(())
+//!     .unwrap();
+//!
+//! let response = handle_display(&request, |_device| async move {
+//!     Ok(DisplayResponse::new("https://example.com/1.png", "1.png"))
+//! })
+//! .await;
+//! assert_eq!(response.status(), 200);
+//! # });
+//! ```
+
+use std::future::Future;
+
+use http::header::CONTENT_TYPE;
+use http::{HeaderMap, Request, Response, StatusCode, Uri};
+use serde::Serialize;
+
+use crate::auth::{token_from_query, Secret, TokenAuth};
+use crate::error::Error;
+use crate::{DeviceInfo, DisplayResponse, LogPayload, LogResponse, SetupResponse};
+
+impl DeviceInfo {
+    /// Parse device info from TRMNL firmware headers.
+    ///
+    /// Reads `ID`, `Battery-Voltage`, `FW-Version`, `RSSI`,
+    /// `Refresh-Rate`, `Access-Token`, `User-Agent`, `Width`, `Height` and
+    /// `Model`. A missing `ID` becomes `"unknown"`; other missing or
+    /// unparseable headers become `None`.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let text = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|s| s.to_string())
+        };
+
+        DeviceInfo {
+            mac_address: text("ID").unwrap_or_else(|| "unknown".to_string()),
+            battery_voltage: text("Battery-Voltage").and_then(|s| s.parse().ok()),
+            firmware_version: text("FW-Version"),
+            rssi: text("RSSI").and_then(|s| s.parse().ok()),
+            refresh_rate: text("Refresh-Rate").and_then(|s| s.parse().ok()),
+            access_token: text("Access-Token").map(Secret::new),
+            user_agent: text("User-Agent"),
+            width: text("Width").and_then(|s| s.parse().ok()),
+            height: text("Height").and_then(|s| s.parse().ok()),
+            model: text("Model"),
+        }
+    }
+}
+
+impl TokenAuth {
+    /// Read the `token` query parameter from a request URI, stripping the
+    /// firmware's `/api/...` suffix.
+    pub fn from_uri(uri: &Uri) -> Self {
+        TokenAuth::new(uri.query().and_then(token_from_query))
+    }
+}
+
+/// Public base URL of the server (scheme and host, no trailing slash).
+///
+/// Image URLs sent to devices must be absolute. [`BaseUrl::from_headers`]
+/// derives the URL the device used from the `Host` header. Behind a reverse
+/// proxy, [`BaseUrl::from_forwarded_headers`] prefers `X-Forwarded-Proto`
+/// and `X-Forwarded-Host`; clients control those headers, so only use it
+/// when the proxy sets or strips them. Use [`BaseUrl::new`] to override
+/// detection.
+///
+/// # Example
+///
+/// ```
+/// use trmnl::http_ext::BaseUrl;
+///
+/// let request = http::Request::get("/api/display")
+///     .header("Host", "10.0.0.5:3000")
+///     .header("X-Forwarded-Proto", "https")
+///     .header("X-Forwarded-Host", "trmnl.example.com")
+///     .body(())
+///     .unwrap();
+///
+/// let base = BaseUrl::from_headers(request.headers()).unwrap();
+/// assert_eq!(base.as_str(), "http://10.0.0.5:3000");
+///
+/// let base = BaseUrl::from_forwarded_headers(request.headers()).unwrap();
+/// assert_eq!(base.join("/images/1.png"), "https://trmnl.example.com/images/1.png");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BaseUrl(String);
+
+impl BaseUrl {
+    /// Use a fixed base URL such as `"https://trmnl.example.com"`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self(url.into().trim_end_matches('/').to_string())
+    }
+
+    /// Derive an `http://` base URL from the `Host` header.
+    ///
+    /// Returns `None` if `Host` is missing or is not a plain `host[:port]`.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let host = first_value(headers, "Host").filter(|h| is_plain_host(h))?;
+        Some(Self::new(format!("http://{}", host)))
+    }
+
+    /// Derive the base URL from `X-Forwarded-Proto` and `X-Forwarded-Host`,
+    /// falling back to `http` and the `Host` header.
+    ///
+    /// Only the first value of comma-separated forwarded headers is used.
+    /// Returns `None` if no host is present, if the scheme is not `http` or
+    /// `https`, or if the host contains URL syntax such as `/` or `@`.
+    pub fn from_forwarded_headers(headers: &HeaderMap) -> Option<Self> {
+        let host =
+            first_value(headers, "X-Forwarded-Host").or_else(|| first_value(headers, "Host"))?;
+        let scheme = first_value(headers, "X-Forwarded-Proto").unwrap_or("http");
+        let scheme = ["http", "https"]
+            .into_iter()
+            .find(|s| s.eq_ignore_ascii_case(scheme))?;
+        if !is_plain_host(host) {
+            return None;
+        }
+        Some(Self::new(format!("{}://{}", scheme, host)))
+    }
+
+    /// The URL as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Append `path` (with or without a leading slash).
+    pub fn join(&self, path: &str) -> String {
+        format!("{}/{}", self.0, path.trim_start_matches('/'))
+    }
+}
+
+/// First comma-separated value of header `name`, trimmed.
+fn first_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Whether `host` is a bare `host[:port]` that can't change the meaning of
+/// the URL it is placed in (no path, userinfo, query or whitespace).
+fn is_plain_host(host: &str) -> bool {
+    !host.is_empty()
+        && !host
+            .chars()
+            .any(|c| matches!(c, '/' | '\\' | '@' | '?' | '#') || c.is_whitespace())
+}
+
+impl std::fmt::Display for BaseUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Serialize `body` as a JSON response with the given status.
+pub fn json_response<T: Serialize>(status: StatusCode, body: &T) -> Response<Vec<u8>> {
+    match serde_json::to_vec(body) {
+        Ok(json) => Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, "application/json")
+            .body(json)
+            .expect("valid response parts"),
+        Err(e) => error_response(&Error::from(e)),
+    }
+}
+
+/// Plain text response for `err`, with [`Error::status_code`].
+///
+/// The message is only included for client errors; server errors get a
+/// generic body and are logged.
+pub fn error_response(err: &Error) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(err.status_code())
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(err.response_body().into_bytes())
+        .expect("valid response parts")
+}
+
+/// Handle `GET /api/setup`.
+///
+/// Requests without a valid MAC address get
+/// [`SetupResponse::not_registered`]; otherwise `setup` decides the
+/// response. Errors from `setup` become an [`error_response`].
+pub async fn handle_setup<B, F, Fut>(request: &Request<B>, setup: F) -> Response<Vec<u8>>
+where
+    F: FnOnce(DeviceInfo) -> Fut,
+    Fut: Future<Output = Result<SetupResponse, Error>>,
+{
+    let device = DeviceInfo::from_headers(request.headers());
+    if device.mac().is_none() {
+        let response = SetupResponse::not_registered("Missing or invalid ID header");
+        return json_response(StatusCode::OK, &response);
+    }
+    match setup(device).await {
+        Ok(response) => json_response(StatusCode::OK, &response),
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Handle `GET /api/display`.
+///
+/// Errors from `display` are logged and answered with
+/// [`DisplayResponse::error`], so the device keeps its current image and
+/// retries later. The log line leaves out the device's MAC address; attach
+/// it (or an obfuscated ID) in your own span if you need it.
+pub async fn handle_display<B, F, Fut>(request: &Request<B>, display: F) -> Response<Vec<u8>>
+where
+    F: FnOnce(DeviceInfo) -> Fut,
+    Fut: Future<Output = Result<DisplayResponse, Error>>,
+{
+    let device = DeviceInfo::from_headers(request.headers());
+    let response = display(device).await.unwrap_or_else(|e| {
+        tracing::warn!("Display request failed: {}", e);
+        DisplayResponse::error()
+    });
+    json_response(StatusCode::OK, &response)
+}
+
+/// Handle `POST /api/log` with the raw request body.
+///
+/// Malformed bodies get `400 Bad Request`. Errors from `log` are logged and
+/// still acknowledged, so the firmware doesn't resend its logs.
+pub async fn handle_log<B, F, Fut>(request: &Request<B>, log: F) -> Response<Vec<u8>>
+where
+    B: AsRef<[u8]>,
+    F: FnOnce(DeviceInfo, LogPayload) -> Fut,
+    Fut: Future<Output = Result<(), Error>>,
+{
+    let device = DeviceInfo::from_headers(request.headers());
+    let payload: LogPayload = match serde_json::from_slice(request.body().as_ref()) {
+        Ok(payload) => payload,
+        Err(e) => return error_response(&Error::Protocol(format!("invalid log payload: {}", e))),
+    };
+    if let Err(e) = log(device, payload).await {
+        tracing::warn!("Failed to record device logs: {}", e);
+    }
+    json_response(StatusCode::OK, &LogResponse::ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(response: &Response<Vec<u8>>) -> serde_json::Value {
+        serde_json::from_slice(response.body()).unwrap()
+    }
+
+    #[test]
+    fn test_device_info_from_headers() {
+        let request = Request::get("/api/display")
+            .header("ID", "AA:BB:CC:DD:EE:FF")
+            .header("Battery-Voltage", "4.1")
+            .header("Width", "not-a-number")
+            .body(())
+            .unwrap();
+        let device = DeviceInfo::from_headers(request.headers());
+        assert_eq!(device.mac_address, "AA:BB:CC:DD:EE:FF");
+        assert_eq!(device.battery_voltage, Some(4.1));
+        assert_eq!(device.width, None);
+
+        let device = DeviceInfo::from_headers(&HeaderMap::new());
+        assert_eq!(device.mac_address, "unknown");
+    }
+
+    #[test]
+    fn test_base_url_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(BaseUrl::from_headers(&headers), None);
+
+        headers.insert("Host", "192.168.1.10:3000".parse().unwrap());
+        let base = BaseUrl::from_headers(&headers).unwrap();
+        assert_eq!(base.as_str(), "http://192.168.1.10:3000");
+
+        headers.insert("X-Forwarded-Proto", "https, http".parse().unwrap());
+        headers.insert("X-Forwarded-Host", "trmnl.example.com".parse().unwrap());
+        let base = BaseUrl::from_headers(&headers).unwrap();
+        assert_eq!(base.as_str(), "http://192.168.1.10:3000");
+        let base = BaseUrl::from_forwarded_headers(&headers).unwrap();
+        assert_eq!(
+            base.join("images/1.png"),
+            "https://trmnl.example.com/images/1.png"
+        );
+
+        headers.insert("X-Forwarded-Proto", "javascript".parse().unwrap());
+        assert_eq!(BaseUrl::from_forwarded_headers(&headers), None);
+
+        headers.insert("X-Forwarded-Proto", "HTTPS".parse().unwrap());
+        for host in ["evil.example/x", "user@evil.example", "a b", "a?b"] {
+            headers.insert("X-Forwarded-Host", host.parse().unwrap());
+            assert_eq!(BaseUrl::from_forwarded_headers(&headers), None);
+        }
+
+        headers.insert("Host", "evil.example/path".parse().unwrap());
+        assert_eq!(BaseUrl::from_headers(&headers), None);
+
+        assert_eq!(
+            BaseUrl::new("https://a.example/").as_str(),
+            "https://a.example"
+        );
+    }
+
+    #[test]
+    fn test_token_from_uri() {
+        let uri: Uri = "/api/display?token=s3cret/api/display".parse().unwrap();
+        assert!(TokenAuth::from_uri(&uri).validate("s3cret").is_ok());
+    }
+
+    #[test]
+    fn test_error_response_hides_server_errors() {
+        let response = error_response(&Error::Io("/srv/trmnl/secret.png: denied".to_string()));
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(response.body(), b"Internal Server Error");
+
+        let response = error_response(&Error::Protocol("invalid MAC address: x".to_string()));
+        assert_eq!(response.body(), b"Protocol error: invalid MAC address: x");
+    }
+
+    #[tokio::test]
+    async fn test_handle_display_error() {
+        let request = Request::get("/api/display").body(()).unwrap();
+        let response = handle_display(&request, |_device| async {
+            Err(Error::Render("boom".to_string()))
+        })
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(json(&response)["status"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_handle_setup_and_log() {
+        let request = Request::get("/api/setup").body(()).unwrap();
+        let response = handle_setup(&request, |_device| async {
+            Ok(SetupResponse::new("ABC123", "", "hi"))
+        })
+        .await;
+        assert_eq!(json(&response)["status"], 404);
+
+        let request = Request::post("/api/log")
+            .header("ID", "AA:BB:CC:DD:EE:FF")
+            .body(br#"{"logs":[{"log_message":"hi"}]}"#.to_vec())
+            .unwrap();
+        let response = handle_log(&request, |_device, payload| async move {
+            assert_eq!(payload.logs.len(), 1);
+            Ok(())
+        })
+        .await;
+        assert_eq!(json(&response)["status"], "ok");
+
+        let request = Request::post("/api/log").body(b"nope".to_vec()).unwrap();
+        let response = handle_log(&request, |_device, _payload| async { Ok(()) }).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
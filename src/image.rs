@@ -0,0 +1,525 @@
+//! PNG inspection and validation for TRMNL displays.
+//!
+//! Useful when images are produced outside this crate (a camera snapshot,
+//! another renderer, a design tool) and you want to know whether the
+//! firmware will accept them before pointing a device at the URL.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use trmnl::image::validate_image;
+//!
+//! let png = std::fs::read("screen.png")?;
+//! let report = validate_image(&png)?;
+//!
+//! if !report.is_valid() {
+//!     for problem in &report.problems {
+//!         eprintln!("{}", problem);
+//!     }
+//! }
+//! ```
+
+use std::collections::HashSet;
+
+use crate::error::Error;
+use crate::profile::DisplayProfile;
+
+/// Maximum PNG bit depth recommended for e-ink rendering.
+pub const MAX_BIT_DEPTH: u8 = 4;
+
+/// Maximum number of distinct colors recommended for e-ink rendering.
+pub const MAX_COLORS: usize = 16;
+
+/// Result of inspecting a PNG image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageReport {
+    /// Image width in pixels
+    pub width: u32,
+    /// Image height in pixels
+    pub height: u32,
+    /// Encoded file size in bytes
+    pub size: usize,
+    /// PNG bit depth per channel (1, 2, 4, 8 or 16)
+    pub bit_depth: u8,
+    /// Number of distinct colors in the decoded image
+    pub color_count: usize,
+    /// Everything that would prevent or degrade display on a TRMNL
+    pub problems: Vec<ImageProblem>,
+}
+
+impl ImageReport {
+    /// Whether the image passed every check.
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A single check that an image failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImageProblem {
+    /// Dimensions differ from the display size
+    WrongDimensions {
+        /// Actual width
+        width: u32,
+        /// Actual height
+        height: u32,
+        /// Display width
+        expected_width: u32,
+        /// Display height
+        expected_height: u32,
+    },
+    /// File exceeds the firmware size limit
+    TooLarge {
+        /// Actual size in bytes
+        size: usize,
+        /// Maximum allowed size
+        max: usize,
+    },
+    /// Bit depth is higher than needed for e-ink
+    BitDepthTooHigh(u8),
+    /// More distinct colors than the panel can show
+    TooManyColors {
+        /// Distinct colors in the image
+        count: usize,
+        /// Gray levels the panel can show
+        max: usize,
+    },
+}
+
+impl std::fmt::Display for ImageProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageProblem::WrongDimensions {
+                width,
+                height,
+                expected_width,
+                expected_height,
+            } => write!(
+                f,
+                "Image is {}x{}, expected {}x{}",
+                width, height, expected_width, expected_height
+            ),
+            ImageProblem::TooLarge { size, max } => {
+                write!(f, "Image is {} bytes (max {} bytes)", size, max)
+            }
+            ImageProblem::BitDepthTooHigh(depth) => write!(
+                f,
+                "Bit depth is {} (max {} recommended)",
+                depth, MAX_BIT_DEPTH
+            ),
+            ImageProblem::TooManyColors { count, max } => {
+                write!(f, "Image has {} colors (max {} recommended)", count, max)
+            }
+        }
+    }
+}
+
+/// Decode a PNG and check it against TRMNL display requirements.
+///
+/// Checks dimensions (800x480), file size (<90KB), bit depth (<=4) and
+/// distinct color count (<=16), i.e. [`DisplayProfile::TRMNL_OG`]. Failed checks are listed in
+/// [`ImageReport::problems`] rather than returned as errors.
+///
+/// # Errors
+///
+/// Returns `Error::Image` if the bytes are not a decodable PNG.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let report = trmnl::validate_image(&png_bytes)?;
+/// assert!(report.is_valid());
+/// ```
+pub fn validate_image(bytes: &[u8]) -> Result<ImageReport, Error> {
+    validate_image_for(bytes, &DisplayProfile::TRMNL_OG)
+}
+
+/// Decode a PNG and check it against a specific display's requirements.
+///
+/// Like [`validate_image`], but dimensions, file size and color count come
+/// from `profile`.
+///
+/// # Errors
+///
+/// Returns `Error::Image` if the bytes are not a decodable PNG.
+pub fn validate_image_for(bytes: &[u8], profile: &DisplayProfile) -> Result<ImageReport, Error> {
+    let decoded = decode(bytes)?;
+    let (width, height, bit_depth) = (decoded.width, decoded.height, decoded.bit_depth);
+    let color_count = decoded.pixels().collect::<HashSet<_>>().len();
+    let max_colors = profile.palette as usize;
+
+    let mut problems = Vec::new();
+    if width != profile.width || height != profile.height {
+        problems.push(ImageProblem::WrongDimensions {
+            width,
+            height,
+            expected_width: profile.width,
+            expected_height: profile.height,
+        });
+    }
+    if bytes.len() > profile.max_bytes {
+        problems.push(ImageProblem::TooLarge {
+            size: bytes.len(),
+            max: profile.max_bytes,
+        });
+    }
+    if bit_depth > MAX_BIT_DEPTH {
+        problems.push(ImageProblem::BitDepthTooHigh(bit_depth));
+    }
+    if color_count > max_colors {
+        problems.push(ImageProblem::TooManyColors {
+            count: color_count,
+            max: max_colors,
+        });
+    }
+
+    Ok(ImageReport {
+        width,
+        height,
+        size: bytes.len(),
+        bit_depth,
+        color_count,
+        problems,
+    })
+}
+
+/// Pixel-level comparison of two images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDiff {
+    /// Number of pixels whose gray level differs
+    pub changed_pixels: u64,
+    /// Number of pixels compared
+    pub total_pixels: u64,
+}
+
+impl ImageDiff {
+    /// Fraction of pixels that changed (0.0 - 1.0).
+    pub fn changed_fraction(&self) -> f64 {
+        if self.total_pixels == 0 {
+            0.0
+        } else {
+            self.changed_pixels as f64 / self.total_pixels as f64
+        }
+    }
+
+    /// Whether more than `threshold` (0.0 - 1.0) of the pixels changed.
+    ///
+    /// A threshold of `0.0` treats any changed pixel as a change.
+    pub fn exceeds(&self, threshold: f64) -> bool {
+        self.changed_pixels > 0 && self.changed_fraction() > threshold
+    }
+}
+
+/// Compare a newly rendered PNG with the previous one.
+///
+/// Every e-ink refresh flashes the panel and adds ghosting, so when a
+/// re-render produces (nearly) the same picture it is better to hand the
+/// device its previous filename and let the firmware skip the refresh.
+///
+/// Pixels are compared by gray level, so an RGB and a grayscale encoding of
+/// the same picture are considered identical. Images with different
+/// dimensions are reported as fully changed.
+///
+/// # Errors
+///
+/// Returns `Error::Image` if either image is not a decodable PNG.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::image::diff_images;
+///
+/// let diff = diff_images(&previous_png, &new_png)?;
+/// if !diff.exceeds(0.001) {
+///     // Less than 0.1% changed: keep the old image
+///     return Json(DisplayResponse::new(previous_url, previous_filename));
+/// }
+/// ```
+pub fn diff_images(previous: &[u8], current: &[u8]) -> Result<ImageDiff, Error> {
+    let previous = decode(previous)?;
+    let current = decode(current)?;
+    let total_pixels = u64::from(current.width) * u64::from(current.height);
+
+    if previous.width != current.width || previous.height != current.height {
+        return Ok(ImageDiff {
+            changed_pixels: total_pixels,
+            total_pixels,
+        });
+    }
+
+    let changed_pixels = previous
+        .luma()
+        .zip(current.luma())
+        .filter(|(a, b)| a != b)
+        .count() as u64;
+
+    Ok(ImageDiff {
+        changed_pixels,
+        total_pixels,
+    })
+}
+
+/// Re-encode a PNG as an indexed grayscale PNG at 1, 2 or 4 bits per pixel.
+///
+/// Each pixel's gray level is mapped to the nearest of `2^bits` evenly
+/// spaced levels (black to white) and written with an explicit palette.
+/// This gives predictable, small files regardless of how the source was
+/// encoded: a typical dashboard at 2-bit depth is well under the 90KB
+/// firmware limit.
+///
+/// No dithering is applied; quantize or dither beforehand (the `render`
+/// pipeline's ImageMagick step does) if the source has smooth gradients.
+///
+/// # Errors
+///
+/// Returns `Error::Image` if `bits` is not 1, 2 or 4, or the input is not
+/// a decodable PNG.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let small = trmnl::image::encode_indexed(&png, 2)?;
+/// ```
+pub fn encode_indexed(bytes: &[u8], bits: u8) -> Result<Vec<u8>, Error> {
+    let depth = match bits {
+        1 | 2 | 4 => png::BitDepth::from_u8(bits).unwrap_or(png::BitDepth::One),
+        _ => {
+            return Err(Error::Image(format!(
+                "Unsupported indexed bit depth {} (expected 1, 2 or 4)",
+                bits
+            )))
+        }
+    };
+
+    let decoded = decode(bytes)?;
+    let max_index = (1u32 << bits) - 1;
+    let palette: Vec<u8> = (0..=max_index)
+        .flat_map(|i| {
+            let level = (i * 255 / max_index) as u8;
+            [level, level, level]
+        })
+        .collect();
+
+    // Pack pixels MSB-first; each row starts on a byte boundary
+    let per_byte = (8 / bits) as usize;
+    let width = decoded.width as usize;
+    let row_bytes = width.div_ceil(per_byte);
+    let mut data = vec![0u8; row_bytes * decoded.height as usize];
+    for (i, luma) in decoded.luma().enumerate() {
+        let (row, col) = (i / width, i % width);
+        let index = (u32::from(luma) * max_index + 127) / 255;
+        let shift = 8 - bits as usize * (col % per_byte + 1);
+        data[row * row_bytes + col / per_byte] |= (index as u8) << shift;
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, decoded.width, decoded.height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(depth);
+    encoder.set_palette(palette);
+    encoder.set_compression(png::Compression::Best);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|e| Error::Image(format!("Failed to encode PNG: {}", e)))?;
+
+    Ok(out)
+}
+
+/// A PNG decoded to 8 bits per sample.
+struct Decoded {
+    width: u32,
+    height: u32,
+    /// Bit depth as stored in the file, before normalization
+    bit_depth: u8,
+    color_type: png::ColorType,
+    data: Vec<u8>,
+}
+
+impl Decoded {
+    /// Raw samples for each pixel.
+    fn pixels(&self) -> impl Iterator<Item = &[u8]> {
+        self.data.chunks_exact(self.color_type.samples())
+    }
+
+    /// Gray level (0-255) of each pixel, ignoring alpha.
+    fn luma(&self) -> impl Iterator<Item = u8> + '_ {
+        self.pixels().map(|p| match p.len() {
+            1 | 2 => p[0],
+            _ => {
+                ((u32::from(p[0]) * 299 + u32::from(p[1]) * 587 + u32::from(p[2]) * 114) / 1000)
+                    as u8
+            }
+        })
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<Decoded, Error> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| Error::Image(format!("Failed to read PNG header: {}", e)))?;
+
+    let bit_depth = reader.info().bit_depth as u8;
+
+    let mut data = vec![0; reader.output_buffer_size()];
+    let frame = reader
+        .next_frame(&mut data)
+        .map_err(|e| Error::Image(format!("Failed to decode PNG: {}", e)))?;
+    data.truncate(frame.buffer_size());
+
+    Ok(Decoded {
+        width: frame.width,
+        height: frame.height,
+        bit_depth,
+        color_type: frame.color_type,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+    fn encode_gray(width: u32, height: u32, depth: png::BitDepth, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(depth);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(data)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn test_valid_image() {
+        // 1-bit, all white
+        let data = vec![0xFF; (DISPLAY_WIDTH as usize / 8) * DISPLAY_HEIGHT as usize];
+        let png = encode_gray(DISPLAY_WIDTH, DISPLAY_HEIGHT, png::BitDepth::One, &data);
+
+        let report = validate_image(&png).unwrap();
+        assert_eq!(report.width, DISPLAY_WIDTH);
+        assert_eq!(report.height, DISPLAY_HEIGHT);
+        assert_eq!(report.bit_depth, 1);
+        assert_eq!(report.color_count, 1);
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn test_problems_reported() {
+        // 8-bit gradient with 256 gray levels and the wrong size
+        let data: Vec<u8> = (0..=255).collect();
+        let png = encode_gray(16, 16, png::BitDepth::Eight, &data);
+
+        let report = validate_image(&png).unwrap();
+        assert_eq!(report.color_count, 256);
+        assert!(report.problems.contains(&ImageProblem::WrongDimensions {
+            width: 16,
+            height: 16,
+            expected_width: DISPLAY_WIDTH,
+            expected_height: DISPLAY_HEIGHT,
+        }));
+        assert!(report.problems.contains(&ImageProblem::BitDepthTooHigh(8)));
+        assert!(report.problems.contains(&ImageProblem::TooManyColors {
+            count: 256,
+            max: MAX_COLORS,
+        }));
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn test_validate_against_profile() {
+        let data: Vec<u8> = vec![0x00, 0xFF, 0x55, 0xAA];
+        let png = encode_gray(16, 16, png::BitDepth::Two, &[data.as_slice(); 16].concat());
+
+        let profile = DisplayProfile {
+            width: 16,
+            height: 16,
+            max_bytes: 10,
+            palette: 2,
+        };
+        let report = validate_image_for(&png, &profile).unwrap();
+        assert!(report.problems.contains(&ImageProblem::TooLarge {
+            size: png.len(),
+            max: 10,
+        }));
+        assert!(report
+            .problems
+            .contains(&ImageProblem::TooManyColors { count: 4, max: 2 }));
+        assert!(!report
+            .problems
+            .iter()
+            .any(|p| matches!(p, ImageProblem::WrongDimensions { .. })));
+    }
+
+    #[test]
+    fn test_diff_images() {
+        let white = vec![0xFF; 100];
+        let mut one_dot = white.clone();
+        one_dot[42] = 0x00;
+
+        let a = encode_gray(10, 10, png::BitDepth::Eight, &white);
+        let b = encode_gray(10, 10, png::BitDepth::Eight, &one_dot);
+
+        let same = diff_images(&a, &a).unwrap();
+        assert_eq!(same.changed_pixels, 0);
+        assert!(!same.exceeds(0.0));
+
+        let diff = diff_images(&a, &b).unwrap();
+        assert_eq!(diff.changed_pixels, 1);
+        assert_eq!(diff.total_pixels, 100);
+        assert!(diff.exceeds(0.0));
+        assert!(!diff.exceeds(0.05));
+    }
+
+    #[test]
+    fn test_diff_images_size_mismatch() {
+        let a = encode_gray(10, 10, png::BitDepth::Eight, &[0; 100]);
+        let b = encode_gray(5, 5, png::BitDepth::Eight, &[0; 25]);
+
+        let diff = diff_images(&a, &b).unwrap();
+        assert_eq!(diff.changed_pixels, diff.total_pixels);
+    }
+
+    #[test]
+    fn test_encode_indexed() {
+        // 8-bit gradient: every gray level once
+        let data: Vec<u8> = (0..=255).collect();
+        let source = encode_gray(16, 16, png::BitDepth::Eight, &data);
+
+        for bits in [1, 2, 4] {
+            let indexed = encode_indexed(&source, bits).unwrap();
+            let report = validate_image(&indexed).unwrap();
+            assert_eq!(report.bit_depth, bits);
+            assert_eq!(report.color_count, 1 << bits);
+            assert_eq!((report.width, report.height), (16, 16));
+        }
+    }
+
+    #[test]
+    fn test_encode_indexed_preserves_black_and_white() {
+        let mut data = vec![0xFF; 100];
+        data[0] = 0x00;
+        let source = encode_gray(10, 10, png::BitDepth::Eight, &data);
+
+        let indexed = encode_indexed(&source, 2).unwrap();
+        assert_eq!(diff_images(&source, &indexed).unwrap().changed_pixels, 0);
+    }
+
+    #[test]
+    fn test_encode_indexed_rejects_bad_depth() {
+        let source = encode_gray(1, 1, png::BitDepth::Eight, &[0]);
+        assert!(encode_indexed(&source, 8).is_err());
+    }
+
+    #[test]
+    fn test_invalid_png() {
+        assert!(matches!(validate_image(b"not a png"), Err(Error::Image(_))));
+    }
+}
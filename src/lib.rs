@@ -54,23 +54,41 @@
 //! - `FW-Version`: Firmware version
 //! - `RSSI`: WiFi signal strength
 //! - `Refresh-Rate`: Current refresh rate
+//! - `Access-Token`: API key issued during setup
+//! - `User-Agent`, `Width`, `Height`, `Model`: Client and panel hints
 //!
 //! ## Feature Flags
 //!
+//! - `http` - Framework-neutral header parsing and handlers on `http` types
 //! - `axum` - Axum extractors and handlers
 //! - `render` - HTML to PNG rendering via Chrome headless
+//! - `headless-chrome` - Alternative render engine using the `headless_chrome` crate
+//! - `preview` - Browser preview routes for iterating on screens
+//! - `image` - PNG inspection and validation
+//! - `privacy` - Device ID obfuscation for storage and logging
 //! - `schedule` - Time-based refresh rate scheduling (YAML config)
-//! - `full` - All features
+//! - `jwt` - JWT validation (HS256/RS256) as an alternative to static tokens
+//! - `openapi` - OpenAPI schemas for the BYOS endpoints (via `utoipa`)
+//! - `full` - All features except `headless-chrome`
 
 pub mod auth;
 mod byos;
 mod error;
+pub mod lint;
+mod mac;
+mod negotiate;
+mod profile;
 
 pub use auth::TokenAuth;
 pub use byos::{
-    DeviceInfo, DeviceStatusStamp, DisplayResponse, LogEntry, LogResponse, SetupResponse,
+    generate_api_key, generate_friendly_id, BatteryState, BatteryThresholds, DeviceInfo,
+    DeviceStatusStamp, DisplayResponse, DisplayResponseBuilder, DisplayStatus, LogEntry,
+    LogPayload, LogResponse, RefreshRate, SetupResponse,
 };
 pub use error::Error;
+pub use mac::MacAddress;
+pub use negotiate::{ImageFormat, Negotiation, ResponseNegotiator};
+pub use profile::DisplayProfile;
 
 /// TRMNL display width in pixels
 pub const DISPLAY_WIDTH: u32 = 800;
@@ -81,6 +99,38 @@ pub const DISPLAY_HEIGHT: u32 = 480;
 /// Maximum image size in bytes (firmware rejects larger)
 pub const MAX_IMAGE_SIZE: usize = 90 * 1024; // 90KB
 
+/// Maximum serialized `/api/display` response size the firmware parses reliably
+pub const MAX_RESPONSE_SIZE: usize = 2048;
+
+/// Maximum serialized `/api/display` response size with an inline image:
+/// [`MAX_RESPONSE_SIZE`] plus a [`MAX_IMAGE_SIZE`] PNG as a base64 data URI
+pub const MAX_INLINE_RESPONSE_SIZE: usize =
+    MAX_RESPONSE_SIZE + "data:image/png;base64,".len() + MAX_IMAGE_SIZE.div_ceil(3) * 4;
+
+/// Maximum URL length (image or firmware) the firmware handles reliably
+pub const MAX_URL_LENGTH: usize = 1024;
+
+/// Maximum filename length the firmware stores for change detection
+pub const MAX_FILENAME_LENGTH: usize = 255;
+
+/// Largest panel width or height `ResponseNegotiator` accepts from a device's
+/// `Width`/`Height` headers
+pub const MAX_REPORTED_DIMENSION: u32 = 4096;
+
+/// Shortest refresh rate (seconds)
+///
+/// Every `RefreshRate` is clamped to at least this, whether converted from
+/// seconds or a `Duration`, deserialized or battery-scaled.
+/// `DisplayResponse::builder()` rejects shorter rates instead.
+pub const MIN_REFRESH_RATE: u32 = 5;
+
+/// Longest refresh rate (seconds)
+///
+/// Every `RefreshRate` is clamped to at most this, the same way as
+/// [`MIN_REFRESH_RATE`]. `DisplayResponse::builder()` rejects longer rates
+/// instead.
+pub const MAX_REFRESH_RATE: u32 = 24 * 60 * 60; // 1 day
+
 /// LiPo battery minimum voltage (0%)
 pub const BATTERY_MIN_MV: u32 = 3000;
 
@@ -90,16 +140,43 @@ pub const BATTERY_MAX_MV: u32 = 4200;
 // Optional modules
 #[cfg(feature = "render")]
 pub mod render;
+#[cfg(feature = "headless-chrome")]
+pub use render::render_batch;
 #[cfg(feature = "render")]
-pub use render::{render_html_to_png, timestamped_filename, RenderConfig};
+pub use render::{
+    render_html_to_png, render_pdf_page_to_png, timestamped_filename, RenderConfig, RenderObserver,
+    RenderReport,
+};
+
+#[cfg(feature = "preview")]
+pub mod preview;
+
+#[cfg(feature = "image")]
+pub mod image;
+#[cfg(feature = "image")]
+pub use image::{
+    diff_images, encode_indexed, validate_image, validate_image_for, ImageDiff, ImageReport,
+};
+
+#[cfg(feature = "privacy")]
+pub mod privacy;
 
 #[cfg(feature = "schedule")]
 pub mod schedule;
 #[cfg(feature = "schedule")]
 pub use schedule::{
-    get_global_refresh_rate, init_global_schedule, DaySelector, RefreshSchedule, ScheduleRule,
+    clear_global_schedule, get_global_refresh_rate, global_schedule, init_global_schedule,
+    set_global_schedule, update_global_schedule, AmbiguousTimePolicy, BatteryScaling, CronExpr,
+    DateRange, DaySelector, MonthSelector, RefreshSchedule, RuleMode, ScheduleBuilder,
+    ScheduleRule,
 };
 
+#[cfg(feature = "http")]
+pub mod http_ext;
+
+#[cfg(feature = "openapi")]
+pub mod openapi;
+
 // Re-export axum integration
 #[cfg(feature = "axum")]
 pub mod axum_ext;
@@ -0,0 +1,250 @@
+//! Lint HTML templates for constructs that render badly on e-ink.
+//!
+//! The display refreshes a few times an hour, shows 16 grays at best, and
+//! is captured by a headless browser with no network guarantees. Markup
+//! that looks fine in a desktop browser can come out blank, muddy or
+//! mid-animation. [`lint_html`] flags the common cases before you render.
+//!
+//! # Example
+//!
+//! ```
+//! use trmnl::lint::{lint_html, LintRule};
+//!
+//! let issues = lint_html(r#"<h1 style="color: #999; height: 50vh">Hi</h1>"#);
+//!
+//! assert!(issues.iter().any(|i| i.rule == LintRule::LowContrastGray));
+//! assert!(issues.iter().any(|i| i.rule == LintRule::ViewportUnits));
+//!
+//! for issue in &issues {
+//!     eprintln!("{}", issue);
+//! }
+//! ```
+//!
+//! To fail CI on lint issues, run `lint_html` over your templates in a test
+//! and assert the result is empty.
+
+use std::fmt;
+
+/// Lightest gray (per channel) flagged by [`LintRule::LowContrastGray`].
+const LOW_CONTRAST_MIN: u8 = 0x60;
+
+/// Darkest gray (per channel) flagged by [`LintRule::LowContrastGray`].
+const LOW_CONTRAST_MAX: u8 = 0xD0;
+
+/// Kind of e-ink pitfall found by [`lint_html`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LintRule {
+    /// CSS animations, transitions, marquees or animated GIFs
+    Animation,
+    /// Mid-range gray colors that dither into noise
+    LowContrastGray,
+    /// `vw`/`vh`/`vmin`/`vmax` units
+    ViewportUnits,
+    /// Fonts loaded over the network
+    ExternalFont,
+}
+
+impl LintRule {
+    /// Actionable explanation of the problem.
+    pub fn message(&self) -> &'static str {
+        match self {
+            LintRule::Animation => {
+                "animations are captured at an arbitrary frame; use static content"
+            }
+            LintRule::LowContrastGray => {
+                "mid-range grays dither into noise on e-ink; use black, white or a light tint"
+            }
+            LintRule::ViewportUnits => {
+                "viewport units depend on the capture window; use px sized for 800x480"
+            }
+            LintRule::ExternalFont => {
+                "external fonts may not load before capture; embed or self-host the font"
+            }
+        }
+    }
+}
+
+/// A single lint finding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    /// Which rule matched
+    pub rule: LintRule,
+    /// 1-based line number in the template
+    pub line: usize,
+    /// The offending snippet
+    pub snippet: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: `{}`: {}",
+            self.line,
+            self.snippet,
+            self.rule.message()
+        )
+    }
+}
+
+/// Check HTML (including inline CSS) for e-ink-hostile constructs.
+///
+/// This is a line-based text scan, not a full parser, so it works on
+/// unrendered templates too. Issues are returned in line order.
+pub fn lint_html(html: &str) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+
+    for (index, line) in html.lines().enumerate() {
+        let lower = line.to_ascii_lowercase();
+        let mut push = |rule, snippet: &str| {
+            issues.push(LintIssue {
+                rule,
+                line: index + 1,
+                snippet: snippet.to_string(),
+            })
+        };
+
+        for pattern in ["@keyframes", "animation", "transition", "<marquee", ".gif"] {
+            if lower.contains(pattern) {
+                push(LintRule::Animation, pattern);
+                break;
+            }
+        }
+
+        for color in gray_hex_colors(&lower) {
+            push(LintRule::LowContrastGray, &color);
+        }
+
+        if let Some(unit) = find_viewport_unit(&lower) {
+            push(LintRule::ViewportUnits, &unit);
+        }
+
+        for pattern in ["fonts.googleapis.com", "use.typekit.net", "fonts.bunny.net"] {
+            if lower.contains(pattern) {
+                push(LintRule::ExternalFont, pattern);
+            }
+        }
+        if lower.contains("@font-face") || lower.contains("src:") {
+            if let Some(pos) = lower.find("url(http") {
+                push(
+                    LintRule::ExternalFont,
+                    &lower[pos..].chars().take(40).collect::<String>(),
+                );
+            }
+        }
+    }
+
+    issues
+}
+
+/// Find `#rgb`/`#rrggbb` grays in the low-contrast range.
+fn gray_hex_colors(line: &str) -> Vec<String> {
+    let bytes = line.as_bytes();
+    let mut found = Vec::new();
+
+    for (start, _) in line.match_indices('#') {
+        let digits: String = bytes[start + 1..]
+            .iter()
+            .take_while(|b| b.is_ascii_hexdigit())
+            .map(|&b| b as char)
+            .collect();
+
+        let channels: Option<[u8; 3]> = match digits.len() {
+            3 => {
+                let c: Vec<u8> = digits
+                    .chars()
+                    .filter_map(|c| c.to_digit(16))
+                    .map(|d| (d * 17) as u8)
+                    .collect();
+                Some([c[0], c[1], c[2]])
+            }
+            6 => {
+                let c: Vec<u8> = (0..3)
+                    .filter_map(|i| u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).ok())
+                    .collect();
+                Some([c[0], c[1], c[2]])
+            }
+            _ => None,
+        };
+
+        if let Some([r, g, b]) = channels {
+            let is_gray = r == g && g == b;
+            if is_gray && (LOW_CONTRAST_MIN..=LOW_CONTRAST_MAX).contains(&r) {
+                found.push(format!("#{}", digits));
+            }
+        }
+    }
+
+    found
+}
+
+/// Find the first number followed by a viewport unit, e.g. `50vh`.
+fn find_viewport_unit(line: &str) -> Option<String> {
+    let bytes = line.as_bytes();
+
+    for unit in ["vmin", "vmax", "vw", "vh"] {
+        for (pos, _) in line.match_indices(unit) {
+            let preceded_by_number = pos > 0 && bytes[pos - 1].is_ascii_digit();
+            let followed_by_letter = bytes
+                .get(pos + unit.len())
+                .is_some_and(|b| b.is_ascii_alphanumeric());
+            if preceded_by_number && !followed_by_letter {
+                let start = line[..pos]
+                    .rfind(|c: char| !(c.is_ascii_digit() || c == '.'))
+                    .map_or(0, |i| i + 1);
+                return Some(line[start..pos + unit.len()].to_string());
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(html: &str) -> Vec<LintRule> {
+        lint_html(html).into_iter().map(|i| i.rule).collect()
+    }
+
+    #[test]
+    fn test_clean_template() {
+        let html = r#"<body style="width: 800px; color: #000; background: #fff">
+            <h1 style="border: 1px solid #eee">Hello</h1>
+        </body>"#;
+        assert!(lint_html(html).is_empty());
+    }
+
+    #[test]
+    fn test_flags_each_rule_with_line() {
+        let html = "<style>\n\
+            @keyframes spin { }\n\
+            p { color: #888888; width: 12.5vw; }\n\
+            </style>\n\
+            <link href=\"https://fonts.googleapis.com/css2?family=Inter\">";
+
+        let issues = lint_html(html);
+        assert_eq!(
+            rules(html),
+            vec![
+                LintRule::Animation,
+                LintRule::LowContrastGray,
+                LintRule::ViewportUnits,
+                LintRule::ExternalFont,
+            ]
+        );
+        assert_eq!(issues[0].line, 2);
+        assert_eq!(issues[1].snippet, "#888888");
+        assert_eq!(issues[2].snippet, "12.5vw");
+        assert_eq!(issues[3].line, 5);
+    }
+
+    #[test]
+    fn test_ignores_non_gray_and_extreme_colors() {
+        assert!(rules("color: #f00; background: #333; border-color: #ddd").is_empty());
+        assert!(rules("<div id=\"vh\">").is_empty());
+        assert_eq!(rules("color: #999"), vec![LintRule::LowContrastGray]);
+    }
+}
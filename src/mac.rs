@@ -0,0 +1,157 @@
+//! Device MAC address parsing and normalization.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// A device MAC address.
+///
+/// Firmware and tooling report MACs in different casings and separators
+/// (`aa:bb:..`, `AA-BB-..`, `aabb.ccdd.eeff`, `AABBCCDDEEFF`). Parsing
+/// accepts exactly these groupings, each with a single separator, and
+/// normalizes them, so `MacAddress` is safe to use as a map key.
+/// It displays and serializes in the canonical `AA:BB:CC:DD:EE:FF` form.
+///
+/// # Example
+///
+/// ```
+/// use trmnl::MacAddress;
+///
+/// let a: MacAddress = "aa-bb-cc-dd-ee-ff".parse().unwrap();
+/// let b: MacAddress = "AABB.CCDD.EEFF".parse().unwrap();
+///
+/// assert_eq!(a, b);
+/// assert_eq!(a.to_string(), "AA:BB:CC:DD:EE:FF");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MacAddress([u8; 6]);
+
+impl MacAddress {
+    /// Create a MAC address from raw bytes.
+    pub fn new(bytes: [u8; 6]) -> Self {
+        Self(bytes)
+    }
+
+    /// Raw address bytes.
+    pub fn bytes(&self) -> [u8; 6] {
+        self.0
+    }
+}
+
+impl FromStr for MacAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::Protocol(format!("invalid MAC address: {}", s));
+
+        // One separator throughout: six pairs split by `:` or `-`, three
+        // quads split by `.`, or twelve bare digits.
+        let s = s.trim();
+        let digits: Vec<u8> = match s.bytes().find(|b| !b.is_ascii_hexdigit()) {
+            None => s.as_bytes().to_vec(),
+            Some(sep @ (b':' | b'-' | b'.')) => {
+                let group_len = if sep == b'.' { 4 } else { 2 };
+                let groups: Vec<&str> = s.split(char::from(sep)).collect();
+                if groups.iter().any(|g| g.len() != group_len) {
+                    return Err(invalid());
+                }
+                groups.concat().into_bytes()
+            }
+            Some(_) => return Err(invalid()),
+        };
+        if digits.len() != 12 || !digits.iter().all(u8::is_ascii_hexdigit) {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0u8; 6];
+        for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(
+            f,
+            "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+impl Serialize for MacAddress {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MacAddress {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_formats() {
+        let expected = MacAddress::new([0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]);
+        for input in [
+            "AA:BB:CC:DD:EE:FF",
+            "aa:bb:cc:dd:ee:ff",
+            "aa-bb-cc-dd-ee-ff",
+            "aabb.ccdd.eeff",
+            "AABBCCDDEEFF",
+            " aa:bb:cc:dd:ee:ff\n",
+        ] {
+            assert_eq!(input.parse::<MacAddress>().unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        for input in [
+            "",
+            "unknown",
+            "AA:BB:CC:DD:EE",
+            "GG:BB:CC:DD:EE:FF",
+            "AA:BB:CC:DD:EE:FF:00",
+            "+A:BB:CC:DD:EE:FF",
+        ] {
+            assert!(input.parse::<MacAddress>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_irregular_grouping() {
+        for input in [
+            "A:ABBCCDDEEFF",
+            "AA:BB-CC:DD:EE:FF",
+            "AAB:BCC:DDE:EFF",
+            "AA:BB:CC:DD:EEFF",
+            "AABB.CCDD.EE.FF",
+            "aa:bb:cc:dd:ee:ff:",
+            "aa::bb:cc:dd:ee:ff",
+        ] {
+            assert!(input.parse::<MacAddress>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let mac: MacAddress = "aa-bb-cc-dd-ee-0f".parse().unwrap();
+        let json = serde_json::to_string(&mac).unwrap();
+        assert_eq!(json, "\"AA:BB:CC:DD:EE:0F\"");
+        assert_eq!(serde_json::from_str::<MacAddress>(&json).unwrap(), mac);
+    }
+}
@@ -0,0 +1,253 @@
+//! Per-device response negotiation for mixed firmware fleets.
+//!
+//! Older firmware only displays 1-bit BMP images and can't take inline
+//! images; newer firmware and DIY panels may report their own resolution.
+//! [`ResponseNegotiator`] turns the headers in [`DeviceInfo`] into a
+//! [`Negotiation`] describing what to send to that device.
+//!
+//! # Example
+//!
+//! ```
+//! use trmnl::{DeviceInfo, ImageFormat, ResponseNegotiator};
+//!
+//! let negotiator = ResponseNegotiator::new()
+//!     .with_bmp_before("1.2.0")?
+//!     .with_inline_since("1.6.0")?;
+//!
+//! let old = DeviceInfo::new("AA:BB:CC:DD:EE:01").with_firmware_version("1.1.9");
+//! assert_eq!(negotiator.negotiate(&old).format, ImageFormat::Bmp);
+//!
+//! let new = DeviceInfo::new("AA:BB:CC:DD:EE:02").with_firmware_version("1.6.1");
+//! assert!(negotiator.negotiate(&new).inline);
+//! # Ok::<(), trmnl::Error>(())
+//! ```
+
+use crate::byos::DeviceInfo;
+use crate::error::Error;
+use crate::{DISPLAY_HEIGHT, DISPLAY_WIDTH, MAX_IMAGE_SIZE, MAX_REPORTED_DIMENSION};
+
+/// Image file format sent to a device.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ImageFormat {
+    /// Grayscale PNG (current firmware)
+    #[default]
+    Png,
+    /// 1-bit BMP (legacy firmware)
+    Bmp,
+}
+
+impl ImageFormat {
+    /// File extension, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Bmp => "bmp",
+        }
+    }
+
+    /// MIME type for `Content-Type` headers.
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Bmp => "image/bmp",
+        }
+    }
+}
+
+/// What to send to a particular device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Negotiation {
+    /// Image format the firmware can display
+    pub format: ImageFormat,
+    /// Whether to embed the image in the response (see
+    /// [`DisplayResponse::with_inline_image`](crate::DisplayResponse::with_inline_image))
+    pub inline: bool,
+    /// Largest image the device accepts, in bytes
+    pub max_image_size: usize,
+    /// Panel width in pixels
+    pub width: u32,
+    /// Panel height in pixels
+    pub height: u32,
+}
+
+impl Negotiation {
+    /// Apply the negotiated format, size limit and dimensions to a render config.
+    #[cfg(feature = "render")]
+    pub fn configure(&self, config: crate::render::RenderConfig) -> crate::render::RenderConfig {
+        let mut config = config
+            .with_format(self.format)
+            .with_max_image_size(self.max_image_size);
+        config.width = self.width;
+        config.height = self.height;
+        config
+    }
+}
+
+/// Chooses image format, delivery and limits from a device's headers.
+///
+/// Devices without a parseable `FW-Version` get the conservative choices:
+/// PNG by URL. Devices without both `Width` and `Height` in
+/// `1..=`[`MAX_REPORTED_DIMENSION`](crate::MAX_REPORTED_DIMENSION) are
+/// assumed to be 800x480.
+#[derive(Debug, Clone)]
+pub struct ResponseNegotiator {
+    bmp_before: Option<Version>,
+    inline_since: Option<Version>,
+    max_image_size: usize,
+}
+
+impl Default for ResponseNegotiator {
+    fn default() -> Self {
+        Self {
+            bmp_before: None,
+            inline_since: None,
+            max_image_size: MAX_IMAGE_SIZE,
+        }
+    }
+}
+
+impl ResponseNegotiator {
+    /// Create a negotiator that sends PNG by URL to every device.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send BMP to firmware older than `version` (e.g. `"1.2.0"`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Config` if `version` is not a `major.minor[.patch]`
+    /// version.
+    pub fn with_bmp_before(mut self, version: &str) -> Result<Self, Error> {
+        self.bmp_before = Some(parse_config_version(version)?);
+        Ok(self)
+    }
+
+    /// Inline images for firmware at or above `version` (e.g. `"1.6.0"`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Config` if `version` is not a `major.minor[.patch]`
+    /// version.
+    pub fn with_inline_since(mut self, version: &str) -> Result<Self, Error> {
+        self.inline_since = Some(parse_config_version(version)?);
+        Ok(self)
+    }
+
+    /// Set the largest image size in bytes (default: 90KB).
+    #[must_use]
+    pub fn with_max_image_size(mut self, bytes: usize) -> Self {
+        self.max_image_size = bytes;
+        self
+    }
+
+    /// Decide what to send to `device`.
+    pub fn negotiate(&self, device: &DeviceInfo) -> Negotiation {
+        let version = device.firmware_version.as_deref().and_then(Version::parse);
+
+        let format = match (self.bmp_before, version) {
+            (Some(cutoff), Some(v)) if v < cutoff => ImageFormat::Bmp,
+            _ => ImageFormat::Png,
+        };
+        let inline = format == ImageFormat::Png
+            && matches!((self.inline_since, version), (Some(since), Some(v)) if v >= since);
+
+        let plausible = 1..=MAX_REPORTED_DIMENSION;
+        let (width, height) = match (device.width, device.height) {
+            (Some(w), Some(h)) if plausible.contains(&w) && plausible.contains(&h) => (w, h),
+            _ => (DISPLAY_WIDTH, DISPLAY_HEIGHT),
+        };
+
+        Negotiation {
+            format,
+            inline,
+            max_image_size: self.max_image_size,
+            width,
+            height,
+        }
+    }
+}
+
+/// Firmware version as `(major, minor, patch)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Version(u32, u32, u32);
+
+impl Version {
+    /// Parse `1.2`, `1.2.3` or `v1.2.3` (suffixes like `-beta` are ignored).
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().trim_start_matches(['v', 'V']);
+        let core = s.split(['-', '+', ' ']).next()?;
+        let mut parts = core.split('.').map(|p| p.parse::<u32>().ok());
+
+        let major = parts.next()??;
+        let minor = parts.next()??;
+        let patch = parts.next().unwrap_or(Some(0))?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Version(major, minor, patch))
+    }
+}
+
+fn parse_config_version(version: &str) -> Result<Version, Error> {
+    Version::parse(version)
+        .ok_or_else(|| Error::Config(format!("invalid firmware version: {}", version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_parsing() {
+        assert_eq!(Version::parse("1.2.3"), Some(Version(1, 2, 3)));
+        assert_eq!(Version::parse("v1.6"), Some(Version(1, 6, 0)));
+        assert_eq!(Version::parse("1.5.2-beta"), Some(Version(1, 5, 2)));
+        assert_eq!(Version::parse("latest"), None);
+        assert_eq!(Version::parse("1.2.3.4"), None);
+    }
+
+    #[test]
+    fn test_negotiate_by_firmware_and_dimensions() {
+        let negotiator = ResponseNegotiator::new()
+            .with_bmp_before("1.2.0")
+            .unwrap()
+            .with_inline_since("1.6.0")
+            .unwrap()
+            .with_max_image_size(48 * 1024);
+
+        let unknown = negotiator.negotiate(&DeviceInfo::new("a"));
+        assert_eq!(unknown.format, ImageFormat::Png);
+        assert!(!unknown.inline);
+        assert_eq!((unknown.width, unknown.height), (800, 480));
+        assert_eq!(unknown.max_image_size, 48 * 1024);
+
+        let legacy = DeviceInfo::new("b").with_firmware_version("1.1.0");
+        let legacy = negotiator.negotiate(&legacy);
+        assert_eq!(legacy.format, ImageFormat::Bmp);
+        assert!(!legacy.inline);
+
+        let diy = DeviceInfo::new("c")
+            .with_firmware_version("1.6.0")
+            .with_dimensions(1872, 1404);
+        let diy = negotiator.negotiate(&diy);
+        assert!(diy.inline);
+        assert_eq!((diy.width, diy.height), (1872, 1404));
+
+        for (width, height) in [(u32::MAX, 480), (800, 0), (5000, 5000)] {
+            let bogus = DeviceInfo::new("d").with_dimensions(width, height);
+            let bogus = negotiator.negotiate(&bogus);
+            assert_eq!((bogus.width, bogus.height), (800, 480));
+        }
+    }
+
+    #[test]
+    fn test_invalid_config_version() {
+        assert!(matches!(
+            ResponseNegotiator::new().with_bmp_before("latest"),
+            Err(Error::Config(_))
+        ));
+        assert!(ResponseNegotiator::new().with_inline_since("1.x").is_err());
+    }
+}
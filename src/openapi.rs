@@ -0,0 +1,196 @@
+//! OpenAPI schemas for the BYOS endpoints.
+//!
+//! With the `openapi` feature, the protocol types implement
+//! [`utoipa::ToSchema`] and [`openapi`] describes the standard endpoints,
+//! for API gateways and contract tests.
+//!
+//! # Example
+//!
+//! ```
+//! let doc = trmnl::openapi::openapi();
+//! assert!(doc.paths.paths.contains_key("/api/display"));
+//! println!("{}", doc.to_pretty_json().unwrap());
+//! ```
+
+use std::borrow::Cow;
+
+use utoipa::openapi::path::{HttpMethod, OperationBuilder, ParameterBuilder, ParameterIn};
+use utoipa::openapi::request_body::RequestBodyBuilder;
+use utoipa::openapi::schema::{ComponentsBuilder, ObjectBuilder, OneOfBuilder, Type};
+use utoipa::openapi::{
+    ContentBuilder, InfoBuilder, OpenApi, OpenApiBuilder, PathItem, PathsBuilder, Ref, RefOr,
+    Required, ResponseBuilder, Schema,
+};
+use utoipa::{PartialSchema, ToSchema};
+
+use crate::{
+    DeviceStatusStamp, DisplayResponse, DisplayStatus, LogEntry, LogPayload, LogResponse,
+    RefreshRate, SetupResponse, MAX_REFRESH_RATE, MIN_REFRESH_RATE,
+};
+
+/// Serialized as its numeric code (see [`DisplayStatus::code`]).
+impl PartialSchema for DisplayStatus {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::Integer)
+            .description(Some("0 = OK, 1 = error"))
+            .enum_values(Some([0, 1]))
+            .into()
+    }
+}
+
+impl ToSchema for DisplayStatus {
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed("DisplayStatus")
+    }
+}
+
+/// Serialized as a string of seconds; numbers are accepted when parsing.
+impl PartialSchema for RefreshRate {
+    fn schema() -> RefOr<Schema> {
+        OneOfBuilder::new()
+            .item(
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .pattern(Some("^[0-9]+$")),
+            )
+            .item(
+                ObjectBuilder::new()
+                    .schema_type(Type::Integer)
+                    .minimum(Some(MIN_REFRESH_RATE))
+                    .maximum(Some(MAX_REFRESH_RATE)),
+            )
+            .description(Some("Seconds until the device polls again"))
+            .into()
+    }
+}
+
+impl ToSchema for RefreshRate {
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed("RefreshRate")
+    }
+}
+
+/// Firmware request headers, as `(name, description, required)`.
+const DEVICE_HEADERS: &[(&str, &str, bool)] = &[
+    ("ID", "Device MAC address", true),
+    ("Access-Token", "API key issued during setup", false),
+    ("Battery-Voltage", "Battery voltage in volts", false),
+    ("FW-Version", "Firmware version", false),
+    ("RSSI", "WiFi signal strength in dBm", false),
+    ("Refresh-Rate", "Current refresh rate in seconds", false),
+    ("Width", "Panel width in pixels", false),
+    ("Height", "Panel height in pixels", false),
+    ("Model", "Device model hint", false),
+];
+
+fn json_response(description: &str, schema: &str) -> ResponseBuilder {
+    ResponseBuilder::new().description(description).content(
+        "application/json",
+        ContentBuilder::new()
+            .schema(Some(Ref::from_schema_name(schema)))
+            .build(),
+    )
+}
+
+fn operation(summary: &str, response: ResponseBuilder) -> OperationBuilder {
+    let parameters = DEVICE_HEADERS.iter().map(|(name, description, required)| {
+        ParameterBuilder::new()
+            .name(*name)
+            .parameter_in(ParameterIn::Header)
+            .description(Some(*description))
+            .required(if *required {
+                Required::True
+            } else {
+                Required::False
+            })
+            .schema(Some(ObjectBuilder::new().schema_type(Type::String)))
+            .build()
+    });
+    OperationBuilder::new()
+        .tag("byos")
+        .summary(Some(summary))
+        .parameters(Some(parameters))
+        .response("200", response)
+}
+
+/// OpenAPI document for `/api/setup`, `/api/display` and `/api/log`.
+///
+/// Merge it into your own document if the server exposes more routes.
+pub fn openapi() -> OpenApi {
+    let setup = operation(
+        "Register a device",
+        json_response("Setup result", "SetupResponse"),
+    );
+    let display = operation(
+        "Get the current screen",
+        json_response("Screen to show", "DisplayResponse"),
+    );
+    let log = operation(
+        "Submit device logs",
+        json_response("Logs accepted", "LogResponse"),
+    )
+    .request_body(Some(
+        RequestBodyBuilder::new()
+            .required(Some(Required::True))
+            .content(
+                "application/json",
+                ContentBuilder::new()
+                    .schema(Some(Ref::from_schema_name("LogPayload")))
+                    .build(),
+            )
+            .build(),
+    ));
+
+    let paths = PathsBuilder::new()
+        .path("/api/setup", PathItem::new(HttpMethod::Get, setup))
+        .path("/api/display", PathItem::new(HttpMethod::Get, display))
+        .path("/api/log", PathItem::new(HttpMethod::Post, log));
+
+    let components = ComponentsBuilder::new()
+        .schema_from::<DisplayResponse>()
+        .schema_from::<DisplayStatus>()
+        .schema_from::<RefreshRate>()
+        .schema_from::<SetupResponse>()
+        .schema_from::<LogPayload>()
+        .schema_from::<LogEntry>()
+        .schema_from::<DeviceStatusStamp>()
+        .schema_from::<LogResponse>();
+
+    OpenApiBuilder::new()
+        .info(
+            InfoBuilder::new()
+                .title("TRMNL BYOS")
+                .version(env!("CARGO_PKG_VERSION"))
+                .build(),
+        )
+        .paths(paths)
+        .components(Some(components.build()))
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_references_resolve() {
+        let doc = openapi();
+        let json = serde_json::to_value(&doc).unwrap();
+        let schemas = json["components"]["schemas"].as_object().unwrap();
+
+        let text = json.to_string();
+        for reference in text.split("\"#/components/schemas/").skip(1) {
+            let name = &reference[..reference.find('"').unwrap()];
+            assert!(schemas.contains_key(name), "unresolved schema {}", name);
+        }
+
+        let display = &schemas["DisplayResponse"]["properties"];
+        assert!(display.get("image_url").is_some());
+        assert!(display.get("refresh_rate").is_some());
+        assert_eq!(
+            json["paths"]["/api/log"]["post"]["requestBody"]["required"],
+            true
+        );
+    }
+}
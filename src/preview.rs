@@ -0,0 +1,400 @@
+//! Live preview of screens in a browser.
+//!
+//! Iterating on templates by flashing a real device is slow. This module
+//! provides axum routes that render your screen on demand and show it in an
+//! 800x480 frame styled like the e-ink panel (grayscale, no smoothing).
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use trmnl::preview::preview_router;
+//! use trmnl::render::RenderConfig;
+//!
+//! async fn my_screen() -> Result<String, trmnl::Error> {
+//!     Ok("<html><body><h1>Hello!</h1></body></html>".to_string())
+//! }
+//!
+//! let app = axum::Router::new()
+//!     .merge(preview_router(my_screen, RenderConfig::default()));
+//!
+//! // Open http://localhost:3000/preview in a browser
+//! ```
+//!
+//! To watch what devices are actually shown, create a [`LivePreview`], pass
+//! it to [`ByosState::with_live_preview`](crate::axum_ext::ByosState::with_live_preview)
+//! (or call [`LivePreview::publish`] from your own handler), and merge
+//! [`LivePreview::router`]. `/preview/live` then updates whenever a device
+//! fetches a new image.
+//!
+//! **The live routes show every device's MAC address and current screen to
+//! anyone who can reach them.** Set a token with [`LivePreview::with_token`]
+//! or mount them behind an authenticating layer before exposing them beyond
+//! your development machine.
+
+use std::convert::Infallible;
+use std::future::Future;
+
+use axum::http::{header, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use futures_util::stream::{self, Stream};
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::auth::Secret;
+use crate::error::Error;
+use crate::negotiate::ImageFormat;
+use crate::render::{render_html_to_png, RenderConfig};
+use crate::TokenAuth;
+
+/// Browser page that displays `/preview.png` and reloads it periodically.
+const PREVIEW_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <title>TRMNL Preview</title>
+    <style>
+        body { background: #2b2b2b; color: #ccc; font-family: sans-serif;
+               display: flex; flex-direction: column; align-items: center; padding: 24px; }
+        .bezel { background: #1a1a1a; padding: 24px; border-radius: 12px; }
+        img { display: block; width: 800px; height: 480px; background: #e8e8e0;
+              filter: grayscale(1); image-rendering: pixelated; }
+        .controls { margin-top: 16px; }
+        #status { margin-left: 12px; font-size: 14px; }
+    </style>
+</head>
+<body>
+    <div class="bezel"><img id="screen" alt="screen"></div>
+    <div class="controls">
+        <button onclick="reload()">Refresh</button>
+        <label><input type="checkbox" id="auto" checked> Auto-refresh (5s)</label>
+        <span id="status"></span>
+    </div>
+    <script>
+        const img = document.getElementById("screen");
+        const status = document.getElementById("status");
+        function reload() {
+            status.textContent = "Rendering...";
+            fetch("/preview.png?t=" + Date.now()).then(async (res) => {
+                if (!res.ok) { status.textContent = await res.text(); return; }
+                const blob = await res.blob();
+                img.src = URL.createObjectURL(blob);
+                status.textContent = (blob.size / 1024).toFixed(1) + " KB";
+            }).catch((e) => { status.textContent = e; });
+        }
+        setInterval(() => { if (document.getElementById("auto").checked) reload(); }, 5000);
+        reload();
+    </script>
+</body>
+</html>
+"#;
+
+/// Build routes for previewing a screen in a browser.
+///
+/// - `GET /preview` - HTML page showing the screen in an e-ink style frame
+/// - `GET /preview.png` - Renders `screen` and returns the PNG
+///
+/// `screen` is called on every request to produce fresh HTML, so edits to
+/// templates show up on the next refresh. Render errors are returned as
+/// plain text and shown below the frame.
+pub fn preview_router<S, F, Fut>(screen: F, config: RenderConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    F: Fn() -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Result<String, Error>> + Send,
+{
+    Router::new()
+        .route("/preview", get(|| async { Html(PREVIEW_PAGE) }))
+        .route(
+            "/preview.png",
+            get(move || async move { render_preview(screen().await, &config).await }),
+        )
+}
+
+/// Render HTML produced by a screen into a PNG response.
+async fn render_preview(html: Result<String, Error>, config: &RenderConfig) -> Response {
+    let result = match html {
+        Ok(html) => render_html_to_png(&html, config).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(png) => (
+            [
+                (header::CONTENT_TYPE, "image/png"),
+                (header::CACHE_CONTROL, "no-store"),
+            ],
+            png,
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::warn!("Preview render failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Browser page showing screens pushed over `/preview/live/events`.
+const LIVE_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <title>TRMNL Live Preview</title>
+    <style>
+        body { background: #2b2b2b; color: #ccc; font-family: sans-serif;
+               display: flex; flex-direction: column; align-items: center; padding: 24px; }
+        .bezel { background: #1a1a1a; padding: 24px; border-radius: 12px; }
+        img { display: block; width: 800px; height: 480px; background: #e8e8e0;
+              filter: grayscale(1); image-rendering: pixelated; }
+        #status { margin-top: 16px; font-size: 14px; }
+    </style>
+</head>
+<body>
+    <div class="bezel"><img id="screen" alt="screen"></div>
+    <div id="status">Waiting for a device to fetch a screen...</div>
+    <script>
+        const img = document.getElementById("screen");
+        const status = document.getElementById("status");
+        const events = new EventSource("/preview/live/events" + location.search);
+        events.addEventListener("screen", (e) => {
+            const screen = JSON.parse(e.data);
+            img.src = screen.data_uri;
+            status.textContent = screen.device + " - " + screen.filename +
+                " (" + new Date().toLocaleTimeString() + ")";
+        });
+        events.onerror = () => { status.textContent = "Disconnected, retrying..."; };
+    </script>
+</body>
+</html>
+"#;
+
+/// A screen sent to a device, as pushed to live preview pages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LiveScreen {
+    /// MAC address of the device that fetched the screen
+    pub device: String,
+    /// Filename of the image
+    pub filename: String,
+    /// The image as a `data:` URI
+    pub data_uri: String,
+}
+
+/// Broadcasts the most recent screen served to a device to browsers.
+///
+/// Cloning shares the same channel. Only the latest screen is kept;
+/// browsers that connect later receive it immediately.
+///
+/// Without a token, anyone who can reach [`LivePreview::router`] sees every
+/// device's MAC address and screen; see [`LivePreview::with_token`].
+#[derive(Debug, Clone)]
+pub struct LivePreview {
+    tx: watch::Sender<Option<LiveScreen>>,
+    token: Option<Secret<String>>,
+}
+
+impl Default for LivePreview {
+    fn default() -> Self {
+        Self {
+            tx: watch::channel(None).0,
+            token: None,
+        }
+    }
+}
+
+impl LivePreview {
+    /// Create a live preview with no screen yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require `?token=<token>` on the live routes.
+    ///
+    /// Open the page as `/preview/live?token=<token>`; it passes the token
+    /// on to the event stream.
+    #[must_use]
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(Secret::new(token.into()));
+        self
+    }
+
+    /// Push an image served to `device` to connected browsers.
+    pub fn publish(&self, device: &str, filename: &str, image: &[u8], format: ImageFormat) {
+        let screen = LiveScreen {
+            device: device.to_string(),
+            filename: filename.to_string(),
+            data_uri: format!(
+                "data:{};base64,{}",
+                format.mime_type(),
+                BASE64_STANDARD.encode(image)
+            ),
+        };
+        self.tx.send_replace(Some(screen));
+    }
+
+    /// The most recently published screen.
+    pub fn latest(&self) -> Option<LiveScreen> {
+        self.tx.borrow().clone()
+    }
+
+    /// Build routes for watching published screens.
+    ///
+    /// - `GET /preview/live` - HTML page that updates with each screen
+    /// - `GET /preview/live/events` - Server-sent events named `screen`
+    ///   carrying a [`LiveScreen`] as JSON
+    ///
+    /// With [`with_token`](Self::with_token), both routes answer
+    /// `401 Unauthorized` unless the request carries the token.
+    pub fn router<S>(&self) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let (page, events) = (self.clone(), self.clone());
+        Router::new()
+            .route(
+                "/preview/live",
+                get(move |auth: TokenAuth| async move {
+                    page.authorize(&auth)?;
+                    Ok::<_, (StatusCode, &'static str)>(Html(LIVE_PAGE))
+                }),
+            )
+            .route(
+                "/preview/live/events",
+                get(move |auth: TokenAuth| async move {
+                    events.authorize(&auth)?;
+                    Ok::<_, (StatusCode, &'static str)>(
+                        Sse::new(events.events()).keep_alive(KeepAlive::default()),
+                    )
+                }),
+            )
+    }
+
+    /// Check the request's token if one is required.
+    fn authorize(&self, auth: &TokenAuth) -> Result<(), (StatusCode, &'static str)> {
+        match &self.token {
+            Some(expected) => auth
+                .validate(expected.expose())
+                .map_err(|e| (StatusCode::UNAUTHORIZED, e.message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Stream of `screen` events, starting with the current screen if any.
+    fn events(&self) -> impl Stream<Item = Result<Event, Infallible>> + Send + 'static {
+        let mut rx = self.tx.subscribe();
+        rx.mark_changed();
+        stream::unfold(rx, |mut rx| async move {
+            loop {
+                rx.changed().await.ok()?;
+                let screen = rx.borrow_and_update().clone();
+                if let Some(screen) = screen {
+                    let event = Event::default()
+                        .event("screen")
+                        .json_data(&screen)
+                        .unwrap_or_default();
+                    return Some((Ok(event), rx));
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        let config = RenderConfig::default()
+            .with_chrome_path("/nonexistent/chrome")
+            .with_temp_dir(std::env::temp_dir().join("trmnl-preview-test"));
+        preview_router(|| async { Ok("<p>hi</p>".to_string()) }, config)
+    }
+
+    #[tokio::test]
+    async fn test_preview_page() {
+        let response = router()
+            .oneshot(Request::get("/preview").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_preview_render_error() {
+        let response = router()
+            .oneshot(Request::get("/preview.png").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_live_preview_events() {
+        use axum::body::BodyDataStream;
+        use futures_util::StreamExt;
+
+        let live = LivePreview::new();
+        let app: Router = live.router();
+
+        let page = app
+            .clone()
+            .oneshot(Request::get("/preview/live").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(page.status(), StatusCode::OK);
+
+        live.publish("AA:BB:CC:DD:EE:FF", "1.png", b"png", ImageFormat::Png);
+        assert_eq!(live.latest().unwrap().filename, "1.png");
+
+        let response = app
+            .oneshot(
+                Request::get("/preview/live/events")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+
+        let mut body: BodyDataStream = response.into_body().into_data_stream();
+        let chunk = body.next().await.unwrap().unwrap();
+        let text = String::from_utf8_lossy(&chunk);
+        assert!(text.contains("event: screen"));
+        assert!(text.contains("data:image/png;base64,cG5n"));
+    }
+
+    #[tokio::test]
+    async fn test_live_preview_token() {
+        let app: Router = LivePreview::new().with_token("s3cret").router();
+        let status = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        assert_eq!(status("/preview/live").await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status("/preview/live/events?token=wrong").await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(status("/preview/live?token=s3cret").await, StatusCode::OK);
+        assert_eq!(
+            status("/preview/live/events?token=s3cret").await,
+            StatusCode::OK
+        );
+    }
+}
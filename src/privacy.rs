@@ -0,0 +1,224 @@
+//! Device ID obfuscation for privacy-conscious deployments.
+//!
+//! MAC addresses are stable hardware identifiers. If you store them, log
+//! them, or attach logs to public bug reports, you may prefer an opaque ID
+//! that still identifies the same device consistently.
+//!
+//! # Example
+//!
+//! ```
+//! use trmnl::privacy::{IdObfuscator, SaltedHash};
+//!
+//! let obfuscator = SaltedHash::new("my-deployment-secret");
+//!
+//! let id = obfuscator.obfuscate("AA:BB:CC:DD:EE:FF");
+//! assert_eq!(id.len(), 16);
+//!
+//! // Formatting differences don't change the result
+//! assert_eq!(id, obfuscator.obfuscate("aa-bb-cc-dd-ee-ff"));
+//! ```
+//!
+//! Pass an obfuscator to `axum_ext::TracingSink`, `axum_ext::FileSink`,
+//! `axum_ext::DeviceTraceLayer` or `axum_ext::ByosState` (with the `axum`
+//! feature) to keep MAC addresses out of their logs and files. The other
+//! handlers in the crate log no device IDs.
+//!
+//! To map obfuscated IDs back to devices, wrap the obfuscator in
+//! [`Reversible`]. It keeps the table in memory only; persisting it is up
+//! to you.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use sha2::{Digest, Sha256};
+
+/// Turns a device ID into a value safe for storage and logging.
+///
+/// Closures taking `&str` and returning `String` implement this trait.
+pub trait IdObfuscator: Send + Sync {
+    /// Obfuscate a device ID (usually a MAC address).
+    fn obfuscate(&self, id: &str) -> String;
+}
+
+impl<F> IdObfuscator for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn obfuscate(&self, id: &str) -> String {
+        self(id)
+    }
+}
+
+impl std::fmt::Debug for dyn IdObfuscator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("IdObfuscator")
+    }
+}
+
+/// Pass-through obfuscator for deployments that don't need privacy.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Plain;
+
+impl IdObfuscator for Plain {
+    fn obfuscate(&self, id: &str) -> String {
+        id.to_string()
+    }
+}
+
+/// Salted SHA-256 obfuscator.
+///
+/// Produces 16 lowercase hex characters (64 bits), which is plenty to keep
+/// a fleet's IDs distinct while being short enough for log lines. MAC
+/// addresses are normalized (separators removed, uppercased) before hashing,
+/// so `aa:bb:..` and `AA-BB-..` map to the same ID.
+///
+/// Keep the salt secret: without it, an attacker could hash every possible
+/// MAC address from a vendor's range and reverse the mapping.
+#[derive(Clone)]
+pub struct SaltedHash {
+    salt: Vec<u8>,
+}
+
+impl SaltedHash {
+    /// Create an obfuscator with the given salt.
+    pub fn new(salt: impl Into<Vec<u8>>) -> Self {
+        Self { salt: salt.into() }
+    }
+
+    /// Create an obfuscator using the salt in an environment variable.
+    ///
+    /// Returns `None` if the variable is not set.
+    pub fn from_env(env_var: &str) -> Option<Self> {
+        std::env::var(env_var).ok().map(Self::new)
+    }
+}
+
+impl std::fmt::Debug for SaltedHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SaltedHash")
+            .field("salt", &"[redacted]")
+            .finish()
+    }
+}
+
+impl IdObfuscator for SaltedHash {
+    fn obfuscate(&self, id: &str) -> String {
+        let normalized: String = id
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+
+        let digest = Sha256::new()
+            .chain_update(&self.salt)
+            .chain_update(normalized.as_bytes())
+            .finalize();
+
+        digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Obfuscator that remembers which ID each input became.
+///
+/// The table lives in memory and is never logged or serialized: its
+/// `Debug` output shows only the number of entries. Clones share the
+/// table, so pass a clone to a sink and keep one for [`reveal`](Self::reveal).
+///
+/// ```
+/// use trmnl::privacy::{IdObfuscator, Reversible, SaltedHash};
+///
+/// let ids = Reversible::new(SaltedHash::new("my-deployment-secret"));
+/// let id = ids.clone().obfuscate("AA:BB:CC:DD:EE:FF");
+///
+/// assert_eq!(ids.reveal(&id).as_deref(), Some("AA:BB:CC:DD:EE:FF"));
+/// ```
+#[derive(Clone)]
+pub struct Reversible<O> {
+    inner: O,
+    seen: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl<O: IdObfuscator> Reversible<O> {
+    /// Record every ID `inner` produces.
+    pub fn new(inner: O) -> Self {
+        Self {
+            inner,
+            seen: Arc::default(),
+        }
+    }
+
+    /// The first input seen for `id`, if any.
+    pub fn reveal(&self, id: &str) -> Option<String> {
+        let seen = self.seen.read().unwrap_or_else(|e| e.into_inner());
+        seen.get(id).cloned()
+    }
+}
+
+impl<O: IdObfuscator> IdObfuscator for Reversible<O> {
+    fn obfuscate(&self, id: &str) -> String {
+        let obfuscated = self.inner.obfuscate(id);
+        let mut seen = self.seen.write().unwrap_or_else(|e| e.into_inner());
+        seen.entry(obfuscated.clone())
+            .or_insert_with(|| id.to_string());
+        obfuscated
+    }
+}
+
+impl<O> std::fmt::Debug for Reversible<O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries = self.seen.read().map_or(0, |seen| seen.len());
+        f.debug_struct("Reversible")
+            .field("entries", &entries)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_salted_hash_is_stable_and_normalized() {
+        let obfuscator = SaltedHash::new("salt");
+        let id = obfuscator.obfuscate("AA:BB:CC:DD:EE:FF");
+
+        assert_eq!(id.len(), 16);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(id, obfuscator.obfuscate("aabbccddeeff"));
+        assert_ne!(id, "AA:BB:CC:DD:EE:FF");
+    }
+
+    #[test]
+    fn test_salt_changes_output() {
+        let a = SaltedHash::new("salt-a").obfuscate("AA:BB:CC:DD:EE:FF");
+        let b = SaltedHash::new("salt-b").obfuscate("AA:BB:CC:DD:EE:FF");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_debug_redacts_salt() {
+        let debug = format!("{:?}", SaltedHash::new("super-secret"));
+        assert!(!debug.contains("super-secret"));
+    }
+
+    #[test]
+    fn test_reversible_reveals_seen_ids() {
+        let ids = Reversible::new(SaltedHash::new("salt"));
+        let id = ids.clone().obfuscate("AA:BB:CC:DD:EE:FF");
+
+        assert_eq!(id, SaltedHash::new("salt").obfuscate("AA:BB:CC:DD:EE:FF"));
+        assert_eq!(ids.reveal(&id).as_deref(), Some("AA:BB:CC:DD:EE:FF"));
+        assert_eq!(ids.reveal("0000000000000000"), None);
+
+        let debug = format!("{:?}", ids);
+        assert!(debug.contains("entries: 1"));
+        assert!(!debug.contains("AA:BB"));
+    }
+
+    #[test]
+    fn test_closure_and_plain() {
+        let upper = |id: &str| id.to_uppercase();
+        assert_eq!(upper.obfuscate("ab"), "AB");
+        assert_eq!(Plain.obfuscate("ab"), "ab");
+    }
+}
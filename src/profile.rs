@@ -0,0 +1,50 @@
+//! Display characteristics for different panels running the TRMNL firmware.
+
+use crate::{DISPLAY_HEIGHT, DISPLAY_WIDTH, MAX_IMAGE_SIZE};
+
+/// Size, file limit and gray levels of a display.
+///
+/// Render and validation code default to [`DisplayProfile::TRMNL_OG`].
+/// Define your own profile for DIY panels running the firmware.
+///
+/// # Example
+///
+/// ```
+/// use trmnl::DisplayProfile;
+///
+/// let diy = DisplayProfile {
+///     width: 1872,
+///     height: 1404,
+///     max_bytes: 256 * 1024,
+///     palette: 16,
+/// };
+/// assert_eq!(DisplayProfile::default(), DisplayProfile::TRMNL_OG);
+/// assert_ne!(diy, DisplayProfile::TRMNL_OG);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DisplayProfile {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Largest image file the firmware accepts, in bytes
+    pub max_bytes: usize,
+    /// Number of gray levels the panel can show
+    pub palette: u32,
+}
+
+impl DisplayProfile {
+    /// The original TRMNL: 800x480, 90KB, 16 grays.
+    pub const TRMNL_OG: DisplayProfile = DisplayProfile {
+        width: DISPLAY_WIDTH,
+        height: DISPLAY_HEIGHT,
+        max_bytes: MAX_IMAGE_SIZE,
+        palette: 16,
+    };
+}
+
+impl Default for DisplayProfile {
+    fn default() -> Self {
+        Self::TRMNL_OG
+    }
+}
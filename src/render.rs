@@ -24,11 +24,16 @@
 //! let png_data = render_html_to_png(html, &config).await?;
 //! ```
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use tokio::process::Command;
 
 use crate::error::Error;
+use crate::negotiate::ImageFormat;
+use crate::profile::DisplayProfile;
 use crate::{DISPLAY_HEIGHT, DISPLAY_WIDTH, MAX_IMAGE_SIZE};
 
 /// Configuration for HTML rendering.
@@ -37,6 +42,9 @@ pub struct RenderConfig {
     /// Path to Chrome executable (default: "google-chrome")
     pub chrome_path: String,
 
+    /// Engine used to drive Chrome (default: [`RenderEngine::ChromeCli`])
+    pub engine: RenderEngine,
+
     /// Directory for temporary files (default: "/tmp/trmnl")
     pub temp_dir: PathBuf,
 
@@ -51,6 +59,48 @@ pub struct RenderConfig {
 
     /// Display height (default: 480)
     pub height: u32,
+
+    /// Maximum time a whole render may take, shared by the Chrome and
+    /// ImageMagick steps; processes still running at the deadline are
+    /// killed (default: 30s)
+    pub timeout: Duration,
+
+    /// Observer notified after each render (default: none)
+    pub observer: Option<Arc<dyn RenderObserver>>,
+
+    /// Gamma correction applied before quantization (default: none).
+    /// Values above 1.0 lighten midtones, below 1.0 darken them.
+    pub gamma: Option<f32>,
+
+    /// Percentage of darkest/lightest pixels clipped when stretching
+    /// contrast before quantization (default: none)
+    pub contrast_stretch: Option<f32>,
+
+    /// Threshold (0-100%) for converting to pure black and white before
+    /// quantization (default: none)
+    pub threshold: Option<u8>,
+
+    /// Region of the page to capture, in page pixels (default: none, the
+    /// top-left `width` x `height`). Regions of a different size are scaled
+    /// to fit the display. Cropping requires optimization (ImageMagick).
+    pub capture_region: Option<Rect>,
+
+    /// Vertical scroll offset in page pixels applied before capturing
+    /// (default: 0). Offsets `capture_region` when both are set.
+    pub scroll_y: u32,
+
+    /// Re-encode the final image as an indexed grayscale PNG at this bit
+    /// depth (1, 2 or 4) instead of keeping ImageMagick's choice (default:
+    /// none). Requires the `image` feature; renders fail with
+    /// `Error::Config` if it is set without it.
+    pub indexed_depth: Option<u8>,
+
+    /// Output format (default: PNG). BMP output is 1-bit and requires
+    /// optimization (ImageMagick).
+    pub format: ImageFormat,
+
+    /// Largest output accepted, in bytes (default: 90KB)
+    pub max_image_size: usize,
 }
 
 impl Default for RenderConfig {
@@ -58,11 +108,22 @@ impl Default for RenderConfig {
         Self {
             chrome_path: std::env::var("CHROME_PATH")
                 .unwrap_or_else(|_| "google-chrome".to_string()),
+            engine: RenderEngine::ChromeCli,
             temp_dir: PathBuf::from("/tmp/trmnl"),
             optimize: true,
             color_depth: 16,
             width: DISPLAY_WIDTH,
             height: DISPLAY_HEIGHT,
+            timeout: Duration::from_secs(30),
+            observer: None,
+            gamma: None,
+            contrast_stretch: None,
+            threshold: None,
+            capture_region: None,
+            scroll_y: 0,
+            indexed_depth: None,
+            format: ImageFormat::Png,
+            max_image_size: MAX_IMAGE_SIZE,
         }
     }
 }
@@ -74,6 +135,42 @@ impl RenderConfig {
         self
     }
 
+    /// Select the engine used to drive Chrome.
+    pub fn with_engine(mut self, engine: RenderEngine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Locate Chrome and use it for rendering.
+    ///
+    /// Probes `CHROME_PATH` (if set) followed by [`CHROME_CANDIDATES`], looking
+    /// bare names up on `PATH`. The first existing executable wins.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Chrome` listing every location tried if none exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let config = RenderConfig::default().auto_detect_chrome()?;
+    /// ```
+    pub fn auto_detect_chrome(mut self) -> Result<Self, Error> {
+        let mut candidates: Vec<String> = std::env::var("CHROME_PATH").into_iter().collect();
+        candidates.extend(CHROME_CANDIDATES.iter().map(|c| c.to_string()));
+
+        let path = find_executable(&candidates).ok_or_else(|| {
+            Error::Chrome(format!(
+                "Chrome not found, tried: {}",
+                candidates.join(", ")
+            ))
+        })?;
+        tracing::debug!("Detected Chrome at {}", path.display());
+
+        self.chrome_path = path.to_string_lossy().into_owned();
+        Ok(self)
+    }
+
     /// Create config with custom temp directory.
     pub fn with_temp_dir(mut self, path: impl Into<PathBuf>) -> Self {
         self.temp_dir = path.into();
@@ -85,6 +182,265 @@ impl RenderConfig {
         self.optimize = false;
         self
     }
+
+    /// Set the time a whole render may take.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Apply gamma correction during optimization.
+    pub fn with_gamma(mut self, gamma: f32) -> Self {
+        self.gamma = Some(gamma);
+        self
+    }
+
+    /// Stretch contrast during optimization, clipping `percent` of the
+    /// darkest and lightest pixels.
+    pub fn with_contrast_stretch(mut self, percent: f32) -> Self {
+        self.contrast_stretch = Some(percent);
+        self
+    }
+
+    /// Convert to pure black and white at the given threshold (0-100%)
+    /// during optimization.
+    pub fn with_threshold(mut self, percent: u8) -> Self {
+        self.threshold = Some(percent.min(100));
+        self
+    }
+
+    /// Capture a specific region of the page instead of the top-left corner.
+    pub fn with_capture_region(mut self, region: Rect) -> Self {
+        self.capture_region = Some(region);
+        self
+    }
+
+    /// Scroll the page down by `pixels` before capturing.
+    pub fn with_scroll_y(mut self, pixels: u32) -> Self {
+        self.scroll_y = pixels;
+        self
+    }
+
+    /// Region of the full-page screenshot that ends up on the display.
+    fn crop_region(&self) -> Rect {
+        match self.capture_region {
+            Some(region) => Rect {
+                y: region.y + self.scroll_y,
+                ..region
+            },
+            None => Rect {
+                x: 0,
+                y: self.scroll_y,
+                width: self.width,
+                height: self.height,
+            },
+        }
+    }
+
+    /// Browser window size needed to include the crop region.
+    fn capture_size(&self) -> (u32, u32) {
+        let region = self.crop_region();
+        (
+            self.width.max(region.x + region.width),
+            self.height.max(region.y + region.height),
+        )
+    }
+
+    /// Re-encode output as an indexed grayscale PNG at 1, 2 or 4 bits per pixel.
+    ///
+    /// Requires the `image` feature; see [`indexed_depth`](Self::indexed_depth).
+    pub fn with_indexed_depth(mut self, bits: u8) -> Self {
+        self.indexed_depth = Some(bits);
+        self
+    }
+
+    /// Set width, height, color count and size limit from a display profile.
+    pub fn with_profile(mut self, profile: DisplayProfile) -> Self {
+        self.width = profile.width;
+        self.height = profile.height;
+        self.color_depth = profile.palette;
+        self.max_image_size = profile.max_bytes;
+        self
+    }
+
+    /// Set output format.
+    pub fn with_format(mut self, format: ImageFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the largest output accepted, in bytes.
+    pub fn with_max_image_size(mut self, bytes: usize) -> Self {
+        self.max_image_size = bytes;
+        self
+    }
+
+    /// ImageMagick output argument for `path` in the configured format.
+    fn output_arg(&self, path: &Path) -> String {
+        match self.format {
+            ImageFormat::Png => path.display().to_string(),
+            ImageFormat::Bmp => format!("BMP3:{}", path.display()),
+        }
+    }
+
+    /// Set an observer to receive a [`RenderReport`] after each render.
+    pub fn with_observer(mut self, observer: impl RenderObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+}
+
+/// A rectangle in page pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    /// Left edge
+    pub x: u32,
+    /// Top edge
+    pub y: u32,
+    /// Width
+    pub width: u32,
+    /// Height
+    pub height: u32,
+}
+
+impl Rect {
+    /// Create a rectangle.
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// Chrome/Chromium locations probed by [`RenderConfig::auto_detect_chrome`].
+///
+/// Bare names are looked up on `PATH`; absolute paths are checked directly.
+pub const CHROME_CANDIDATES: &[&str] = &[
+    "google-chrome",
+    "google-chrome-stable",
+    "chromium",
+    "chromium-browser",
+    "chrome",
+    "/usr/bin/google-chrome",
+    "/usr/bin/chromium",
+    "/usr/bin/chromium-browser",
+    "/usr/bin/chrome",
+    "/snap/bin/chromium",
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+    r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+    r"C:\Program Files (x86)\Google\Chrome\Application\chrome.exe",
+];
+
+/// Return the first candidate that exists, searching `PATH` for bare names.
+fn find_executable(candidates: &[String]) -> Option<PathBuf> {
+    let search_path = std::env::var_os("PATH");
+
+    candidates.iter().find_map(|candidate| {
+        let candidate = Path::new(candidate);
+        if candidate.components().count() > 1 {
+            return candidate.is_file().then(|| candidate.to_path_buf());
+        }
+        std::env::split_paths(search_path.as_deref()?)
+            .flat_map(|dir| {
+                [
+                    dir.join(candidate),
+                    dir.join(candidate).with_extension("exe"),
+                ]
+            })
+            .find(|path| path.is_file())
+    })
+}
+
+#[cfg(feature = "axum")]
+impl RenderConfig {
+    /// Check that the configured Chrome executable exists, for readiness
+    /// probes.
+    pub(crate) fn check_chrome(&self) -> Result<(), Error> {
+        match self.engine {
+            RenderEngine::ChromeCli => find_executable(std::slice::from_ref(&self.chrome_path))
+                .map(|_| ())
+                .ok_or_else(|| Error::Chrome(format!("Chrome not found: {}", self.chrome_path))),
+            // headless_chrome locates its own executable
+            #[cfg(feature = "headless-chrome")]
+            RenderEngine::HeadlessChromeCrate => Ok(()),
+        }
+    }
+}
+
+/// Engine used to render HTML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RenderEngine {
+    /// Chrome headless invoked as a command-line process
+    ChromeCli,
+    /// Chrome driven over the DevTools protocol by the `headless_chrome`
+    /// crate. Avoids CLI quirks such as snap-confined Chrome being unable
+    /// to write screenshots outside its sandbox.
+    #[cfg(feature = "headless-chrome")]
+    HeadlessChromeCrate,
+}
+
+/// What produced a rendered image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RenderSource {
+    /// An HTML page captured by the given engine
+    Html(RenderEngine),
+    /// A PDF page or existing image converted by ImageMagick `convert`
+    ImageMagick,
+}
+
+/// Metrics describing a completed render.
+#[derive(Debug, Clone)]
+pub struct RenderReport {
+    /// Wall-clock time spent rendering (including optimization)
+    pub duration: Duration,
+    /// Size of the final image in bytes
+    pub size: usize,
+    /// What produced the image
+    pub source: RenderSource,
+    /// Whether the image was successfully optimized with ImageMagick
+    pub optimized: bool,
+}
+
+/// Hook for feeding render metrics into a metrics system.
+///
+/// Invoked once per render that produces an image, including images that
+/// are subsequently rejected as too large. Closures taking `&RenderReport`
+/// implement this trait.
+///
+/// # Example
+///
+/// ```
+/// use trmnl::render::{RenderConfig, RenderReport};
+///
+/// let config = RenderConfig::default().with_observer(|report: &RenderReport| {
+///     println!("rendered {} bytes in {:?}", report.size, report.duration);
+/// });
+/// assert!(config.observer.is_some());
+/// ```
+pub trait RenderObserver: Send + Sync {
+    /// Called after a render completes.
+    fn on_render(&self, report: &RenderReport);
+}
+
+impl<F> RenderObserver for F
+where
+    F: Fn(&RenderReport) + Send + Sync,
+{
+    fn on_render(&self, report: &RenderReport) {
+        self(report)
+    }
+}
+
+impl std::fmt::Debug for dyn RenderObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RenderObserver")
+    }
 }
 
 /// Render HTML to PNG using Chrome headless.
@@ -102,6 +458,7 @@ impl RenderConfig {
 ///
 /// Returns error if:
 /// - Chrome is not found or fails
+/// - Chrome and ImageMagick together do not finish within `config.timeout`
 /// - File I/O fails
 /// - Image is too large (>90KB after optimization)
 ///
@@ -114,57 +471,199 @@ impl RenderConfig {
 /// let png = render_html_to_png(html, &RenderConfig::default()).await?;
 /// ```
 pub async fn render_html_to_png(html: &str, config: &RenderConfig) -> Result<Vec<u8>, Error> {
-    // Ensure temp directory exists
+    let deadline = tokio::time::Instant::now() + config.timeout;
+    prepare_temp_dirs(config).await?;
+    let session = CaptureSession::start(config, deadline).await?;
+
+    let stem = unique_stem("render");
+    let result = render_page(html, config, &stem, &session, deadline).await;
+    remove_temp_files(config, &stem).await;
+    result
+}
+
+/// Counter for naming temp files of concurrent renders.
+static RENDER_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Unique temp file stem so concurrent renders sharing a temp dir don't clash.
+fn unique_stem(prefix: &str) -> String {
+    let id = RENDER_COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{}-{}-{}", prefix, std::process::id(), id)
+}
+
+/// Remove the temp files written by [`render_page`] for `stem`.
+async fn remove_temp_files(config: &RenderConfig, stem: &str) {
+    let names = [
+        format!("{}.html", stem),
+        format!("{}-screenshot.png", stem),
+        format!("{}-optimized.{}", stem, config.format.extension()),
+    ];
+    for name in names {
+        let _ = tokio::fs::remove_file(config.temp_dir.join(name)).await;
+    }
+}
+
+/// Render many HTML screens in one pass.
+///
+/// Useful for playlists or pre-generating screens. One browser process
+/// renders every screen, and a failure on one screen does not abort the
+/// rest of the batch.
+///
+/// The batch always runs on [`RenderEngine::HeadlessChromeCrate`], whatever
+/// `config.engine` says, since the CLI engine needs a Chrome process per
+/// page. Screens are loaded into a single tab; a tab whose page failed or
+/// timed out is closed and the next screen gets a fresh one.
+/// `config.timeout` applies to each screen.
+///
+/// # Returns
+///
+/// One `(name, result)` pair per input screen, in input order.
+///
+/// # Errors
+///
+/// Returns error only if the shared temp directories cannot be created or
+/// the browser cannot be launched. Per-screen failures are reported in the
+/// individual results.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::render::{render_batch, RenderConfig};
+///
+/// let screens = vec![
+///     ("weather", weather_html),
+///     ("calendar", calendar_html),
+/// ];
+///
+/// for (name, result) in render_batch(screens, &RenderConfig::default()).await? {
+///     match result {
+///         Ok(png) => std::fs::write(format!("{}.png", name), png)?,
+///         Err(e) => eprintln!("{} failed: {}", name, e),
+///     }
+/// }
+/// ```
+#[cfg(feature = "headless-chrome")]
+pub async fn render_batch<N, H>(
+    screens: impl IntoIterator<Item = (N, H)>,
+    config: &RenderConfig,
+) -> Result<Vec<(String, Result<Vec<u8>, Error>)>, Error>
+where
+    N: Into<String>,
+    H: AsRef<str>,
+{
+    prepare_temp_dirs(config).await?;
+    let session = launch_browser(config, tokio::time::Instant::now() + config.timeout).await?;
+
+    let mut results = Vec::new();
+    for (name, html) in screens {
+        let name = name.into();
+        let stem = unique_stem("batch");
+        let deadline = tokio::time::Instant::now() + config.timeout;
+        let result = render_page(html.as_ref(), config, &stem, &session, deadline).await;
+        remove_temp_files(config, &stem).await;
+        if let Err(e) = &result {
+            tracing::warn!("Batch render of '{}' failed: {}", name, e);
+        }
+        results.push((name, result));
+    }
+
+    Ok(results)
+}
+
+/// Create the temp and Chrome profile directories.
+async fn prepare_temp_dirs(config: &RenderConfig) -> Result<(), Error> {
     tokio::fs::create_dir_all(&config.temp_dir)
         .await
         .map_err(|e| Error::Io(format!("Failed to create temp dir: {}", e)))?;
 
-    let html_path = config.temp_dir.join("render.html");
-    let screenshot_path = config.temp_dir.join("screenshot.png");
-    let optimized_path = config.temp_dir.join("optimized.png");
-    let chrome_data_dir = config.temp_dir.join("chrome-data");
+    tokio::fs::create_dir_all(config.temp_dir.join("chrome-data"))
+        .await
+        .map_err(|e| Error::Io(format!("Failed to create chrome data dir: {}", e)))
+}
+
+/// Browser state shared by every page rendered in one call.
+enum CaptureSession {
+    /// Chrome's `--screenshot` mode, one process per page
+    Cli,
+    /// A `headless_chrome` browser and the tab pages are loaded into
+    #[cfg(feature = "headless-chrome")]
+    Crate {
+        browser: headless_chrome::Browser,
+        // Empty after a failed page, until the next page opens a new tab
+        tab: std::sync::Mutex<Option<Arc<headless_chrome::Tab>>>,
+    },
+}
+
+impl CaptureSession {
+    /// Start a session for the configured engine.
+    #[cfg_attr(not(feature = "headless-chrome"), allow(unused_variables))]
+    async fn start(config: &RenderConfig, deadline: tokio::time::Instant) -> Result<Self, Error> {
+        match config.engine {
+            RenderEngine::ChromeCli => Ok(Self::Cli),
+            #[cfg(feature = "headless-chrome")]
+            RenderEngine::HeadlessChromeCrate => launch_browser(config, deadline).await,
+        }
+    }
+
+    /// Engine reported to the observer for pages captured in this session.
+    fn engine(&self) -> RenderEngine {
+        match self {
+            Self::Cli => RenderEngine::ChromeCli,
+            #[cfg(feature = "headless-chrome")]
+            Self::Crate { .. } => RenderEngine::HeadlessChromeCrate,
+        }
+    }
+
+    /// Load `html_url` and write its screenshot to `screenshot_path`.
+    async fn capture(
+        &self,
+        html_url: &str,
+        screenshot_path: &Path,
+        config: &RenderConfig,
+        deadline: tokio::time::Instant,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Cli => capture_with_cli(html_url, screenshot_path, config, deadline).await,
+            #[cfg(feature = "headless-chrome")]
+            Self::Crate { browser, tab } => {
+                capture_with_crate(browser, tab, html_url, screenshot_path, config, deadline).await
+            }
+        }
+    }
+}
+
+/// Render a single page, using `stem` to name its temp files.
+///
+/// Every subprocess step must finish by `deadline`.
+async fn render_page(
+    html: &str,
+    config: &RenderConfig,
+    stem: &str,
+    session: &CaptureSession,
+    deadline: tokio::time::Instant,
+) -> Result<Vec<u8>, Error> {
+    let started = Instant::now();
+
+    let html_path = config.temp_dir.join(format!("{}.html", stem));
+    let screenshot_path = config.temp_dir.join(format!("{}-screenshot.png", stem));
+    let optimized_path =
+        config
+            .temp_dir
+            .join(format!("{}-optimized.{}", stem, config.format.extension()));
+
+    // Remove leftovers from a previous render so they can't be mistaken for output
+    let _ = tokio::fs::remove_file(&screenshot_path).await;
+    let _ = tokio::fs::remove_file(&optimized_path).await;
 
     // Write HTML file
     tokio::fs::write(&html_path, html)
         .await
         .map_err(|e| Error::Io(format!("Failed to write HTML: {}", e)))?;
 
-    // Ensure chrome data dir exists
-    tokio::fs::create_dir_all(&chrome_data_dir)
-        .await
-        .map_err(|e| Error::Io(format!("Failed to create chrome data dir: {}", e)))?;
-
     let html_url = format!("file://{}", html_path.display());
 
-    // Run Chrome headless
-    let output = Command::new(&config.chrome_path)
-        .arg("--headless=new")
-        .arg("--no-sandbox")
-        .arg("--disable-gpu")
-        .arg("--disable-dev-shm-usage")
-        .arg("--disable-software-rasterizer")
-        .arg("--no-first-run")
-        .arg("--disable-extensions")
-        .arg("--disable-background-networking")
-        .arg("--force-device-scale-factor=1")
-        .arg("--hide-scrollbars")
-        .arg("--default-background-color=ffffffff")
-        .arg(format!("--user-data-dir={}", chrome_data_dir.display()))
-        .arg(format!(
-            "--window-size={},{}",
-            config.width,
-            config.height + 100 // Extra height for scrollbar avoidance
-        ))
-        .arg(format!("--screenshot={}", screenshot_path.display()))
-        .arg(&html_url)
-        .output()
-        .await
-        .map_err(|e| Error::Chrome(format!("Failed to run Chrome: {}", e)))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        tracing::warn!("Chrome stderr: {}", stderr);
-    }
+    session
+        .capture(&html_url, &screenshot_path, config, deadline)
+        .await?;
 
     // Check if screenshot was created
     if !tokio::fs::try_exists(&screenshot_path)
@@ -179,18 +678,16 @@ pub async fn render_html_to_png(html: &str, config: &RenderConfig) -> Result<Vec
     // Optimize if requested
     let final_path = if config.optimize {
         // Try to optimize with ImageMagick
-        let convert_result = Command::new("convert")
+        let convert = Command::new("convert")
             .arg(&screenshot_path)
-            .arg("-crop")
-            .arg(format!("{}x{}+0+0", config.width, config.height))
-            .arg("+repage")
-            .arg("-colors")
-            .arg(config.color_depth.to_string())
-            .arg("-depth")
-            .arg("4")
-            .arg(&optimized_path)
-            .output()
-            .await;
+            .args(crop_args(config))
+            .args(quantize_args(config))
+            .arg(config.output_arg(&optimized_path))
+            .kill_on_drop(true)
+            .output();
+        let convert_result = tokio::time::timeout_at(deadline, convert)
+            .await
+            .map_err(|_| Error::RenderTimeout(config.timeout))?;
 
         match convert_result {
             Ok(output) if output.status.success() => {
@@ -198,7 +695,7 @@ pub async fn render_html_to_png(html: &str, config: &RenderConfig) -> Result<Vec
                     .await
                     .unwrap_or(false)
                 {
-                    optimized_path
+                    optimized_path.clone()
                 } else {
                     screenshot_path
                 }
@@ -222,19 +719,482 @@ pub async fn render_html_to_png(html: &str, config: &RenderConfig) -> Result<Vec
         .await
         .map_err(|e| Error::Io(format!("Failed to read screenshot: {}", e)))?;
 
-    tracing::info!("Rendered PNG: {} bytes", png_data.len());
+    let optimized = final_path == optimized_path;
+    if config.format != ImageFormat::Png && !optimized {
+        return Err(Error::Render(format!(
+            "{:?} output requires ImageMagick optimization",
+            config.format
+        )));
+    }
+    finish_render(
+        png_data,
+        config,
+        started,
+        RenderSource::Html(session.engine()),
+        optimized,
+    )
+}
+
+/// Capture a screenshot by running Chrome's `--screenshot` CLI mode.
+async fn capture_with_cli(
+    html_url: &str,
+    screenshot_path: &Path,
+    config: &RenderConfig,
+    deadline: tokio::time::Instant,
+) -> Result<(), Error> {
+    let chrome_data_dir = config.temp_dir.join("chrome-data");
+    let (capture_width, capture_height) = config.capture_size();
+
+    // Run Chrome headless. `kill_on_drop` ensures the child is reaped both on
+    // timeout and when the caller drops this future (e.g. client disconnect).
+    let chrome = Command::new(&config.chrome_path)
+        .arg("--headless=new")
+        .arg("--no-sandbox")
+        .arg("--disable-gpu")
+        .arg("--disable-dev-shm-usage")
+        .arg("--disable-software-rasterizer")
+        .arg("--no-first-run")
+        .arg("--disable-extensions")
+        .arg("--disable-background-networking")
+        .arg("--force-device-scale-factor=1")
+        .arg("--hide-scrollbars")
+        .arg("--default-background-color=ffffffff")
+        .arg(format!("--user-data-dir={}", chrome_data_dir.display()))
+        .arg(format!(
+            "--window-size={},{}",
+            capture_width,
+            capture_height + 100 // Extra height for scrollbar avoidance
+        ))
+        .arg(format!("--screenshot={}", screenshot_path.display()))
+        .arg(html_url)
+        .kill_on_drop(true)
+        .output();
+
+    let output = tokio::time::timeout_at(deadline, chrome)
+        .await
+        .map_err(|_| Error::RenderTimeout(config.timeout))?
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => Error::Chrome(format!(
+                "Chrome not found at '{}' (try RenderConfig::auto_detect_chrome())",
+                config.chrome_path
+            )),
+            _ => Error::Chrome(format!("Failed to run Chrome: {}", e)),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        tracing::warn!("Chrome stderr: {}", stderr);
+    }
+
+    Ok(())
+}
+
+/// Launch a browser and open the tab pages are rendered in.
+///
+/// The crate's API is blocking, so the launch runs on a blocking thread.
+/// The browser shuts itself down after idling for `config.timeout`.
+#[cfg(feature = "headless-chrome")]
+async fn launch_browser(
+    config: &RenderConfig,
+    deadline: tokio::time::Instant,
+) -> Result<CaptureSession, Error> {
+    use headless_chrome::{Browser, LaunchOptions};
+
+    let chrome_path = PathBuf::from(&config.chrome_path);
+    let ((width, height), timeout) = (config.capture_size(), config.timeout);
+
+    let launch = tokio::task::spawn_blocking(move || -> Result<CaptureSession, String> {
+        let options = LaunchOptions::default_builder()
+            .path(Some(chrome_path))
+            .window_size(Some((width, height)))
+            .sandbox(false)
+            .idle_browser_timeout(timeout)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let browser = Browser::new(options).map_err(|e| e.to_string())?;
+        let tab = open_tab(&browser, timeout)?;
+        Ok(CaptureSession::Crate {
+            browser,
+            tab: std::sync::Mutex::new(Some(tab)),
+        })
+    });
+
+    tokio::time::timeout_at(deadline, launch)
+        .await
+        .map_err(|_| Error::RenderTimeout(timeout))?
+        .map_err(|e| Error::Chrome(format!("Browser launch task failed: {}", e)))?
+        .map_err(|e| Error::Chrome(format!("headless_chrome failed to launch: {}", e)))
+}
+
+/// Open a tab whose operations give up after `timeout`.
+#[cfg(feature = "headless-chrome")]
+fn open_tab(
+    browser: &headless_chrome::Browser,
+    timeout: Duration,
+) -> Result<Arc<headless_chrome::Tab>, String> {
+    let tab = browser.new_tab().map_err(|e| e.to_string())?;
+    tab.set_default_timeout(timeout);
+    Ok(tab)
+}
+
+/// Capture a screenshot over the DevTools protocol using `headless_chrome`.
+///
+/// The crate's API is blocking, so the capture runs on a blocking thread.
+/// The page is loaded into the session's tab, or a new one if the previous
+/// page failed. A tab is never reused after a failure: on error it is
+/// closed, and on timeout the render returns immediately and the tab is
+/// closed once its own timeout (also `config.timeout`) stops the abandoned
+/// navigation.
+#[cfg(feature = "headless-chrome")]
+async fn capture_with_crate(
+    browser: &headless_chrome::Browser,
+    tab: &std::sync::Mutex<Option<Arc<headless_chrome::Tab>>>,
+    html_url: &str,
+    screenshot_path: &Path,
+    config: &RenderConfig,
+    deadline: tokio::time::Instant,
+) -> Result<(), Error> {
+    use headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption;
+
+    let current = tab
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .take();
+    let (browser, html_url, timeout) = (browser.clone(), html_url.to_string(), config.timeout);
+
+    let mut capture = tokio::task::spawn_blocking(move || {
+        let tab = match current {
+            Some(tab) => tab,
+            None => open_tab(&browser, timeout)?,
+        };
+        let png = tab
+            .navigate_to(&html_url)
+            .and_then(|tab| tab.wait_until_navigated())
+            .and_then(|tab| {
+                tab.capture_screenshot(CaptureScreenshotFormatOption::Png, None, None, true)
+            });
+        match png {
+            Ok(png) => Ok((tab, png)),
+            Err(e) => {
+                let _ = tab.close(false);
+                Err(e.to_string())
+            }
+        }
+    });
+
+    let (reusable, png) = match tokio::time::timeout_at(deadline, &mut capture).await {
+        Ok(joined) => joined
+            .map_err(|e| Error::Chrome(format!("Render task failed: {}", e)))?
+            .map_err(|e| Error::Chrome(format!("headless_chrome failed: {}", e)))?,
+        Err(_) => {
+            tokio::spawn(async move {
+                if let Ok(Ok((abandoned, _))) = capture.await {
+                    let _ = tokio::task::spawn_blocking(move || abandoned.close(false)).await;
+                }
+            });
+            return Err(Error::RenderTimeout(timeout));
+        }
+    };
+    *tab.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(reusable);
+
+    tokio::fs::write(screenshot_path, png)
+        .await
+        .map_err(|e| Error::Io(format!("Failed to write screenshot: {}", e)))
+}
+
+/// Render one page of a PDF to PNG using ImageMagick.
+///
+/// The page is rasterized, scaled to fit the display (letterboxed on
+/// white) and, if enabled, optimized like HTML renders. Useful for daily
+/// reports that are already produced as PDFs.
+///
+/// # Requirements
+///
+/// ImageMagick (`convert`) with Ghostscript for PDF support.
+///
+/// # Arguments
+///
+/// * `pdf` - PDF file contents
+/// * `page` - Zero-based page index
+/// * `config` - Render configuration (dimensions, optimization, timeout)
+///
+/// # Errors
+///
+/// Returns error if:
+/// - ImageMagick is missing or cannot read the PDF/page
+/// - Conversion does not finish within `config.timeout`
+/// - File I/O fails
+/// - Image is too large (>90KB after optimization)
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::render::{render_pdf_page_to_png, RenderConfig};
+///
+/// let pdf = std::fs::read("briefing.pdf")?;
+/// let png = render_pdf_page_to_png(&pdf, 0, &RenderConfig::default()).await?;
+/// ```
+pub async fn render_pdf_page_to_png(
+    pdf: &[u8],
+    page: u32,
+    config: &RenderConfig,
+) -> Result<Vec<u8>, Error> {
+    letterbox_with_imagemagick(
+        pdf,
+        "pdf",
+        &["-density", "150"],
+        page,
+        &[],
+        config.optimize,
+        config,
+    )
+    .await
+}
+
+/// Convert an existing image (JPEG, PNG, WebP or GIF) for display on a TRMNL.
+///
+/// The image is auto-rotated from EXIF, scaled to fit the display
+/// (letterboxed on white), converted to grayscale and quantized with the
+/// same options as HTML renders. Handy for photos and camera snapshots.
+///
+/// Quantization always runs, even if `config.optimize` is false. Combine
+/// with [`RenderConfig::with_threshold`] or [`RenderConfig::with_indexed_depth`]
+/// (with the `image` feature) if the result is still over 90KB.
+///
+/// # Requirements
+///
+/// ImageMagick (`convert`) with delegates for the input format.
+///
+/// # Errors
+///
+/// Returns error if:
+/// - The input is not a JPEG, PNG, WebP or GIF (judged by its leading bytes;
+///   other formats, notably SVG, are rejected because ImageMagick can be
+///   made to run scripts or read local files through them)
+/// - ImageMagick is missing or cannot decode the input
+/// - Conversion does not finish within `config.timeout`
+/// - File I/O fails
+/// - Image is too large (>90KB after conversion)
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use trmnl::render::{convert_image_for_trmnl, RenderConfig};
+///
+/// let photo = std::fs::read("snapshot.jpg")?;
+/// let png = convert_image_for_trmnl(&photo, &RenderConfig::default()).await?;
+/// ```
+pub async fn convert_image_for_trmnl(
+    image: &[u8],
+    config: &RenderConfig,
+) -> Result<Vec<u8>, Error> {
+    let coder = image_coder(image).ok_or_else(|| {
+        Error::Render("Unsupported image format (expected JPEG, PNG, WebP or GIF)".to_string())
+    })?;
+    letterbox_with_imagemagick(image, coder, &[], 0, &["-colorspace", "Gray"], true, config).await
+}
+
+/// ImageMagick coder for `image`, identified from its magic bytes.
+///
+/// Only raster formats are recognized, so ImageMagick is never asked to
+/// interpret scriptable inputs such as SVG, MVG or MSL.
+fn image_coder(image: &[u8]) -> Option<&'static str> {
+    match image {
+        [0xFF, 0xD8, 0xFF, ..] => Some("jpeg"),
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some("png"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("webp"),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some("gif"),
+        _ => None,
+    }
+}
+
+/// Scale frame `frame` of `input` onto a white, display-sized canvas with
+/// ImageMagick, then apply `extra_args` and (if `optimize`) quantization.
+///
+/// `coder` forces ImageMagick's input format instead of letting it guess
+/// from the content.
+async fn letterbox_with_imagemagick(
+    input: &[u8],
+    coder: &str,
+    read_args: &[&str],
+    frame: u32,
+    extra_args: &[&str],
+    optimize: bool,
+    config: &RenderConfig,
+) -> Result<Vec<u8>, Error> {
+    let deadline = tokio::time::Instant::now() + config.timeout;
+    tokio::fs::create_dir_all(&config.temp_dir)
+        .await
+        .map_err(|e| Error::Io(format!("Failed to create temp dir: {}", e)))?;
+
+    let stem = unique_stem("convert");
+    let input_path = config.temp_dir.join(format!("{}-source.{}", stem, coder));
+    let output_path =
+        config
+            .temp_dir
+            .join(format!("{}-output.{}", stem, config.format.extension()));
+
+    let started = Instant::now();
+    let result = async {
+        tokio::fs::write(&input_path, input)
+            .await
+            .map_err(|e| Error::Io(format!("Failed to write conversion input: {}", e)))?;
+
+        let source = format!("{}:{}[{}]", coder, input_path.display(), frame);
+        let size = format!("{}x{}", config.width, config.height);
+        let mut convert = Command::new("convert");
+        convert
+            .args(read_args)
+            .arg(source)
+            .args(["-auto-orient", "-background", "white", "-alpha", "remove"])
+            .args(["-resize", &size, "-gravity", "center", "-extent", &size])
+            .args(extra_args);
+        if optimize || config.format != ImageFormat::Png {
+            convert.args(quantize_args(config));
+        }
+        let convert = convert
+            .arg(config.output_arg(&output_path))
+            .kill_on_drop(true)
+            .output();
+
+        let output = tokio::time::timeout_at(deadline, convert)
+            .await
+            .map_err(|_| Error::RenderTimeout(config.timeout))?
+            .map_err(|e| Error::Render(format!("Failed to run ImageMagick: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(Error::Render(format!(
+                "ImageMagick failed to convert {} input (frame {}): {}",
+                coder,
+                frame,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let png_data = tokio::fs::read(&output_path)
+            .await
+            .map_err(|e| Error::Io(format!("Failed to read converted image: {}", e)))?;
+
+        finish_render(
+            png_data,
+            config,
+            started,
+            RenderSource::ImageMagick,
+            optimize,
+        )
+    }
+    .await;
+
+    let _ = tokio::fs::remove_file(&input_path).await;
+    let _ = tokio::fs::remove_file(&output_path).await;
+    result
+}
+
+/// Log, report to the observer and size-check a rendered image.
+fn finish_render(
+    png_data: Vec<u8>,
+    config: &RenderConfig,
+    started: Instant,
+    source: RenderSource,
+    optimized: bool,
+) -> Result<Vec<u8>, Error> {
+    let png_data = match config.indexed_depth {
+        #[cfg(feature = "image")]
+        Some(bits) if config.format == ImageFormat::Png => {
+            crate::image::encode_indexed(&png_data, bits)?
+        }
+        #[cfg(not(feature = "image"))]
+        Some(bits) if config.format == ImageFormat::Png => {
+            return Err(Error::Config(format!(
+                "indexed_depth of {} bits requires the `image` feature",
+                bits
+            )));
+        }
+        _ => png_data,
+    };
+
+    tracing::info!("Rendered {:?}: {} bytes", config.format, png_data.len());
+
+    if let Some(observer) = &config.observer {
+        observer.on_render(&RenderReport {
+            duration: started.elapsed(),
+            size: png_data.len(),
+            source,
+            optimized,
+        });
+    }
 
     // Check size
-    if png_data.len() > MAX_IMAGE_SIZE {
+    if png_data.len() > config.max_image_size {
         return Err(Error::ImageTooLarge {
             size: png_data.len(),
-            max: MAX_IMAGE_SIZE,
+            max: config.max_image_size,
         });
     }
 
     Ok(png_data)
 }
 
+/// ImageMagick arguments for cutting the display area out of a screenshot.
+fn crop_args(config: &RenderConfig) -> Vec<String> {
+    let region = config.crop_region();
+    let mut args = vec![
+        "-crop".to_string(),
+        format!(
+            "{}x{}+{}+{}",
+            region.width, region.height, region.x, region.y
+        ),
+        "+repage".to_string(),
+    ];
+
+    if (region.width, region.height) != (config.width, config.height) {
+        let size = format!("{}x{}", config.width, config.height);
+        args.extend(
+            [
+                "-resize",
+                &size,
+                "-background",
+                "white",
+                "-gravity",
+                "center",
+                "-extent",
+                &size,
+            ]
+            .map(String::from),
+        );
+    }
+    args
+}
+
+/// ImageMagick arguments for enhancing and quantizing an image.
+///
+/// Levels adjustments run before `-colors` so they shape which colors
+/// survive quantization.
+fn quantize_args(config: &RenderConfig) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(gamma) = config.gamma {
+        args.extend(["-gamma".to_string(), gamma.to_string()]);
+    }
+    if let Some(percent) = config.contrast_stretch {
+        args.extend(["-contrast-stretch".to_string(), format!("{}%", percent)]);
+    }
+    if let Some(percent) = config.threshold {
+        args.extend(["-threshold".to_string(), format!("{}%", percent)]);
+    }
+
+    match config.format {
+        ImageFormat::Png => args.extend([
+            "-colors".to_string(),
+            config.color_depth.to_string(),
+            "-depth".to_string(),
+            "4".to_string(),
+        ]),
+        ImageFormat::Bmp => args.extend(["-monochrome".to_string()]),
+    }
+    args
+}
+
 /// Generate a timestamped filename for cache busting.
 ///
 /// The TRMNL firmware compares filenames to detect new images.
@@ -267,6 +1227,48 @@ mod tests {
         assert_eq!(config.height, 480);
         assert!(config.optimize);
         assert_eq!(config.color_depth, 16);
+        assert_eq!(config.engine, RenderEngine::ChromeCli);
+    }
+
+    #[test]
+    fn test_timeout_config() {
+        assert_eq!(RenderConfig::default().timeout, Duration::from_secs(30));
+
+        let config = RenderConfig::default().with_timeout(Duration::from_secs(5));
+        assert_eq!(config.timeout, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_output_format_defaults() {
+        let config = RenderConfig::default();
+        assert_eq!(config.format, ImageFormat::Png);
+        assert_eq!(config.max_image_size, MAX_IMAGE_SIZE);
+    }
+
+    #[test]
+    fn test_with_profile() {
+        let profile = DisplayProfile {
+            width: 1872,
+            height: 1404,
+            max_bytes: 256 * 1024,
+            palette: 4,
+        };
+        let config = RenderConfig::default().with_profile(profile);
+        assert_eq!((config.width, config.height), (1872, 1404));
+        assert_eq!(config.color_depth, 4);
+        assert_eq!(config.max_image_size, 256 * 1024);
+    }
+
+    #[test]
+    fn test_bmp_output_args() {
+        let config = RenderConfig::default()
+            .with_format(ImageFormat::Bmp)
+            .with_gamma(0.8);
+        assert_eq!(quantize_args(&config), ["-gamma", "0.8", "-monochrome"]);
+        assert_eq!(
+            config.output_arg(Path::new("/tmp/out.bmp")),
+            "BMP3:/tmp/out.bmp"
+        );
     }
 
     #[test]
@@ -289,4 +1291,141 @@ mod tests {
         assert_eq!(config.temp_dir, PathBuf::from("/var/tmp/trmnl"));
         assert!(!config.optimize);
     }
+
+    #[test]
+    fn test_quantize_args_enhancements_before_colors() {
+        let config = RenderConfig::default();
+        assert_eq!(crop_args(&config), ["-crop", "800x480+0+0", "+repage"]);
+        assert_eq!(quantize_args(&config), ["-colors", "16", "-depth", "4"]);
+
+        let args = quantize_args(
+            &RenderConfig::default()
+                .with_gamma(1.5)
+                .with_contrast_stretch(2.0)
+                .with_threshold(60),
+        );
+        let colors = args.iter().position(|a| a == "-colors").unwrap();
+        for (flag, value) in [
+            ("-gamma", "1.5"),
+            ("-contrast-stretch", "2%"),
+            ("-threshold", "60%"),
+        ] {
+            let idx = args.iter().position(|a| a == flag).unwrap();
+            assert_eq!(args[idx + 1], value);
+            assert!(idx < colors);
+        }
+    }
+
+    #[test]
+    fn test_capture_region_and_scroll() {
+        let scrolled = RenderConfig::default().with_scroll_y(300);
+        assert_eq!(scrolled.capture_size(), (800, 780));
+        assert_eq!(crop_args(&scrolled)[1], "800x480+0+300");
+
+        let region = RenderConfig::default()
+            .with_capture_region(Rect::new(100, 1000, 1600, 960))
+            .with_scroll_y(50);
+        assert_eq!(region.capture_size(), (1700, 2010));
+
+        let args = crop_args(&region);
+        assert_eq!(args[1], "1600x960+100+1050");
+        let resize = args.iter().position(|a| a == "-resize").unwrap();
+        assert_eq!(args[resize + 1], "800x480");
+    }
+
+    #[test]
+    fn test_find_executable() {
+        let dir = std::env::temp_dir().join("trmnl-find-chrome-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let fake_chrome = dir.join("fake-chrome");
+        std::fs::write(&fake_chrome, "").unwrap();
+
+        let candidates = vec![
+            "/nonexistent/chrome".to_string(),
+            fake_chrome.display().to_string(),
+        ];
+        assert_eq!(find_executable(&candidates), Some(fake_chrome));
+        assert_eq!(find_executable(&candidates[..1]), None);
+    }
+
+    #[cfg(feature = "headless-chrome")]
+    #[tokio::test]
+    async fn test_render_batch_requires_browser() {
+        // The CLI engine is ignored; the batch launches its own browser
+        let config = RenderConfig::default()
+            .with_engine(RenderEngine::ChromeCli)
+            .with_chrome_path("/nonexistent/chrome")
+            .with_temp_dir(std::env::temp_dir().join("trmnl-batch-test"));
+        let _ = std::fs::remove_dir_all(&config.temp_dir);
+
+        let result = render_batch(vec![("a", "<p>a</p>"), ("b", "<p>b</p>")], &config).await;
+        assert!(matches!(result, Err(Error::Chrome(_))));
+
+        let leftovers = std::fs::read_dir(&config.temp_dir)
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("batch-"))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_render_timeout_kills_chrome() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join("trmnl-timeout-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let hung_chrome = dir.join("hung-chrome");
+        std::fs::write(&hung_chrome, "#!/bin/sh\nsleep 10\n").unwrap();
+        std::fs::set_permissions(&hung_chrome, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = RenderConfig::default()
+            .with_chrome_path(hung_chrome.display().to_string())
+            .with_temp_dir(&dir)
+            .with_timeout(Duration::from_millis(300));
+
+        let started = Instant::now();
+        let result = render_html_to_png("<p>hi</p>", &config).await;
+        assert!(matches!(result, Err(Error::RenderTimeout(_))));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_convert_image_rejects_garbage() {
+        let config =
+            RenderConfig::default().with_temp_dir(std::env::temp_dir().join("trmnl-convert-test"));
+
+        let result = convert_image_for_trmnl(b"not an image", &config).await;
+        assert!(matches!(result, Err(Error::Render(_))));
+
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg"></svg>"#;
+        let result = convert_image_for_trmnl(svg, &config).await;
+        assert!(matches!(result, Err(Error::Render(_))));
+    }
+
+    #[cfg(not(feature = "image"))]
+    #[test]
+    fn test_indexed_depth_requires_image_feature() {
+        let config = RenderConfig::default().with_indexed_depth(2);
+        let result = finish_render(
+            vec![0; 16],
+            &config,
+            Instant::now(),
+            RenderSource::ImageMagick,
+            true,
+        );
+        assert!(matches!(result, Err(Error::Config(_))));
+    }
+
+    #[test]
+    fn test_image_coder() {
+        assert_eq!(image_coder(&[0xFF, 0xD8, 0xFF, 0xE0]), Some("jpeg"));
+        assert_eq!(image_coder(b"\x89PNG\r\n\x1a\n...."), Some("png"));
+        assert_eq!(image_coder(b"RIFF\0\0\0\0WEBPVP8 "), Some("webp"));
+        assert_eq!(image_coder(b"GIF89a.."), Some("gif"));
+        assert_eq!(image_coder(b"<?xml version=\"1.0\"?><svg/>"), None);
+        assert_eq!(image_coder(b"push graphic-context"), None);
+        assert_eq!(image_coder(b""), None);
+    }
 }
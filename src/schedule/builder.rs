@@ -0,0 +1,292 @@
+//! Building schedules in code instead of YAML.
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+
+use super::{
+    AmbiguousTimePolicy, BatteryScaling, CronExpr, DateRange, DaySelector, MonthSelector,
+    RefreshSchedule, RuleMode, ScheduleRule,
+};
+use crate::Error;
+
+/// Builder for a [`RefreshSchedule`] configured from code (e.g. from a
+/// database) rather than a YAML file.
+///
+/// Created by [`RefreshSchedule::builder`]. [`build`](Self::build) checks
+/// the schedule the same way [`RefreshSchedule::from_yaml`] does.
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleBuilder {
+    timezone: Option<Tz>,
+    default_refresh_rate: Option<u32>,
+    rules: Vec<ScheduleRule>,
+    holidays: Vec<DateRange>,
+    align: bool,
+    default_screen: Option<String>,
+    ambiguous_time_policy: AmbiguousTimePolicy,
+    battery_scaling: Vec<BatteryScaling>,
+}
+
+impl ScheduleBuilder {
+    /// Set the timezone rule times are interpreted in.
+    #[must_use]
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = Some(timezone);
+        self
+    }
+
+    /// Set the refresh rate used when no rule matches (in seconds).
+    #[must_use]
+    pub fn with_default_refresh_rate(mut self, seconds: u32) -> Self {
+        self.default_refresh_rate = Some(seconds);
+        self
+    }
+
+    /// Add a rule; rules are evaluated in the order they are added.
+    #[must_use]
+    pub fn with_rule(mut self, rule: ScheduleRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Add a date or date range to the holiday calendar.
+    #[must_use]
+    pub fn with_holiday(mut self, holiday: impl Into<DateRange>) -> Self {
+        self.holidays.push(holiday.into());
+        self
+    }
+
+    /// Align fixed refresh rates to wall-clock boundaries (see
+    /// [`ScheduleRule::align`]).
+    #[must_use]
+    pub fn with_align(mut self, align: bool) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Set the screen shown when the matching rule has none.
+    #[must_use]
+    pub fn with_default_screen(mut self, screen: impl Into<String>) -> Self {
+        self.default_screen = Some(screen.into());
+        self
+    }
+
+    /// Set which occurrence of a repeated local time to wake at when clocks
+    /// go back.
+    #[must_use]
+    pub fn with_ambiguous_time_policy(mut self, policy: AmbiguousTimePolicy) -> Self {
+        self.ambiguous_time_policy = policy;
+        self
+    }
+
+    /// Multiply fixed refresh rates by `multiplier` for devices below
+    /// `below` percent battery.
+    #[must_use]
+    pub fn with_battery_scaling(mut self, below: u8, multiplier: u32) -> Self {
+        self.battery_scaling
+            .push(BatteryScaling { below, multiplier });
+        self
+    }
+
+    /// Validate and build the schedule.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Config` if the timezone or default refresh rate is
+    /// not set, or a rule is invalid.
+    pub fn build(self) -> Result<RefreshSchedule, Error> {
+        let timezone = self
+            .timezone
+            .ok_or_else(|| Error::Config("Schedule needs a timezone".to_string()))?;
+        let default_refresh_rate = self
+            .default_refresh_rate
+            .ok_or_else(|| Error::Config("Schedule needs a default refresh rate".to_string()))?;
+
+        let schedule = RefreshSchedule {
+            timezone: timezone.name().to_string(),
+            default_refresh_rate,
+            schedule: self.rules,
+            holidays: self.holidays,
+            align: self.align,
+            default_screen: self.default_screen,
+            ambiguous_time_policy: self.ambiguous_time_policy,
+            battery_scaling: self.battery_scaling,
+        };
+        schedule.check()?;
+        Ok(schedule)
+    }
+}
+
+impl ScheduleRule {
+    /// Poll every `refresh_rate` seconds on `days` between `start` and `end`
+    /// (HH:MM, 24-hour format).
+    pub fn new(
+        days: impl Into<DaySelector>,
+        start: impl Into<String>,
+        end: impl Into<String>,
+        refresh_rate: u32,
+    ) -> Self {
+        Self {
+            days: days.into(),
+            start: Some(start.into()),
+            end: Some(end.into()),
+            refresh_rate: Some(refresh_rate),
+            ..Default::default()
+        }
+    }
+
+    /// Poll every `refresh_rate` seconds between `start` and `end` every day.
+    pub fn daily(start: impl Into<String>, end: impl Into<String>, refresh_rate: u32) -> Self {
+        Self::new("all", start, end, refresh_rate)
+    }
+
+    /// Poll every `refresh_rate` seconds between `start` and `end` on
+    /// weekdays.
+    pub fn weekdays(start: impl Into<String>, end: impl Into<String>, refresh_rate: u32) -> Self {
+        Self::new("weekdays", start, end, refresh_rate)
+    }
+
+    /// Poll every `refresh_rate` seconds between `start` and `end` on
+    /// weekends.
+    pub fn weekends(start: impl Into<String>, end: impl Into<String>, refresh_rate: u32) -> Self {
+        Self::new("weekends", start, end, refresh_rate)
+    }
+
+    /// Wake the device at each run of `cron`.
+    pub fn from_cron(cron: CronExpr) -> Self {
+        Self {
+            cron: Some(cron),
+            ..Default::default()
+        }
+    }
+
+    /// Only apply in these months or seasons.
+    #[must_use]
+    pub fn with_months(mut self, months: impl Into<MonthSelector>) -> Self {
+        self.months = months.into();
+        self
+    }
+
+    /// Only apply on these dates.
+    #[must_use]
+    pub fn with_dates(mut self, dates: impl IntoIterator<Item = NaiveDate>) -> Self {
+        self.dates.extend(dates);
+        self
+    }
+
+    /// Only apply within these date ranges.
+    #[must_use]
+    pub fn with_date_ranges(mut self, ranges: impl IntoIterator<Item = DateRange>) -> Self {
+        self.date_ranges.extend(ranges);
+        self
+    }
+
+    /// Set the refresh rate (in seconds).
+    #[must_use]
+    pub fn with_refresh_rate(mut self, seconds: u32) -> Self {
+        self.refresh_rate = Some(seconds);
+        self
+    }
+
+    /// Set whether to align the refresh rate to wall-clock boundaries,
+    /// overriding the schedule's setting.
+    #[must_use]
+    pub fn with_align(mut self, align: bool) -> Self {
+        self.align = Some(align);
+        self
+    }
+
+    /// Show the named screen while the rule matches.
+    #[must_use]
+    pub fn with_screen(mut self, screen: impl Into<String>) -> Self {
+        self.screen = Some(screen.into());
+        self
+    }
+
+    /// Keep the current image while the rule matches (see
+    /// [`RefreshSchedule::should_update`]).
+    #[must_use]
+    pub fn with_no_refresh(mut self) -> Self {
+        self.no_refresh = true;
+        self
+    }
+
+    /// Set what the device does while the rule matches.
+    #[must_use]
+    pub fn with_mode(mut self, mode: RuleMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+impl From<&str> for DaySelector {
+    fn from(name: &str) -> Self {
+        Self::Named(name.to_string())
+    }
+}
+
+impl From<Vec<String>> for DaySelector {
+    fn from(days: Vec<String>) -> Self {
+        Self::List(days)
+    }
+}
+
+impl From<&str> for MonthSelector {
+    fn from(name: &str) -> Self {
+        Self::Named(name.to_string())
+    }
+}
+
+impl From<Vec<String>> for MonthSelector {
+    fn from(months: Vec<String>) -> Self {
+        Self::List(months)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_builder() {
+        let schedule = RefreshSchedule::builder()
+            .with_timezone(chrono_tz::Europe::Berlin)
+            .with_rule(ScheduleRule::weekdays("09:00", "18:00", 120))
+            .with_rule(ScheduleRule::daily("23:00", "06:00", 0).with_mode(RuleMode::Sleep))
+            .with_holiday(NaiveDate::from_ymd_opt(2025, 12, 25).unwrap())
+            .with_default_refresh_rate(300)
+            .build()
+            .unwrap();
+        assert_eq!(schedule.timezone, "Europe/Berlin");
+
+        let tz = chrono_tz::Europe::Berlin;
+        let monday = tz.with_ymd_and_hms(2025, 1, 6, 10, 0, 0).unwrap();
+        assert_eq!(schedule.get_refresh_rate_for_time(monday), 120);
+        let saturday = tz.with_ymd_and_hms(2025, 1, 4, 10, 0, 0).unwrap();
+        assert_eq!(schedule.get_refresh_rate_for_time(saturday), 300);
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        assert!(RefreshSchedule::builder()
+            .with_default_refresh_rate(300)
+            .build()
+            .is_err());
+        assert!(RefreshSchedule::builder()
+            .with_timezone(chrono_tz::UTC)
+            .build()
+            .is_err());
+
+        // Same check as the YAML path: rules need a way to get a rate
+        let rule = ScheduleRule {
+            refresh_rate: None,
+            ..ScheduleRule::weekdays("09:00", "18:00", 120)
+        };
+        assert!(RefreshSchedule::builder()
+            .with_timezone(chrono_tz::UTC)
+            .with_default_refresh_rate(300)
+            .with_rule(rule)
+            .build()
+            .is_err());
+    }
+}
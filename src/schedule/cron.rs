@@ -0,0 +1,285 @@
+//! Five-field cron expressions for schedule rules.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Deserializer};
+
+use crate::Error;
+
+/// A standard five-field cron expression: minute, hour, day of month,
+/// month and day of week.
+///
+/// Fields accept `*`, single values, ranges (`8-18`), lists (`1,15`) and
+/// steps (`*/5`, `8-18/2`). Months and days of week also accept names
+/// (`JAN`, `MON-FRI`); day of week 0 and 7 are both Sunday. As in most cron
+/// implementations, when both day of month and day of week are restricted,
+/// a day matching either one matches. The `@hourly`, `@daily`, `@weekly`,
+/// `@monthly` and `@yearly` shorthands are also accepted.
+///
+/// # Example
+///
+/// ```
+/// use trmnl::schedule::CronExpr;
+///
+/// let business_hours: CronExpr = "*/5 8-18 * * MON-FRI".parse().unwrap();
+/// assert_eq!(business_hours.to_string(), "*/5 8-18 * * MON-FRI");
+/// assert!("61 * * * *".parse::<CronExpr>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+/// How far ahead to look for the next run; long enough for `29 2` to
+/// match across a century year that isn't a leap year.
+const SEARCH_DAYS: i64 = 366 * 8;
+
+const MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const DAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl CronExpr {
+    /// Whether the expression fires at `t` (seconds are ignored).
+    pub fn matches(&self, t: NaiveDateTime) -> bool {
+        self.matches_hour(t) && bit(self.minutes, t.minute())
+    }
+
+    /// Whether `t` falls in an hour in which the expression fires,
+    /// ignoring the minute field.
+    ///
+    /// Rules use this as the window during which they apply, so
+    /// `*/5 8-18 * * MON-FRI` covers 08:00 to 19:00 on weekdays.
+    pub fn matches_hour(&self, t: NaiveDateTime) -> bool {
+        self.matches_date(t.date()) && bit(self.hours, t.hour())
+    }
+
+    /// The first time strictly after `t` at which the expression fires, or
+    /// `None` if it never does (e.g. `0 0 31 2 *`).
+    pub fn next_after(&self, t: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut next = t
+            .date()
+            .and_time(NaiveTime::from_hms_opt(t.hour(), t.minute(), 0)?)
+            + Duration::minutes(1);
+        let limit = next + Duration::days(SEARCH_DAYS);
+
+        while next < limit {
+            if !self.matches_date(next.date()) {
+                next = next.date().succ_opt()?.and_time(NaiveTime::MIN);
+            } else if !bit(self.hours, next.hour()) {
+                next = next.date().and_hms_opt(next.hour(), 0, 0)? + Duration::hours(1);
+            } else if !bit(self.minutes, next.minute()) {
+                next += Duration::minutes(1);
+            } else {
+                return Some(next);
+            }
+        }
+        None
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !bit(self.months, date.month()) {
+            return false;
+        }
+        let day_of_month = bit(self.days_of_month, date.day());
+        let day_of_week = bit(self.days_of_week, date.weekday().num_days_from_sunday());
+        if self.day_of_month_restricted && self.day_of_week_restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Parse one field into a bit mask of the values it covers.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        let lower = s.to_lowercase();
+        let n = match names.iter().position(|name| *name == lower) {
+            // Month names start at 1, day names at 0
+            Some(i) => i as u32 + min,
+            None => s.parse().map_err(|_| format!("invalid value '{}'", s))?,
+        };
+        if n < min || n > max {
+            return Err(format!("{} is out of range {}-{}", n, min, max));
+        }
+        Ok(n)
+    };
+
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("invalid step '{}'", step))?;
+                if step == 0 {
+                    return Err("step must be at least 1".to_string());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (value(start)?, value(end)?),
+            // `5/15` runs from 5 to the end of the range
+            None if step > 1 => (value(range)?, max),
+            None => {
+                let n = value(range)?;
+                (n, n)
+            }
+        };
+        if start > end {
+            return Err(format!("range {} is backwards", range));
+        }
+        for n in (start..=end).step_by(step as usize) {
+            mask |= 1 << n;
+        }
+    }
+    Ok(mask)
+}
+
+impl FromStr for CronExpr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let expanded = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(Error::Config(format!(
+                "Invalid cron expression '{}': expected 5 fields",
+                s
+            )));
+        };
+        let invalid = |field: &str, e: String| {
+            Error::Config(format!("Invalid cron expression '{}': {} {}", s, field, e))
+        };
+
+        let mut days_of_week =
+            parse_field(day_of_week, 0, 7, DAY_NAMES).map_err(|e| invalid("day of week", e))?;
+        // 7 is an alias for Sunday
+        if bit(days_of_week, 7) {
+            days_of_week |= 1;
+        }
+
+        Ok(Self {
+            source: s.trim().to_string(),
+            minutes: parse_field(minute, 0, 59, &[]).map_err(|e| invalid("minute", e))?,
+            hours: parse_field(hour, 0, 23, &[]).map_err(|e| invalid("hour", e))?,
+            days_of_month: parse_field(day_of_month, 1, 31, &[])
+                .map_err(|e| invalid("day of month", e))?,
+            months: parse_field(month, 1, 12, MONTH_NAMES).map_err(|e| invalid("month", e))?,
+            days_of_week,
+            day_of_month_restricted: !day_of_month.starts_with('*'),
+            day_of_week_restricted: !day_of_week.starts_with('*'),
+        })
+    }
+}
+
+impl fmt::Display for CronExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for CronExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let cron: CronExpr = "*/15 8-18/2 1,15 jan-mar mon-fri".parse().unwrap();
+        assert_eq!(cron.minutes, 1 | 1 << 15 | 1 << 30 | 1 << 45);
+        assert_eq!(cron.hours, (8..=18).step_by(2).fold(0, |m, h| m | 1 << h));
+        assert_eq!(cron.months, 0b1110);
+        assert_eq!(cron.days_of_week, 0b111110);
+
+        let sunday: CronExpr = "0 0 * * 7".parse().unwrap();
+        assert!(bit(sunday.days_of_week, 0));
+
+        for invalid in [
+            "* * * *",
+            "60 * * * *",
+            "* 5-2 * * *",
+            "*/0 * * * *",
+            "* * * foo *",
+        ] {
+            assert!(invalid.parse::<CronExpr>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let cron: CronExpr = "*/5 8-18 * * MON-FRI".parse().unwrap();
+        // 2025-01-06 is a Monday
+        assert!(cron.matches(at("2025-01-06 08:05:00")));
+        assert!(!cron.matches(at("2025-01-06 08:06:00")));
+        assert!(cron.matches_hour(at("2025-01-06 18:59:00")));
+        assert!(!cron.matches_hour(at("2025-01-06 19:00:00")));
+        assert!(!cron.matches_hour(at("2025-01-04 10:00:00")));
+
+        // Restricted day of month and day of week match either
+        let cron: CronExpr = "0 0 1 * MON".parse().unwrap();
+        assert!(cron.matches(at("2025-02-01 00:00:00"))); // Saturday the 1st
+        assert!(cron.matches(at("2025-02-03 00:00:00"))); // Monday the 3rd
+        assert!(!cron.matches(at("2025-02-04 00:00:00")));
+    }
+
+    #[test]
+    fn test_next_after() {
+        let cron: CronExpr = "*/5 8-18 * * MON-FRI".parse().unwrap();
+        assert_eq!(
+            cron.next_after(at("2025-01-06 10:02:30")),
+            Some(at("2025-01-06 10:05:00"))
+        );
+        assert_eq!(
+            cron.next_after(at("2025-01-06 10:05:00")),
+            Some(at("2025-01-06 10:10:00"))
+        );
+        // Friday evening skips to Monday morning
+        assert_eq!(
+            cron.next_after(at("2025-01-10 18:57:00")),
+            Some(at("2025-01-13 08:00:00"))
+        );
+
+        let leap: CronExpr = "0 12 29 2 *".parse().unwrap();
+        assert_eq!(
+            leap.next_after(at("2025-03-01 00:00:00")),
+            Some(at("2028-02-29 12:00:00"))
+        );
+        let never: CronExpr = "0 0 31 2 *".parse().unwrap();
+        assert_eq!(never.next_after(at("2025-01-01 00:00:00")), None);
+    }
+}
@@ -0,0 +1,1592 @@
+//! Refresh rate scheduling based on time of day and day of week.
+//!
+//! This module allows you to configure different refresh rates for different times,
+//! helping optimize battery life while keeping displays fresh when needed.
+//!
+//! # Example Schedule (YAML)
+//!
+//! ```yaml
+//! timezone: "America/New_York"
+//! default_refresh_rate: 300  # 5 minutes
+//!
+//! schedule:
+//!   # Sleep hours - very infrequent updates
+//!   - days: all
+//!     start: "23:00"
+//!     end: "06:00"
+//!     refresh_rate: 1800  # 30 minutes
+//!
+//!   # Or sleep through the whole window in one go
+//!   - days: weekends
+//!     start: "22:00"
+//!     end: "08:00"
+//!     mode: sleep
+//!
+//!   # Morning routine - frequent updates
+//!   - days: weekdays
+//!     start: "06:00"
+//!     end: "09:00"
+//!     refresh_rate: 60  # 1 minute
+//!
+//!   # Work hours - moderate updates
+//!   - days: weekdays
+//!     start: "09:00"
+//!     end: "18:00"
+//!     refresh_rate: 120  # 2 minutes
+//!
+//!   # Cron rules: wake every 5 minutes during weekday business hours...
+//!   - cron: "*/5 8-18 * * MON-FRI"
+//!
+//!   # ...and on the hour otherwise
+//!   - cron: "0 * * * *"
+//! ```
+//!
+//! Rules can also be limited to `dates` and `date_ranges`, and `days:
+//! holidays` matches the dates listed under `holidays`:
+//!
+//! ```yaml
+//! holidays:
+//!   - "2025-07-04"
+//!   - "2025-12-24..2026-01-02"
+//!
+//! schedule:
+//!   - days: holidays
+//!     refresh_rate: 3600
+//! ```
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use trmnl::schedule::RefreshSchedule;
+//!
+//! // Load schedule from YAML file
+//! let schedule = RefreshSchedule::load("config/schedule.yaml")?;
+//!
+//! // Get current refresh rate based on time
+//! let refresh_rate = schedule.get_refresh_rate();
+//!
+//! // Use in your display response
+//! DisplayResponse::new(url, filename).with_refresh_rate(refresh_rate)
+//! ```
+
+mod builder;
+mod cron;
+mod validate;
+
+pub use builder::ScheduleBuilder;
+pub use cron::CronExpr;
+pub use validate::{Diagnostic, Severity};
+
+use chrono::{
+    DateTime, Datelike, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset, Timelike, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{DeviceInfo, Error, MAX_REFRESH_RATE};
+
+/// A refresh rate schedule configuration.
+///
+/// Loads from YAML and provides time-based refresh rate lookup.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RefreshSchedule {
+    /// Timezone for interpreting times (e.g., "America/New_York")
+    pub timezone: String,
+    /// Default refresh rate if no rule matches (seconds)
+    pub default_refresh_rate: u32,
+    /// List of schedule rules (evaluated in order, first match wins)
+    pub schedule: Vec<ScheduleRule>,
+    /// Holiday calendar matched by `days: holidays`
+    #[serde(default)]
+    pub holidays: Vec<DateRange>,
+    /// Align fixed refresh rates to wall-clock boundaries (see
+    /// [`ScheduleRule::align`])
+    #[serde(default)]
+    pub align: bool,
+    /// Screen to show when the matching rule has none, or no rule matches
+    #[serde(default)]
+    pub default_screen: Option<String>,
+    /// Which occurrence of a local time repeated when clocks go back to
+    /// wake at
+    #[serde(default)]
+    pub ambiguous_time_policy: AmbiguousTimePolicy,
+    /// Longer refresh rates for devices running low on battery (see
+    /// [`get_refresh_rate_for_device`](Self::get_refresh_rate_for_device))
+    #[serde(default)]
+    pub battery_scaling: Vec<BatteryScaling>,
+}
+
+/// Multiply refresh rates while a device's battery is low.
+///
+/// ```yaml
+/// battery_scaling:
+///   - below: 20  # percent
+///     multiplier: 2
+///   - below: 10
+///     multiplier: 4
+/// ```
+///
+/// When several thresholds apply, the largest multiplier wins. Only fixed
+/// refresh rates are scaled; sleep and cron rules still wake the device
+/// on time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct BatteryScaling {
+    /// Battery percentage below which this applies
+    pub below: u8,
+    /// Factor to multiply the refresh rate by
+    pub multiplier: u32,
+}
+
+/// Which occurrence of a repeated local time to use when clocks go back.
+///
+/// Rules are written in wall-clock time, so a rule ending at `01:30` on the
+/// night clocks go back from 02:00 to 01:00 ends at one of two instants an
+/// hour apart. Local times skipped when clocks go forward wake the device
+/// when the clocks change (e.g. `02:30` becomes `03:00`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AmbiguousTimePolicy {
+    /// The occurrence with the same UTC offset as the current time, so a
+    /// window started before the change ends at the first occurrence and
+    /// one started after it at the second
+    #[default]
+    Default,
+    /// The first occurrence, in the earlier (summer) offset, unless it has
+    /// already passed
+    Earliest,
+    /// Always the second occurrence, in the later (winter) offset
+    Latest,
+}
+
+/// A single schedule rule.
+///
+/// A rule matches when all of its selectors do: `days`, `dates` or
+/// `date_ranges` (if any are given), the `start`-`end` window (the whole
+/// day if omitted) and the hours in which `cron` fires. Days and dates are
+/// those of the current local time, so an overnight window on a listed
+/// date doesn't extend past midnight.
+///
+/// Rules with a `cron` expression and no `refresh_rate` wake the device at
+/// the expression's next run, or when the rule stops matching if that is
+/// sooner, so that later rules take over on time. Rules with `mode: sleep`
+/// wake the device when they stop matching. Other rules need a
+/// `refresh_rate`. Computed rates are at most a day
+/// ([`MAX_REFRESH_RATE`]).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ScheduleRule {
+    /// Days this rule applies to
+    #[serde(default)]
+    pub days: DaySelector,
+    /// Months this rule applies to
+    #[serde(default)]
+    pub months: MonthSelector,
+    /// Dates this rule applies to (e.g. `2025-12-24`)
+    #[serde(default)]
+    pub dates: Vec<NaiveDate>,
+    /// Date ranges this rule applies to (e.g. `2025-12-24..2026-01-02`)
+    #[serde(default)]
+    pub date_ranges: Vec<DateRange>,
+    /// Start time (HH:MM, 24-hour format)
+    #[serde(default)]
+    pub start: Option<String>,
+    /// End time (HH:MM, 24-hour format)
+    #[serde(default)]
+    pub end: Option<String>,
+    /// Cron expression (e.g. `*/5 8-18 * * MON-FRI`)
+    #[serde(default)]
+    pub cron: Option<CronExpr>,
+    /// Refresh rate in seconds
+    #[serde(default)]
+    pub refresh_rate: Option<u32>,
+    /// What the device does while the rule matches
+    #[serde(default)]
+    pub mode: RuleMode,
+    /// Shorten the refresh rate so the device wakes on a multiple of it
+    /// since local midnight (e.g. exactly on the minute for a rate of 60,
+    /// or on the quarter hour for 900), so clock screens don't drift.
+    /// Defaults to the schedule's `align`
+    #[serde(default)]
+    pub align: Option<bool>,
+    /// Name of the screen or template to show while the rule matches (see
+    /// [`RefreshSchedule::get_screen_and_rate_for_time`])
+    #[serde(default)]
+    pub screen: Option<String>,
+    /// Keep the current image while the rule matches, only checking in
+    /// (see [`RefreshSchedule::should_update`])
+    #[serde(default)]
+    pub no_refresh: bool,
+}
+
+/// What the device does while a rule matches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleMode {
+    /// Poll at the rule's refresh rate
+    #[default]
+    Refresh,
+    /// Sleep until the rule stops matching (e.g. through the night)
+    Sleep,
+}
+
+/// Day selector for schedule rules.
+///
+/// Defaults to all days.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum DaySelector {
+    /// A specific list of days (e.g., ["mon", "tue", "wed"])
+    List(Vec<String>),
+    /// A named group: "all", "weekdays", "weekends", "holidays", or a
+    /// single day name
+    Named(String),
+}
+
+impl Default for DaySelector {
+    fn default() -> Self {
+        Self::Named("all".to_string())
+    }
+}
+
+/// Month selector for schedule rules.
+///
+/// Seasons are meteorological seasons in the northern hemisphere (e.g.
+/// summer is June to August); list months explicitly for the southern
+/// hemisphere. Defaults to all months.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum MonthSelector {
+    /// A list of months or seasons (e.g., ["jun", "jul", "aug"])
+    List(Vec<String>),
+    /// "all", a season ("spring", "summer", "autumn"/"fall", "winter"), or
+    /// a single month name
+    Named(String),
+}
+
+impl Default for MonthSelector {
+    fn default() -> Self {
+        Self::Named("all".to_string())
+    }
+}
+
+impl MonthSelector {
+    /// Whether `month` (1-12) is selected.
+    pub fn matches(&self, month: u32) -> bool {
+        let selected = |name: &str| months_from_str(name).is_some_and(|m| m & (1 << month) != 0);
+        match self {
+            Self::Named(name) => selected(name),
+            Self::List(names) => names.iter().any(|name| selected(name)),
+        }
+    }
+
+    fn is_all(&self) -> bool {
+        matches!(self, Self::Named(name) if name.eq_ignore_ascii_case("all"))
+    }
+}
+
+/// An inclusive range of dates, written `2025-12-24..2026-01-02`, or a
+/// single date.
+///
+/// # Example
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use trmnl::schedule::DateRange;
+///
+/// let range: DateRange = "2025-12-24..2026-01-02".parse().unwrap();
+/// assert!(range.contains(NaiveDate::from_ymd_opt(2026, 1, 2).unwrap()));
+/// assert!(!range.contains(NaiveDate::from_ymd_opt(2026, 1, 3).unwrap()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    /// First date in the range
+    pub start: NaiveDate,
+    /// Last date in the range (inclusive)
+    pub end: NaiveDate,
+}
+
+impl DateRange {
+    /// Whether `date` falls in the range.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+impl From<NaiveDate> for DateRange {
+    fn from(date: NaiveDate) -> Self {
+        Self {
+            start: date,
+            end: date,
+        }
+    }
+}
+
+impl FromStr for DateRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let date = |d: &str| {
+            d.trim()
+                .parse::<NaiveDate>()
+                .map_err(|e| Error::Config(format!("Invalid date '{}': {}", d.trim(), e)))
+        };
+        let range = match s.split_once("..") {
+            Some((start, end)) => Self {
+                start: date(start)?,
+                end: date(end)?,
+            },
+            None => date(s)?.into(),
+        };
+        if range.start > range.end {
+            return Err(Error::Config(format!("Date range '{}' is backwards", s)));
+        }
+        Ok(range)
+    }
+}
+
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}..{}", self.start, self.end)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DateRange {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl RefreshSchedule {
+    /// Load schedule from a YAML file.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let schedule = RefreshSchedule::load("config/schedule.yaml")?;
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            Error::Config(format!(
+                "Failed to read schedule file '{}': {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        Self::from_yaml(&content)
+    }
+
+    /// Parse schedule from a YAML string.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let yaml = r#"
+    /// timezone: "UTC"
+    /// default_refresh_rate: 300
+    /// schedule: []
+    /// "#;
+    /// let schedule = RefreshSchedule::from_yaml(yaml)?;
+    /// ```
+    pub fn from_yaml(yaml: &str) -> Result<Self, Error> {
+        let schedule: Self = serde_yaml::from_str(yaml)
+            .map_err(|e| Error::Config(format!("Invalid schedule YAML: {}", e)))?;
+        schedule.check()?;
+        Ok(schedule)
+    }
+
+    /// Start building a schedule in code.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::schedule::{RefreshSchedule, ScheduleRule};
+    ///
+    /// let schedule = RefreshSchedule::builder()
+    ///     .with_timezone(chrono_tz::America::New_York)
+    ///     .with_rule(ScheduleRule::weekdays("09:00", "18:00", 120))
+    ///     .with_default_refresh_rate(300)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(schedule.schedule.len(), 1);
+    /// ```
+    pub fn builder() -> ScheduleBuilder {
+        ScheduleBuilder::default()
+    }
+
+    /// Run [`validate`](Self::validate) at load time, failing on errors
+    /// and logging warnings.
+    fn check(&self) -> Result<(), Error> {
+        let mut errors = Vec::new();
+        for diagnostic in self.validate() {
+            match diagnostic.severity {
+                Severity::Error => errors.push(diagnostic.to_string()),
+                Severity::Warning => tracing::warn!("Schedule {}", diagnostic),
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Config(format!(
+                "Invalid schedule: {}",
+                errors.join("; ")
+            )))
+        }
+    }
+
+    /// Get the refresh rate for the current time.
+    ///
+    /// Evaluates rules in order and returns the first match,
+    /// or `default_refresh_rate` if no rules match.
+    pub fn get_refresh_rate(&self) -> u32 {
+        self.get_refresh_rate_for_time(self.now())
+    }
+
+    /// The current time in the schedule's timezone.
+    pub(crate) fn now(&self) -> DateTime<Tz> {
+        let tz: Tz = self
+            .timezone
+            .parse()
+            .unwrap_or(chrono_tz::America::New_York);
+        Utc::now().with_timezone(&tz)
+    }
+
+    /// Get the refresh rate for a specific time.
+    ///
+    /// Useful for testing or for pre-calculating schedules.
+    ///
+    /// Rates that wake the device at a local time (sleep and cron rules)
+    /// are measured in real time, so they stay correct across DST changes;
+    /// see [`AmbiguousTimePolicy`].
+    pub fn get_refresh_rate_for_time<T: chrono::TimeZone>(&self, dt: DateTime<T>) -> u32 {
+        self.lookup(&dt, None).1
+    }
+
+    /// Get the refresh rate for `device` at the current time, scaled by
+    /// `battery_scaling` for its battery level.
+    ///
+    /// Devices that don't report a battery voltage get the unscaled rate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::schedule::RefreshSchedule;
+    /// use trmnl::DeviceInfo;
+    ///
+    /// let schedule = RefreshSchedule::from_yaml(
+    ///     r#"
+    /// timezone: "UTC"
+    /// default_refresh_rate: 300
+    /// schedule: []
+    /// battery_scaling:
+    ///   - below: 20
+    ///     multiplier: 2
+    ///   - below: 10
+    ///     multiplier: 4
+    /// "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let device = DeviceInfo::new("AA:BB:CC:DD:EE:FF");
+    /// assert_eq!(schedule.get_refresh_rate_for_device(&device.clone().with_battery_voltage(4.0)), 300);
+    /// assert_eq!(schedule.get_refresh_rate_for_device(&device.clone().with_battery_voltage(3.2)), 600);
+    /// assert_eq!(schedule.get_refresh_rate_for_device(&device.with_battery_voltage(3.05)), 1200);
+    /// ```
+    pub fn get_refresh_rate_for_device(&self, device: &DeviceInfo) -> u32 {
+        self.get_refresh_rate_for_device_at(device, self.now())
+    }
+
+    /// Get the refresh rate for `device` at a specific time, scaled for its
+    /// battery level.
+    pub fn get_refresh_rate_for_device_at<T: chrono::TimeZone>(
+        &self,
+        device: &DeviceInfo,
+        dt: DateTime<T>,
+    ) -> u32 {
+        self.lookup(&dt, device.battery_percentage()).1
+    }
+
+    /// Get the screen to show and the refresh rate for the current time.
+    pub fn get_screen_and_rate(&self) -> (Option<&str>, u32) {
+        self.get_screen_and_rate_for_time(self.now())
+    }
+
+    /// Get the screen to show and the refresh rate for a specific time.
+    ///
+    /// The screen is the matching rule's `screen`, or `default_screen` if
+    /// the rule has none or no rule matches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use trmnl::schedule::RefreshSchedule;
+    ///
+    /// let schedule = RefreshSchedule::from_yaml(
+    ///     r#"
+    /// timezone: "UTC"
+    /// default_refresh_rate: 1800
+    /// default_screen: photo-frame
+    /// schedule:
+    ///   - start: "06:00"
+    ///     end: "09:00"
+    ///     refresh_rate: 300
+    ///     screen: news-briefing
+    /// "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let morning = Utc.with_ymd_and_hms(2025, 1, 6, 7, 0, 0).unwrap();
+    /// assert_eq!(
+    ///     schedule.get_screen_and_rate_for_time(morning),
+    ///     (Some("news-briefing"), 300)
+    /// );
+    /// let night = Utc.with_ymd_and_hms(2025, 1, 6, 22, 0, 0).unwrap();
+    /// assert_eq!(
+    ///     schedule.get_screen_and_rate_for_time(night),
+    ///     (Some("photo-frame"), 1800)
+    /// );
+    /// ```
+    pub fn get_screen_and_rate_for_time<T: chrono::TimeZone>(
+        &self,
+        dt: DateTime<T>,
+    ) -> (Option<&str>, u32) {
+        let (rule, refresh_rate) = self.lookup(&dt, None);
+        let screen = rule
+            .and_then(|i| self.schedule[i].screen.as_deref())
+            .or(self.default_screen.as_deref());
+        (screen, refresh_rate)
+    }
+
+    /// Whether the device should get new content at `now`.
+    ///
+    /// Returns `false` while a `no_refresh` rule matches, e.g. for a
+    /// bedroom screen that should still check in at night but not visibly
+    /// flash. Respond with [`DisplayResponse::unchanged`](crate::DisplayResponse::unchanged)
+    /// in that case.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use trmnl::schedule::RefreshSchedule;
+    ///
+    /// let schedule = RefreshSchedule::from_yaml(
+    ///     r#"
+    /// timezone: "UTC"
+    /// default_refresh_rate: 300
+    /// schedule:
+    ///   - start: "22:00"
+    ///     end: "07:00"
+    ///     refresh_rate: 3600
+    ///     no_refresh: true
+    /// "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert!(!schedule.should_update(Utc.with_ymd_and_hms(2025, 1, 6, 23, 0, 0).unwrap()));
+    /// assert!(schedule.should_update(Utc.with_ymd_and_hms(2025, 1, 6, 12, 0, 0).unwrap()));
+    /// ```
+    pub fn should_update<T: chrono::TimeZone>(&self, now: DateTime<T>) -> bool {
+        let (rule, _) = self.lookup(&now, None);
+        !rule.is_some_and(|i| self.schedule[i].no_refresh)
+    }
+
+    /// Evaluate the schedule every `step` from `from` until (not including)
+    /// `to`, yielding the time, refresh rate and index of the matching rule
+    /// (`None` for the default).
+    ///
+    /// Use this to check a schedule in tests, or print it as a table,
+    /// before deploying it to battery-powered devices. A `step` that isn't
+    /// positive yields only `from`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::{Duration, TimeZone};
+    /// use trmnl::schedule::RefreshSchedule;
+    ///
+    /// let schedule = RefreshSchedule::from_yaml(
+    ///     r#"
+    /// timezone: "Europe/London"
+    /// default_refresh_rate: 300
+    /// schedule:
+    ///   - start: "23:00"
+    ///     end: "06:00"
+    ///     refresh_rate: 1800
+    /// "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let tz = chrono_tz::Europe::London;
+    /// let from = tz.with_ymd_and_hms(2025, 1, 6, 0, 0, 0).unwrap();
+    /// for (at, refresh_rate, rule) in schedule.simulate(from, from + Duration::days(1), Duration::hours(1)) {
+    ///     println!("{}  {:>5}s  {:?}", at.format("%H:%M"), refresh_rate, rule);
+    /// }
+    ///
+    /// let rates: Vec<u32> = schedule
+    ///     .simulate(from, from + Duration::days(1), Duration::hours(6))
+    ///     .map(|(_, refresh_rate, _)| refresh_rate)
+    ///     .collect();
+    /// assert_eq!(rates, [1800, 300, 300, 300]);
+    /// ```
+    pub fn simulate<'a, T: chrono::TimeZone + 'a>(
+        &'a self,
+        from: DateTime<T>,
+        to: DateTime<T>,
+        step: chrono::Duration,
+    ) -> impl Iterator<Item = (DateTime<T>, u32, Option<usize>)> + 'a {
+        let first = (from < to).then_some(from);
+        std::iter::successors(first, move |at| {
+            let next = at.clone() + step;
+            (step > chrono::Duration::zero() && next < to).then_some(next)
+        })
+        .map(|at| {
+            let (rule, refresh_rate) = self.lookup(&at, None);
+            (at, refresh_rate, rule)
+        })
+    }
+
+    /// Index of the first rule matching `now`, and the refresh rate for a
+    /// device at `battery` percent.
+    fn lookup<T: chrono::TimeZone>(
+        &self,
+        now: &DateTime<T>,
+        battery: Option<u8>,
+    ) -> (Option<usize>, u32) {
+        let local = now.naive_local();
+        for (i, rule) in self.schedule.iter().enumerate() {
+            if let Some(wake) = rule.wake_at(local, &self.holidays) {
+                let refresh_rate = match wake {
+                    // Computed rates already wake at the right time
+                    Wake::At(wake) => self.seconds_until(now, wake),
+                    Wake::After(refresh_rate) => {
+                        let refresh_rate = self.scale_for_battery(refresh_rate, battery);
+                        if rule.align.unwrap_or(self.align) {
+                            align_refresh_rate(local, refresh_rate)
+                        } else {
+                            refresh_rate
+                        }
+                    }
+                };
+                tracing::debug!(
+                    "Schedule rule matched: {:?} refresh_rate={}",
+                    rule,
+                    refresh_rate
+                );
+                return (Some(i), refresh_rate);
+            }
+        }
+
+        tracing::debug!(
+            "No schedule rule matched, using default: {}",
+            self.default_refresh_rate
+        );
+        let refresh_rate = self.scale_for_battery(self.default_refresh_rate, battery);
+        let refresh_rate = if self.align {
+            align_refresh_rate(local, refresh_rate)
+        } else {
+            refresh_rate
+        };
+        (None, refresh_rate)
+    }
+
+    /// Multiply a fixed refresh rate by the largest `battery_scaling`
+    /// multiplier whose threshold `battery` is below, up to a day.
+    fn scale_for_battery(&self, refresh_rate: u32, battery: Option<u8>) -> u32 {
+        let Some(battery) = battery else {
+            return refresh_rate;
+        };
+        let multiplier = self
+            .battery_scaling
+            .iter()
+            .filter(|scaling| battery < scaling.below)
+            .map(|scaling| scaling.multiplier)
+            .max()
+            .unwrap_or(1);
+        if multiplier <= 1 {
+            return refresh_rate;
+        }
+        refresh_rate
+            .saturating_mul(multiplier)
+            .min(MAX_REFRESH_RATE)
+    }
+
+    /// When the matching rule next changes, and the refresh rate from then.
+    ///
+    /// Use this to keep a device from sleeping past a rule boundary, e.g. by
+    /// capping the refresh rate at the time until the transition. Looks up
+    /// to a week ahead at minute resolution, and returns `None` if the same
+    /// rule (or the default) applies throughout. Rules that give the same
+    /// rate still count as a transition.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use trmnl::schedule::RefreshSchedule;
+    ///
+    /// let schedule = RefreshSchedule::from_yaml(
+    ///     r#"
+    /// timezone: "UTC"
+    /// default_refresh_rate: 300
+    /// schedule:
+    ///   - start: "09:00"
+    ///     end: "17:00"
+    ///     refresh_rate: 60
+    /// "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let now = Utc.with_ymd_and_hms(2025, 1, 6, 8, 30, 0).unwrap();
+    /// let (at, refresh_rate) = schedule.next_transition(now).unwrap();
+    /// assert_eq!(at, Utc.with_ymd_and_hms(2025, 1, 6, 9, 0, 0).unwrap());
+    /// assert_eq!(refresh_rate, 60);
+    /// ```
+    pub fn next_transition<T: chrono::TimeZone>(
+        &self,
+        now: DateTime<T>,
+    ) -> Option<(DateTime<T>, u32)> {
+        let tz = now.timezone();
+        let start = now.naive_local();
+        let current = self.matching_rule(start);
+        let limit = start + chrono::Duration::days(7);
+
+        let mut t = start.with_second(0)?.with_nanosecond(0)?;
+        loop {
+            t += chrono::Duration::minutes(1);
+            if t > limit {
+                return None;
+            }
+            if self.matching_rule(t) == current {
+                continue;
+            }
+            // Local times skipped by a DST change don't exist; the
+            // transition happens at the first one that does
+            if let Some(at) = self.resolve(tz.from_local_datetime(&t), &now) {
+                let refresh_rate = self.get_refresh_rate_for_time(at.clone());
+                return Some((at, refresh_rate));
+            }
+        }
+    }
+
+    /// Seconds from `now` until local time `wake`, at most a day.
+    fn seconds_until<T: chrono::TimeZone>(&self, now: &DateTime<T>, wake: NaiveDateTime) -> u32 {
+        let tz = now.timezone();
+        let mut local = wake;
+        // Local times skipped by a DST change wake the device when the
+        // clocks change; gaps are at most a few hours
+        let at = loop {
+            if let Some(at) = self.resolve(tz.from_local_datetime(&local), now) {
+                break at;
+            }
+            if local - wake > chrono::Duration::days(1) {
+                return MAX_REFRESH_RATE;
+            }
+            local += chrono::Duration::minutes(1);
+        };
+        let seconds = (at - now.clone()).num_seconds();
+        seconds.clamp(1, i64::from(MAX_REFRESH_RATE)) as u32
+    }
+
+    /// Pick an instant for a local time according to
+    /// `ambiguous_time_policy`, or `None` if the local time doesn't exist.
+    fn resolve<T: chrono::TimeZone>(
+        &self,
+        local: LocalResult<DateTime<T>>,
+        now: &DateTime<T>,
+    ) -> Option<DateTime<T>> {
+        match local {
+            LocalResult::None => None,
+            LocalResult::Single(t) => Some(t),
+            // The first occurrence is no use once it has passed
+            LocalResult::Ambiguous(earliest, latest) if earliest <= *now => Some(latest),
+            LocalResult::Ambiguous(earliest, latest) => Some(match self.ambiguous_time_policy {
+                AmbiguousTimePolicy::Earliest => earliest,
+                AmbiguousTimePolicy::Latest => latest,
+                AmbiguousTimePolicy::Default => {
+                    if latest.offset().fix() == now.offset().fix() {
+                        latest
+                    } else {
+                        earliest
+                    }
+                }
+            }),
+        }
+    }
+
+    /// Index of the first rule matching local time `t`.
+    fn matching_rule(&self, t: NaiveDateTime) -> Option<usize> {
+        self.schedule
+            .iter()
+            .position(|rule| rule.active_at(t, &self.holidays))
+    }
+}
+
+/// When a matching rule wakes the device.
+enum Wake {
+    /// After a fixed refresh rate (in seconds)
+    After(u32),
+    /// At a local time
+    At(NaiveDateTime),
+}
+
+impl ScheduleRule {
+    /// When this rule wakes the device from local time `t`, if it matches.
+    fn wake_at(&self, t: NaiveDateTime, holidays: &[DateRange]) -> Option<Wake> {
+        if !self.active_at(t, holidays) {
+            return None;
+        }
+        let horizon = t + chrono::Duration::seconds(i64::from(MAX_REFRESH_RATE));
+        match (self.mode, &self.cron, self.refresh_rate) {
+            (RuleMode::Sleep, _, _) => Some(Wake::At(self.active_until(t, holidays, horizon))),
+            (RuleMode::Refresh, Some(cron), None) => {
+                let next = cron.next_after(t).map_or(horizon, |next| next.min(horizon));
+                // Wake when this rule stops matching if that comes first
+                Some(Wake::At(self.active_until(t, holidays, next)))
+            }
+            (RuleMode::Refresh, _, refresh_rate) => refresh_rate.map(Wake::After),
+        }
+    }
+
+    /// Whether all of this rule's selectors match local time `t`.
+    fn active_at(&self, t: NaiveDateTime, holidays: &[DateRange]) -> bool {
+        self.matches_at(t, holidays) && self.cron.as_ref().map_or(true, |c| c.matches_hour(t))
+    }
+
+    /// The first minute after `t` at which this rule stops matching, or
+    /// `limit` if it matches until then.
+    fn active_until(
+        &self,
+        t: NaiveDateTime,
+        holidays: &[DateRange],
+        limit: NaiveDateTime,
+    ) -> NaiveDateTime {
+        let Some(mut next) = t.with_second(0).and_then(|t| t.with_nanosecond(0)) else {
+            return limit;
+        };
+        next += chrono::Duration::minutes(1);
+        while next < limit {
+            if !self.active_at(next, holidays) {
+                return next;
+            }
+            next += chrono::Duration::minutes(1);
+        }
+        limit
+    }
+
+    /// Check the day, date and time selectors against local time `t`.
+    fn matches_at(&self, t: NaiveDateTime, holidays: &[DateRange]) -> bool {
+        let time = NaiveTime::from_hms_opt(t.hour(), t.minute(), 0).unwrap_or_default();
+        self.date_matches(t.date(), holidays) && self.matches(t.weekday(), time)
+    }
+
+    /// Check the `dates` and `date_ranges` selectors, and `days: holidays`.
+    fn date_matches(&self, date: NaiveDate, holidays: &[DateRange]) -> bool {
+        if matches!(&self.days, DaySelector::Named(name) if name.eq_ignore_ascii_case("holidays"))
+            && !holidays.iter().any(|h| h.contains(date))
+        {
+            return false;
+        }
+        if !self.months.matches(date.month()) {
+            return false;
+        }
+        if self.dates.is_empty() && self.date_ranges.is_empty() {
+            return true;
+        }
+        self.dates.contains(&date) || self.date_ranges.iter().any(|r| r.contains(date))
+    }
+
+    /// Check if this rule matches the given day and time.
+    ///
+    /// Only `days` and the `start`-`end` window are checked here.
+    fn matches(&self, weekday: Weekday, time: NaiveTime) -> bool {
+        // Check if the day matches
+        if !self.day_matches(weekday) {
+            return false;
+        }
+
+        let (start, end) = match (&self.start, &self.end) {
+            (None, None) => return true,
+            (start, end) => (
+                start.as_deref().and_then(parse_time),
+                end.as_deref().and_then(parse_time),
+            ),
+        };
+
+        match (start, end) {
+            (Some(s), Some(e)) => {
+                if s <= e {
+                    // Normal range (e.g., 09:00 - 17:00)
+                    time >= s && time < e
+                } else {
+                    // Overnight range (e.g., 23:00 - 06:00)
+                    time >= s || time < e
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Check if this rule applies to the given weekday.
+    fn day_matches(&self, weekday: Weekday) -> bool {
+        match &self.days {
+            DaySelector::Named(name) => match name.to_lowercase().as_str() {
+                "all" => true,
+                "weekdays" => matches!(
+                    weekday,
+                    Weekday::Mon | Weekday::Tue | Weekday::Wed | Weekday::Thu | Weekday::Fri
+                ),
+                "weekends" => matches!(weekday, Weekday::Sat | Weekday::Sun),
+                // Checked against the holiday calendar by date_matches
+                "holidays" => true,
+                _ => {
+                    // Single day name
+                    weekday_from_str(name) == Some(weekday)
+                }
+            },
+            DaySelector::List(days) => days.iter().any(|d| weekday_from_str(d) == Some(weekday)),
+        }
+    }
+}
+
+/// Seconds until the next multiple of `refresh_rate` since local midnight.
+fn align_refresh_rate(t: NaiveDateTime, refresh_rate: u32) -> u32 {
+    if refresh_rate == 0 {
+        return 0;
+    }
+    refresh_rate - t.num_seconds_from_midnight() % refresh_rate
+}
+
+/// Parse a time string (HH:MM) into NaiveTime.
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() != 2 {
+        return None;
+    }
+    let hour: u32 = parts[0].parse().ok()?;
+    let minute: u32 = parts[1].parse().ok()?;
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// Convert a day name to Weekday.
+fn weekday_from_str(s: &str) -> Option<Weekday> {
+    match s.to_lowercase().as_str() {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Convert a month name, season or "all" to a bit mask of months (bit 1 is
+/// January).
+fn months_from_str(s: &str) -> Option<u16> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    let mask = |months: &[u16]| months.iter().fold(0, |mask, m| mask | 1 << m);
+    let s = s.to_lowercase();
+    match s.as_str() {
+        "all" => Some(mask(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12])),
+        "spring" => Some(mask(&[3, 4, 5])),
+        "summer" => Some(mask(&[6, 7, 8])),
+        "autumn" | "fall" => Some(mask(&[9, 10, 11])),
+        "winter" => Some(mask(&[12, 1, 2])),
+        // Full names or abbreviations of at least three letters
+        _ if s.len() >= 3 => {
+            let month = MONTHS.iter().position(|m| m.starts_with(s.as_str()))?;
+            Some(1 << (month + 1))
+        }
+        _ => None,
+    }
+}
+
+// =============================================================================
+// Global Schedule (optional convenience pattern)
+// =============================================================================
+
+use std::sync::{Arc, RwLock};
+
+/// Global schedule instance; replaced on reload.
+static SCHEDULE: RwLock<Option<Arc<RefreshSchedule>>> = RwLock::new(None);
+
+/// Initialize the global schedule from a file.
+///
+/// Call this at application startup, and again to reload the file. If the
+/// file doesn't exist or is invalid, a warning is logged and the previous
+/// schedule is kept (the default rate is used if there is none).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// trmnl::schedule::init_global_schedule("config/schedule.yaml");
+///
+/// // Later, in handlers:
+/// let rate = trmnl::schedule::get_global_refresh_rate();
+/// ```
+pub fn init_global_schedule(path: &str) {
+    match RefreshSchedule::load(path) {
+        Ok(s) => {
+            tracing::info!(
+                "Loaded TRMNL schedule with {} rules, default={}s",
+                s.schedule.len(),
+                s.default_refresh_rate
+            );
+            *SCHEDULE.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(s));
+        }
+        Err(e) => {
+            tracing::warn!("Failed to load TRMNL schedule: {}", e);
+        }
+    }
+}
+
+/// Replace the global schedule.
+///
+/// # Errors
+///
+/// Returns `Error::Config` if [`RefreshSchedule::validate`] finds errors;
+/// the previous schedule is kept.
+pub fn set_global_schedule(schedule: RefreshSchedule) -> Result<(), Error> {
+    schedule.check()?;
+    *SCHEDULE.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(schedule));
+    Ok(())
+}
+
+/// Modify the global schedule in place, e.g. to change a rule at runtime.
+///
+/// # Errors
+///
+/// Returns `Error::Config` if no schedule is loaded or the modified
+/// schedule is invalid; the previous schedule is kept.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// trmnl::schedule::update_global_schedule(|schedule| {
+///     schedule.default_refresh_rate = 600;
+/// })?;
+/// ```
+pub fn update_global_schedule(f: impl FnOnce(&mut RefreshSchedule)) -> Result<(), Error> {
+    let mut global = SCHEDULE.write().unwrap_or_else(|e| e.into_inner());
+    let current = global
+        .as_deref()
+        .ok_or_else(|| Error::Config("No global schedule loaded".to_string()))?;
+    let mut updated = current.clone();
+    f(&mut updated);
+    updated.check()?;
+    *global = Some(Arc::new(updated));
+    Ok(())
+}
+
+/// Remove the global schedule, so the default rate is used.
+pub fn clear_global_schedule() {
+    *SCHEDULE.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// The current global schedule, if one is loaded.
+pub fn global_schedule() -> Option<Arc<RefreshSchedule>> {
+    SCHEDULE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Get the current refresh rate based on the global schedule.
+///
+/// Returns 60 seconds if no schedule is loaded.
+pub fn get_global_refresh_rate() -> u32 {
+    const DEFAULT_REFRESH_RATE: u32 = 60;
+
+    match global_schedule() {
+        Some(schedule) => schedule.get_refresh_rate(),
+        None => DEFAULT_REFRESH_RATE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("09:00"), NaiveTime::from_hms_opt(9, 0, 0));
+        assert_eq!(parse_time("23:30"), NaiveTime::from_hms_opt(23, 30, 0));
+        assert_eq!(parse_time("invalid"), None);
+        assert_eq!(parse_time("12"), None);
+    }
+
+    #[test]
+    fn test_weekday_from_str() {
+        assert_eq!(weekday_from_str("mon"), Some(Weekday::Mon));
+        assert_eq!(weekday_from_str("Monday"), Some(Weekday::Mon));
+        assert_eq!(weekday_from_str("MON"), Some(Weekday::Mon));
+        assert_eq!(weekday_from_str("sat"), Some(Weekday::Sat));
+        assert_eq!(weekday_from_str("invalid"), None);
+    }
+
+    #[test]
+    fn test_schedule_rule_day_match_named() {
+        let rule = ScheduleRule {
+            days: DaySelector::Named("weekdays".to_string()),
+            start: Some("09:00".to_string()),
+            end: Some("17:00".to_string()),
+            refresh_rate: Some(60),
+            ..Default::default()
+        };
+        assert!(rule.day_matches(Weekday::Mon));
+        assert!(rule.day_matches(Weekday::Fri));
+        assert!(!rule.day_matches(Weekday::Sat));
+        assert!(!rule.day_matches(Weekday::Sun));
+    }
+
+    #[test]
+    fn test_schedule_rule_day_match_list() {
+        let rule = ScheduleRule {
+            days: DaySelector::List(vec![
+                "mon".to_string(),
+                "wed".to_string(),
+                "fri".to_string(),
+            ]),
+            start: Some("09:00".to_string()),
+            end: Some("17:00".to_string()),
+            refresh_rate: Some(60),
+            ..Default::default()
+        };
+        assert!(rule.day_matches(Weekday::Mon));
+        assert!(rule.day_matches(Weekday::Wed));
+        assert!(rule.day_matches(Weekday::Fri));
+        assert!(!rule.day_matches(Weekday::Tue));
+        assert!(!rule.day_matches(Weekday::Sat));
+    }
+
+    #[test]
+    fn test_schedule_rule_time_match() {
+        let rule = ScheduleRule {
+            days: DaySelector::Named("all".to_string()),
+            start: Some("09:00".to_string()),
+            end: Some("17:00".to_string()),
+            refresh_rate: Some(60),
+            ..Default::default()
+        };
+        let time_10am = NaiveTime::from_hms_opt(10, 0, 0).unwrap();
+        let time_8am = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+        let time_6pm = NaiveTime::from_hms_opt(18, 0, 0).unwrap();
+        let time_5pm = NaiveTime::from_hms_opt(17, 0, 0).unwrap();
+
+        assert!(rule.matches(Weekday::Mon, time_10am));
+        assert!(!rule.matches(Weekday::Mon, time_8am));
+        assert!(!rule.matches(Weekday::Mon, time_6pm));
+        assert!(!rule.matches(Weekday::Mon, time_5pm)); // End is exclusive
+    }
+
+    #[test]
+    fn test_overnight_rule() {
+        let rule = ScheduleRule {
+            days: DaySelector::Named("all".to_string()),
+            start: Some("23:00".to_string()),
+            end: Some("06:00".to_string()),
+            refresh_rate: Some(1800),
+            ..Default::default()
+        };
+        let time_midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+        let time_3am = NaiveTime::from_hms_opt(3, 0, 0).unwrap();
+        let time_11pm = NaiveTime::from_hms_opt(23, 30, 0).unwrap();
+        let time_noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+
+        assert!(rule.matches(Weekday::Mon, time_midnight));
+        assert!(rule.matches(Weekday::Mon, time_3am));
+        assert!(rule.matches(Weekday::Mon, time_11pm));
+        assert!(!rule.matches(Weekday::Mon, time_noon));
+    }
+
+    #[test]
+    fn test_from_yaml() {
+        let yaml = r#"
+timezone: "America/New_York"
+default_refresh_rate: 300
+schedule:
+  - days: weekdays
+    start: "09:00"
+    end: "17:00"
+    refresh_rate: 60
+  - days: all
+    start: "23:00"
+    end: "06:00"
+    refresh_rate: 1800
+"#;
+        let schedule = RefreshSchedule::from_yaml(yaml).unwrap();
+        assert_eq!(schedule.timezone, "America/New_York");
+        assert_eq!(schedule.default_refresh_rate, 300);
+        assert_eq!(schedule.schedule.len(), 2);
+        assert_eq!(schedule.schedule[0].refresh_rate, Some(60));
+        assert_eq!(schedule.schedule[1].refresh_rate, Some(1800));
+    }
+
+    #[test]
+    fn test_empty_schedule_returns_default() {
+        let yaml = r#"
+timezone: "UTC"
+default_refresh_rate: 300
+schedule: []
+"#;
+        let schedule = RefreshSchedule::from_yaml(yaml).unwrap();
+        assert_eq!(schedule.get_refresh_rate(), 300);
+    }
+
+    #[test]
+    fn test_cron_rules() {
+        let yaml = r#"
+timezone: "UTC"
+default_refresh_rate: 300
+schedule:
+  - cron: "*/5 8-18 * * MON-FRI"
+  - days: weekends
+    cron: "0 9-17 * * *"
+    refresh_rate: 900
+  - cron: "0 * * * *"
+"#;
+        let schedule = RefreshSchedule::from_yaml(yaml).unwrap();
+        let at = |s: &str| {
+            let t = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+            schedule.get_refresh_rate_for_time(t.and_utc())
+        };
+
+        // 2025-01-06 is a Monday: every 5 minutes during business hours
+        assert_eq!(at("2025-01-06 10:02:30"), 150);
+        // Near the end of the window, wake when the hourly rule takes over
+        assert_eq!(at("2025-01-06 18:57:00"), 180);
+        // Hourly otherwise
+        assert_eq!(at("2025-01-06 19:00:00"), 3600);
+        assert_eq!(at("2025-01-06 07:30:00"), 1800);
+        // Fixed rate within a cron window
+        assert_eq!(at("2025-01-04 12:00:00"), 900);
+        assert_eq!(at("2025-01-04 18:10:00"), 3000);
+
+        let missing_rate = "timezone: UTC\ndefault_refresh_rate: 300\nschedule:\n  - days: all\n";
+        assert!(RefreshSchedule::from_yaml(missing_rate).is_err());
+    }
+
+    #[test]
+    fn test_date_and_holiday_rules() {
+        let yaml = r#"
+timezone: "UTC"
+default_refresh_rate: 300
+holidays:
+  - "2025-07-04"
+  - "2025-12-24..2026-01-02"
+schedule:
+  - days: holidays
+    refresh_rate: 3600
+  - dates: ["2025-11-28"]
+    refresh_rate: 1800
+  - date_ranges: ["2025-08-01..2025-08-15"]
+    days: weekdays
+    refresh_rate: 900
+  - days: weekdays
+    refresh_rate: 120
+"#;
+        let schedule = RefreshSchedule::from_yaml(yaml).unwrap();
+        let at = |s: &str| {
+            let date: NaiveDate = s.parse().unwrap();
+            schedule.get_refresh_rate_for_time(date.and_hms_opt(12, 0, 0).unwrap().and_utc())
+        };
+
+        assert_eq!(at("2025-07-04"), 3600);
+        assert_eq!(at("2025-12-31"), 3600);
+        assert_eq!(at("2026-01-02"), 3600);
+        assert_eq!(at("2026-01-05"), 120);
+        assert_eq!(at("2025-11-28"), 1800);
+        assert_eq!(at("2025-08-04"), 900);
+        // Weekend inside the range
+        assert_eq!(at("2025-08-09"), 300);
+
+        assert!("2026-01-02..2025-12-24".parse::<DateRange>().is_err());
+        assert!("2025-13-01".parse::<DateRange>().is_err());
+    }
+
+    #[test]
+    fn test_sleep_rules() {
+        let yaml = r#"
+timezone: "UTC"
+default_refresh_rate: 300
+schedule:
+  - start: "23:00"
+    end: "06:30"
+    mode: sleep
+  - days: weekends
+    mode: sleep
+"#;
+        let schedule = RefreshSchedule::from_yaml(yaml).unwrap();
+        let at = |s: &str| {
+            let t = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+            schedule.get_refresh_rate_for_time(t.and_utc())
+        };
+
+        // 2025-01-06 is a Monday: sleep until the window ends
+        assert_eq!(at("2025-01-06 23:00:00"), 7 * 3600 + 1800);
+        assert_eq!(at("2025-01-07 06:29:30"), 30);
+        assert_eq!(at("2025-01-07 12:00:00"), 300);
+        // Whole-weekend sleep is capped at a day
+        assert_eq!(at("2025-01-04 12:00:00"), MAX_REFRESH_RATE);
+        assert_eq!(at("2025-01-05 12:00:00"), 12 * 3600);
+    }
+
+    #[test]
+    fn test_next_transition() {
+        use chrono::TimeZone;
+
+        let yaml = r#"
+timezone: "America/New_York"
+default_refresh_rate: 300
+schedule:
+  - days: weekdays
+    start: "09:00"
+    end: "17:00"
+    refresh_rate: 60
+  - start: "02:30"
+    end: "03:00"
+    refresh_rate: 900
+"#;
+        let schedule = RefreshSchedule::from_yaml(yaml).unwrap();
+        let tz = chrono_tz::America::New_York;
+
+        // Friday afternoon: work hours end, then the next rule is at 02:30
+        let now = tz.with_ymd_and_hms(2025, 1, 10, 16, 0, 0).unwrap();
+        let (at, rate) = schedule.next_transition(now).unwrap();
+        assert_eq!(at, tz.with_ymd_and_hms(2025, 1, 10, 17, 0, 0).unwrap());
+        assert_eq!(rate, 300);
+        let (at, rate) = schedule.next_transition(at).unwrap();
+        assert_eq!(at, tz.with_ymd_and_hms(2025, 1, 11, 2, 30, 0).unwrap());
+        assert_eq!(rate, 900);
+
+        // 02:30 doesn't exist on 2025-03-09; the rule never applies that night
+        let now = tz.with_ymd_and_hms(2025, 3, 9, 0, 0, 0).unwrap();
+        let (at, rate) = schedule.next_transition(now).unwrap();
+        assert_eq!(at, tz.with_ymd_and_hms(2025, 3, 10, 2, 30, 0).unwrap());
+        assert_eq!(rate, 900);
+
+        let empty =
+            RefreshSchedule::from_yaml("timezone: UTC\ndefault_refresh_rate: 300\nschedule: []")
+                .unwrap();
+        assert!(empty.next_transition(now).is_none());
+    }
+
+    #[test]
+    fn test_align() {
+        let yaml = r#"
+timezone: "UTC"
+default_refresh_rate: 60
+align: true
+schedule:
+  - start: "09:00"
+    end: "10:00"
+    refresh_rate: 900
+  - start: "10:00"
+    end: "11:00"
+    refresh_rate: 900
+    align: false
+"#;
+        let schedule = RefreshSchedule::from_yaml(yaml).unwrap();
+        let at = |s: &str| {
+            let t = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+            schedule.get_refresh_rate_for_time(t.and_utc())
+        };
+
+        assert_eq!(at("2025-01-06 08:02:37"), 23);
+        assert_eq!(at("2025-01-06 08:03:00"), 60);
+        assert_eq!(at("2025-01-06 09:02:00"), 780);
+        assert_eq!(at("2025-01-06 10:02:00"), 900);
+    }
+
+    #[test]
+    fn test_dst() {
+        let sleep = |start: &str, end: &str| {
+            let yaml = format!(
+                "timezone: America/New_York\ndefault_refresh_rate: 300\nschedule:\n  \
+                 - start: \"{}\"\n    end: \"{}\"\n    mode: sleep",
+                start, end
+            );
+            RefreshSchedule::from_yaml(&yaml).unwrap()
+        };
+        let tz = chrono_tz::America::New_York;
+
+        // Overnight sleep is 8 hours when clocks go back, and 6 when they
+        // go forward
+        let overnight = sleep("23:00", "06:00");
+        let fall = tz.with_ymd_and_hms(2025, 11, 1, 23, 0, 0).unwrap();
+        assert_eq!(overnight.get_refresh_rate_for_time(fall), 8 * 3600);
+        let spring = tz.with_ymd_and_hms(2025, 3, 8, 23, 0, 0).unwrap();
+        assert_eq!(overnight.get_refresh_rate_for_time(spring), 6 * 3600);
+
+        // 01:30 happens twice on 2025-11-02
+        let mut schedule = sleep("00:00", "01:30");
+        let midnight = tz.with_ymd_and_hms(2025, 11, 2, 0, 0, 0).unwrap();
+        assert_eq!(schedule.get_refresh_rate_for_time(midnight), 5400);
+        let second = tz
+            .from_local_datetime(&midnight.naive_local().with_hour(1).unwrap())
+            .latest()
+            .unwrap();
+        assert_eq!(schedule.get_refresh_rate_for_time(second), 1800);
+        schedule.ambiguous_time_policy = AmbiguousTimePolicy::Latest;
+        assert_eq!(schedule.get_refresh_rate_for_time(midnight), 9000);
+        // The first occurrence has passed by the second
+        schedule.ambiguous_time_policy = AmbiguousTimePolicy::Earliest;
+        assert_eq!(schedule.get_refresh_rate_for_time(midnight), 5400);
+        assert_eq!(schedule.get_refresh_rate_for_time(second), 1800);
+
+        // 02:30 doesn't exist on 2025-03-09, so wake at 03:00 EDT
+        let schedule = sleep("01:00", "02:30");
+        let one = tz.with_ymd_and_hms(2025, 3, 9, 1, 0, 0).unwrap();
+        assert_eq!(schedule.get_refresh_rate_for_time(one), 3600);
+    }
+
+    #[test]
+    fn test_battery_scaling() {
+        let yaml = r#"
+timezone: "UTC"
+default_refresh_rate: 300
+align: true
+battery_scaling:
+  - below: 10
+    multiplier: 4
+  - below: 20
+    multiplier: 2
+schedule:
+  - start: "23:00"
+    end: "06:00"
+    mode: sleep
+"#;
+        let schedule = RefreshSchedule::from_yaml(yaml).unwrap();
+        let device = DeviceInfo::new("AA:BB:CC:DD:EE:FF");
+        let low = device.clone().with_battery_voltage(3.05);
+        let at = |device: &DeviceInfo, s: &str| {
+            let t = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+            schedule.get_refresh_rate_for_device_at(device, t.and_utc())
+        };
+
+        // Scaled before aligning; the largest multiplier wins
+        assert_eq!(at(&device, "2025-01-06 12:00:00"), 300);
+        assert_eq!(at(&low, "2025-01-06 12:00:00"), 1200);
+        assert_eq!(at(&low, "2025-01-06 12:05:00"), 900);
+        // Devices without a voltage, and sleep rules, aren't scaled
+        assert_eq!(at(&device, "2025-01-06 05:00:00"), 3600);
+        assert_eq!(at(&low, "2025-01-06 05:00:00"), 3600);
+
+        let invalid = yaml.replace("multiplier: 2", "multiplier: 0");
+        assert!(RefreshSchedule::from_yaml(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_global_schedule() {
+        let yaml = "timezone: UTC\ndefault_refresh_rate: 300\nschedule: []";
+        set_global_schedule(RefreshSchedule::from_yaml(yaml).unwrap()).unwrap();
+        assert_eq!(get_global_refresh_rate(), 300);
+
+        update_global_schedule(|schedule| schedule.default_refresh_rate = 600).unwrap();
+        assert_eq!(get_global_refresh_rate(), 600);
+
+        // Invalid updates and failed reloads keep the current schedule
+        let invalid = update_global_schedule(|schedule| schedule.timezone = "Nowhere".to_string());
+        assert!(invalid.is_err());
+        init_global_schedule("/nonexistent/schedule.yaml");
+        assert_eq!(global_schedule().unwrap().timezone, "UTC");
+        assert_eq!(get_global_refresh_rate(), 600);
+
+        clear_global_schedule();
+        assert_eq!(get_global_refresh_rate(), 60);
+        assert!(update_global_schedule(|_| {}).is_err());
+    }
+
+    #[test]
+    fn test_month_selectors() {
+        assert_eq!(months_from_str("jun"), Some(1 << 6));
+        assert_eq!(months_from_str("September"), Some(1 << 9));
+        assert_eq!(months_from_str("winter"), Some(1 << 12 | 1 << 1 | 1 << 2));
+        assert_eq!(months_from_str("ju"), None);
+        assert_eq!(months_from_str("junly"), None);
+
+        let yaml = r#"
+timezone: "UTC"
+default_refresh_rate: 300
+schedule:
+  - months: summer
+    days: weekdays
+    refresh_rate: 1800
+  - months: [dec, jan]
+    refresh_rate: 600
+"#;
+        let schedule = RefreshSchedule::from_yaml(yaml).unwrap();
+        let at = |s: &str| {
+            let date: NaiveDate = s.parse().unwrap();
+            schedule.get_refresh_rate_for_time(date.and_hms_opt(12, 0, 0).unwrap().and_utc())
+        };
+        assert_eq!(at("2025-07-15"), 1800);
+        assert_eq!(at("2025-09-15"), 300);
+        assert_eq!(at("2025-12-15"), 600);
+    }
+
+    #[test]
+    fn test_simulate() {
+        use chrono::TimeZone;
+
+        let yaml = r#"
+timezone: "UTC"
+default_refresh_rate: 300
+schedule:
+  - days: weekdays
+    start: "09:00"
+    end: "17:00"
+    refresh_rate: 60
+  - start: "23:00"
+    end: "06:00"
+    refresh_rate: 1800
+"#;
+        let schedule = RefreshSchedule::from_yaml(yaml).unwrap();
+        let from = chrono::Utc.with_ymd_and_hms(2025, 1, 6, 0, 0, 0).unwrap();
+        let to = from + chrono::Duration::days(1);
+
+        let steps: Vec<_> = schedule
+            .simulate(from, to, chrono::Duration::hours(1))
+            .collect();
+        assert_eq!(steps.len(), 24);
+        assert_eq!(steps[0], (from, 1800, Some(1)));
+        assert_eq!(steps[8].1, 300);
+        assert_eq!(steps[8].2, None);
+        assert_eq!(steps[9].2, Some(0));
+        assert_eq!(steps[23].0, from + chrono::Duration::hours(23));
+
+        assert_eq!(
+            schedule
+                .simulate(to, from, chrono::Duration::hours(1))
+                .count(),
+            0
+        );
+        assert_eq!(
+            schedule
+                .simulate(from, to, chrono::Duration::zero())
+                .count(),
+            1
+        );
+    }
+}
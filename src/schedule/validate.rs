@@ -0,0 +1,322 @@
+//! Load-time checks for schedules.
+
+use std::fmt;
+
+use chrono::{NaiveTime, Weekday};
+use chrono_tz::Tz;
+
+use super::{
+    months_from_str, parse_time, weekday_from_str, DaySelector, MonthSelector, RefreshSchedule,
+    RuleMode, ScheduleRule,
+};
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The schedule can't be used; loading fails
+    Error,
+    /// The schedule works but probably not as intended
+    Warning,
+}
+
+/// A problem found by [`RefreshSchedule::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Whether loading fails because of this
+    pub severity: Severity,
+    /// Index of the rule the problem is in, if any
+    pub rule: Option<usize>,
+    /// What is wrong
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(rule: Option<usize>, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            rule,
+            message,
+        }
+    }
+
+    fn warning(rule: Option<usize>, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            rule,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rule {
+            // Rules are numbered from 1 for people editing the YAML
+            Some(i) => write!(f, "rule {}: {}", i + 1, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+const DAY_GROUPS: &[&str] = &["all", "weekdays", "weekends", "holidays"];
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+impl RefreshSchedule {
+    /// Check the schedule for mistakes that would otherwise only show up as
+    /// rules that silently never match.
+    ///
+    /// Errors are an unknown timezone, a `battery_scaling` multiplier of 0,
+    /// unparsable times or day names, a `start` without an `end` (or the
+    /// reverse), and rules with no way to get a refresh rate. Warnings are
+    /// rules that can never match: empty time windows, `days: holidays`
+    /// without a holiday calendar, and rules shadowed by an earlier rule that
+    /// matches whenever they do.
+    ///
+    /// [`from_yaml`](Self::from_yaml) and [`ScheduleBuilder::build`](super::ScheduleBuilder::build)
+    /// run this, failing on errors and logging warnings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use trmnl::schedule::{RefreshSchedule, ScheduleRule, Severity};
+    ///
+    /// let schedule = RefreshSchedule {
+    ///     timezone: "UTC".to_string(),
+    ///     default_refresh_rate: 300,
+    ///     schedule: vec![
+    ///         ScheduleRule::daily("00:00", "23:59", 600),
+    ///         ScheduleRule::weekdays("25:99", "18:00", 120),
+    ///     ],
+    ///     holidays: vec![],
+    ///     align: false,
+    ///     default_screen: None,
+    ///     ambiguous_time_policy: Default::default(),
+    ///     battery_scaling: vec![],
+    /// };
+    /// let diagnostics = schedule.validate();
+    /// assert_eq!(diagnostics[0].severity, Severity::Error);
+    /// assert_eq!(diagnostics[0].to_string(), "rule 2: invalid start time '25:99'");
+    /// ```
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        if self.timezone.parse::<Tz>().is_err() {
+            diagnostics.push(Diagnostic::error(
+                None,
+                format!("unknown timezone '{}'", self.timezone),
+            ));
+        }
+
+        for scaling in &self.battery_scaling {
+            if scaling.multiplier == 0 {
+                diagnostics.push(Diagnostic::error(
+                    None,
+                    format!(
+                        "battery_scaling below {}: multiplier must be at least 1",
+                        scaling.below
+                    ),
+                ));
+            }
+        }
+
+        for (i, rule) in self.schedule.iter().enumerate() {
+            let errors = rule_errors(rule);
+            let valid = errors.is_empty();
+            diagnostics.extend(errors.into_iter().map(|e| Diagnostic::error(Some(i), e)));
+            if !valid {
+                continue;
+            }
+
+            if is_holidays(&rule.days) && self.holidays.is_empty() {
+                diagnostics.push(Diagnostic::warning(
+                    Some(i),
+                    "days: holidays never matches without a holiday calendar".to_string(),
+                ));
+            } else if !matches_any_time(rule) {
+                diagnostics.push(Diagnostic::warning(
+                    Some(i),
+                    "time window is empty".to_string(),
+                ));
+            } else if let Some(earlier) = self.schedule[..i]
+                .iter()
+                .position(|earlier| rule_errors(earlier).is_empty() && shadows(earlier, rule))
+            {
+                diagnostics.push(Diagnostic::warning(
+                    Some(i),
+                    format!(
+                        "never reached: rule {} matches whenever it does",
+                        earlier + 1
+                    ),
+                ));
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Problems that make a rule unusable.
+fn rule_errors(rule: &ScheduleRule) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    let days: Vec<&str> = match &rule.days {
+        DaySelector::Named(name) => vec![name],
+        DaySelector::List(days) => days.iter().map(String::as_str).collect(),
+    };
+    for day in days {
+        let known = weekday_from_str(day).is_some()
+            || (matches!(rule.days, DaySelector::Named(_))
+                && DAY_GROUPS.contains(&day.to_lowercase().as_str()));
+        if !known {
+            errors.push(format!("unknown day '{}'", day));
+        }
+    }
+
+    let months: Vec<&str> = match &rule.months {
+        MonthSelector::Named(name) => vec![name],
+        MonthSelector::List(months) => months.iter().map(String::as_str).collect(),
+    };
+    for month in months {
+        if months_from_str(month).is_none() {
+            errors.push(format!("unknown month '{}'", month));
+        }
+    }
+
+    for (name, time) in [("start", &rule.start), ("end", &rule.end)] {
+        if let Some(time) = time {
+            if parse_time(time).is_none() {
+                errors.push(format!("invalid {} time '{}'", name, time));
+            }
+        }
+    }
+    match (&rule.start, &rule.end) {
+        (Some(_), None) => errors.push("start without end".to_string()),
+        (None, Some(_)) => errors.push("end without start".to_string()),
+        _ => {}
+    }
+
+    if rule.mode == RuleMode::Refresh && rule.refresh_rate.is_none() && rule.cron.is_none() {
+        errors.push("needs a refresh_rate, cron expression or mode: sleep".to_string());
+    }
+    errors
+}
+
+fn is_holidays(days: &DaySelector) -> bool {
+    matches!(days, DaySelector::Named(name) if name.eq_ignore_ascii_case("holidays"))
+}
+
+/// Every minute of the week, as checked by [`ScheduleRule::matches`].
+fn minutes_of_week() -> impl Iterator<Item = (Weekday, NaiveTime)> {
+    WEEKDAYS.into_iter().flat_map(|day| {
+        (0..24 * 60).filter_map(move |m| Some((day, NaiveTime::from_hms_opt(m / 60, m % 60, 0)?)))
+    })
+}
+
+fn matches_any_time(rule: &ScheduleRule) -> bool {
+    minutes_of_week().any(|(day, time)| rule.matches(day, time))
+}
+
+/// Whether `earlier` matches at every time `later` does.
+///
+/// Only rules without date, month or cron selectors, which can match on any
+/// date, are considered able to shadow others.
+fn shadows(earlier: &ScheduleRule, later: &ScheduleRule) -> bool {
+    if earlier.cron.is_some()
+        || !earlier.months.is_all()
+        || !earlier.dates.is_empty()
+        || !earlier.date_ranges.is_empty()
+        || is_holidays(&earlier.days)
+    {
+        return false;
+    }
+    minutes_of_week().all(|(day, time)| !later.matches(day, time) || earlier.matches(day, time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(yaml: &str) -> Vec<String> {
+        let schedule: RefreshSchedule = serde_yaml::from_str(yaml).unwrap();
+        schedule
+            .validate()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_errors() {
+        let yaml = r#"
+timezone: "Mars/Olympus_Mons"
+default_refresh_rate: 300
+schedule:
+  - days: [mon, funday]
+    start: "09:00"
+    end: "25:99"
+    refresh_rate: 60
+  - days: weekdayz
+    start: "09:00"
+    refresh_rate: 60
+  - months: [jun, smarch]
+    refresh_rate: 60
+"#;
+        assert_eq!(
+            messages(yaml),
+            [
+                "unknown timezone 'Mars/Olympus_Mons'",
+                "rule 1: unknown day 'funday'",
+                "rule 1: invalid end time '25:99'",
+                "rule 2: unknown day 'weekdayz'",
+                "rule 2: start without end",
+                "rule 3: unknown month 'smarch'",
+            ]
+        );
+        assert!(RefreshSchedule::from_yaml(yaml).is_err());
+    }
+
+    #[test]
+    fn test_warnings() {
+        let yaml = r#"
+timezone: "UTC"
+default_refresh_rate: 300
+schedule:
+  - days: weekdays
+    start: "08:00"
+    end: "18:00"
+    refresh_rate: 60
+  - days: [mon, tue]
+    start: "09:00"
+    end: "17:00"
+    refresh_rate: 120
+  - days: weekends
+    start: "09:00"
+    end: "09:00"
+    refresh_rate: 600
+  - days: holidays
+    refresh_rate: 3600
+  - days: [mon]
+    start: "17:00"
+    end: "19:00"
+    refresh_rate: 120
+"#;
+        assert_eq!(
+            messages(yaml),
+            [
+                "rule 2: never reached: rule 1 matches whenever it does",
+                "rule 3: time window is empty",
+                "rule 4: days: holidays never matches without a holiday calendar",
+            ]
+        );
+        // Warnings don't stop the schedule from loading
+        assert!(RefreshSchedule::from_yaml(yaml).is_ok());
+    }
+}